@@ -0,0 +1,247 @@
+// Copyright 2024-2025 Golem Cloud
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Parsing of the `#[wit(...)]` attributes of the `IntoValue` and `FromValueAndType` derives
+
+use heck::{
+    ToKebabCase, ToLowerCamelCase, ToPascalCase, ToShoutyKebabCase, ToShoutySnakeCase, ToSnakeCase,
+};
+use syn::ext::IdentExt;
+use syn::meta::ParseNestedMeta;
+use syn::punctuated::Punctuated;
+use syn::{Attribute, Ident, LitStr, Token, WherePredicate};
+
+#[derive(Default)]
+pub struct ContainerAttrs {
+    pub rename: Option<String>,
+    pub rename_all: Option<RenameRule>,
+    pub owner: Option<String>,
+    /// Set by `#[wit(transparent)]` or the `#[flatten_value]` attribute
+    pub transparent: bool,
+    /// Represents a struct of `bool` fields by WIT flags, set by `#[wit(flags)]`
+    pub flags: bool,
+    /// Represents a `bitflags` type by WIT flags, set by `#[wit(bitflags)]`
+    pub bitflags: bool,
+    /// Replaces the inferred `IntoValue` bounds of the type parameters
+    pub into_value_bound: Option<Vec<WherePredicate>>,
+    /// Replaces the inferred `FromValueAndType` bounds of the type parameters
+    pub from_value_and_type_bound: Option<Vec<WherePredicate>>,
+}
+
+impl ContainerAttrs {
+    pub fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut result = Self {
+            transparent: attrs
+                .iter()
+                .any(|attr| attr.path().is_ident("flatten_value")),
+            ..Default::default()
+        };
+        parse_wit_attrs(attrs, |meta| {
+            if meta.path.is_ident("rename") {
+                result.rename = Some(string_value(&meta)?);
+            } else if meta.path.is_ident("rename_all") {
+                result.rename_all = Some(RenameRule::parse(&meta)?);
+            } else if meta.path.is_ident("owner") {
+                result.owner = Some(string_value(&meta)?);
+            } else if meta.path.is_ident("transparent") {
+                result.transparent = true;
+            } else if meta.path.is_ident("flags") {
+                result.flags = true;
+            } else if meta.path.is_ident("bitflags") {
+                result.bitflags = true;
+            } else if meta.path.is_ident("bound") {
+                if meta.input.peek(Token![=]) {
+                    let bound = where_predicates(&meta)?;
+                    result.into_value_bound = Some(bound.clone());
+                    result.from_value_and_type_bound = Some(bound);
+                } else {
+                    meta.parse_nested_meta(|meta| {
+                        if meta.path.is_ident("into_value") {
+                            result.into_value_bound = Some(where_predicates(&meta)?);
+                        } else if meta.path.is_ident("from_value_and_type") {
+                            result.from_value_and_type_bound = Some(where_predicates(&meta)?);
+                        } else {
+                            return Err(meta.error("unsupported bound attribute"));
+                        }
+                        Ok(())
+                    })?;
+                }
+            } else {
+                return Err(meta.error("unsupported container attribute"));
+            }
+            Ok(())
+        })?;
+        Ok(result)
+    }
+
+    /// Name of the type, the Rust identifier unless renamed
+    pub fn type_name(&self, ident: &Ident) -> String {
+        self.rename
+            .clone()
+            .unwrap_or_else(|| ident.unraw().to_string())
+    }
+}
+
+#[derive(Default)]
+pub struct VariantAttrs {
+    pub rename: Option<String>,
+    /// Applies to the fields of a struct variant
+    pub rename_all: Option<RenameRule>,
+    /// Name of the record or tuple type of the case's fields
+    pub type_name: Option<String>,
+    /// Set by `#[wit(unit_case)]` or the `#[unit_case]` attribute
+    pub unit_case: bool,
+}
+
+impl VariantAttrs {
+    pub fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut result = Self {
+            unit_case: attrs.iter().any(|attr| attr.path().is_ident("unit_case")),
+            ..Default::default()
+        };
+        parse_wit_attrs(attrs, |meta| {
+            if meta.path.is_ident("rename") {
+                result.rename = Some(string_value(&meta)?);
+            } else if meta.path.is_ident("rename_all") {
+                result.rename_all = Some(RenameRule::parse(&meta)?);
+            } else if meta.path.is_ident("type_name") {
+                result.type_name = Some(string_value(&meta)?);
+            } else if meta.path.is_ident("unit_case") {
+                result.unit_case = true;
+            } else {
+                return Err(meta.error("unsupported variant attribute"));
+            }
+            Ok(())
+        })?;
+        Ok(result)
+    }
+}
+
+#[derive(Default)]
+pub struct FieldAttrs {
+    pub rename: Option<String>,
+    /// Not part of the value, set to its default when extracted
+    pub skip: bool,
+    /// Used when the field is missing from the extracted value
+    pub default: Option<FieldDefault>,
+}
+
+impl FieldAttrs {
+    pub fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut result = Self::default();
+        parse_wit_attrs(attrs, |meta| {
+            if meta.path.is_ident("rename") {
+                result.rename = Some(string_value(&meta)?);
+            } else if meta.path.is_ident("skip") {
+                result.skip = true;
+            } else if meta.path.is_ident("default") {
+                result.default = Some(if meta.input.peek(Token![=]) {
+                    FieldDefault::Function(meta.value()?.parse::<LitStr>()?.parse()?)
+                } else {
+                    FieldDefault::Default
+                });
+            } else {
+                return Err(meta.error("unsupported field attribute"));
+            }
+            Ok(())
+        })?;
+        Ok(result)
+    }
+}
+
+pub enum FieldDefault {
+    /// `Default::default()`
+    Default,
+    /// A function with no arguments, given by its path
+    Function(syn::ExprPath),
+}
+
+/// Case conversion of `rename_all`, with the same names as in serde
+#[derive(Clone, Copy)]
+pub enum RenameRule {
+    Lower,
+    Upper,
+    Pascal,
+    Camel,
+    Snake,
+    ScreamingSnake,
+    Kebab,
+    ScreamingKebab,
+}
+
+impl RenameRule {
+    fn parse(meta: &ParseNestedMeta) -> syn::Result<Self> {
+        let value = meta.value()?.parse::<LitStr>()?;
+        match value.value().as_str() {
+            "lowercase" => Ok(RenameRule::Lower),
+            "UPPERCASE" => Ok(RenameRule::Upper),
+            "PascalCase" => Ok(RenameRule::Pascal),
+            "camelCase" => Ok(RenameRule::Camel),
+            "snake_case" => Ok(RenameRule::Snake),
+            "SCREAMING_SNAKE_CASE" => Ok(RenameRule::ScreamingSnake),
+            "kebab-case" => Ok(RenameRule::Kebab),
+            "SCREAMING-KEBAB-CASE" => Ok(RenameRule::ScreamingKebab),
+            _ => Err(syn::Error::new(value.span(), "unknown rename rule")),
+        }
+    }
+
+    pub fn apply(&self, name: &str) -> String {
+        match self {
+            RenameRule::Lower => name.to_lowercase(),
+            RenameRule::Upper => name.to_uppercase(),
+            RenameRule::Pascal => name.to_pascal_case(),
+            RenameRule::Camel => name.to_lower_camel_case(),
+            RenameRule::Snake => name.to_snake_case(),
+            RenameRule::ScreamingSnake => name.to_shouty_snake_case(),
+            RenameRule::Kebab => name.to_kebab_case(),
+            RenameRule::ScreamingKebab => name.to_shouty_kebab_case(),
+        }
+    }
+}
+
+/// Name of a field or case: the explicit rename, or the identifier converted by the rename rule,
+/// which defaults to kebab-case
+pub fn wit_name(ident: &Ident, rename: &Option<String>, rename_all: Option<RenameRule>) -> String {
+    match rename {
+        Some(rename) => rename.clone(),
+        None => rename_all
+            .unwrap_or(RenameRule::Kebab)
+            .apply(&ident.unraw().to_string()),
+    }
+}
+
+fn parse_wit_attrs(
+    attrs: &[Attribute],
+    mut f: impl FnMut(ParseNestedMeta) -> syn::Result<()>,
+) -> syn::Result<()> {
+    for attr in attrs {
+        if attr.path().is_ident("wit") {
+            attr.parse_nested_meta(&mut f)?;
+        }
+    }
+    Ok(())
+}
+
+fn string_value(meta: &ParseNestedMeta) -> syn::Result<String> {
+    Ok(meta.value()?.parse::<LitStr>()?.value())
+}
+
+/// Parses a string of comma-separated where predicates, such as `"T: Clone, U: Default"`
+fn where_predicates(meta: &ParseNestedMeta) -> syn::Result<Vec<WherePredicate>> {
+    let predicates = meta
+        .value()?
+        .parse::<LitStr>()?
+        .parse_with(Punctuated::<WherePredicate, Token![,]>::parse_terminated)?;
+    Ok(predicates.into_iter().collect())
+}
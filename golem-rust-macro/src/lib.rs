@@ -16,20 +16,51 @@ use proc_macro::TokenStream;
 
 use crate::transaction::golem_operation_impl;
 
+mod attrs;
 mod transaction;
 mod value;
 
-#[proc_macro_derive(IntoValue, attributes(flatten_value, unit_case))]
+/// Derives `IntoValue`, mapping structs to records or tuples and enums to enums or variants
+///
+/// Names are kebab-case by default, and can be customized by `#[wit(...)]` attributes:
+/// - on the type: `rename = "..."` for the type name, `rename_all = "..."` for the field or case
+///   names (using the serde rule names such as `"snake_case"`), `owner = "..."` for the type
+///   owner, `transparent` to represent a single field struct by its field, and `bound = "..."`
+///   to replace the bounds required from the type parameters (by default, all of them have to
+///   implement the derived trait). `bound(into_value = "...", from_value_and_type = "...")`
+///   sets them separately for the two derives.
+/// - on enum cases: `rename = "..."`, `rename_all = "..."` for the fields of a struct case,
+///   `type_name = "..."` for the name of the record or tuple type of the case's fields, which is
+///   `Parent.Case` by default, and `unit_case` to represent the case without its value
+/// - on fields: `rename = "..."`, `skip` to leave the field out of the value, and `default` or
+///   `default = "path"` to use the default when the field is missing on extraction
+///
+/// Structs of `bool` fields marked with `#[wit(flags)]` and types defined by the `bitflags!` macro
+/// marked with `#[wit(bitflags)]` are represented by WIT flags instead. The latter requires the
+/// `bitflags` feature of `golem-rust`.
+///
+/// The fields of enum cases with named fields, or with multiple unnamed ones, are represented by
+/// named records or tuples, owned by the owner of the enum.
+///
+/// Named types without type parameters are added to a `WitType` only once, and refer to their
+/// node when repeated, so their types can also be recursive. Values of recursive types cannot be
+/// built yet, only extracted by `FromValueAndType`.
+///
+/// The same attributes are used by the `FromValueAndType` derive.
+#[proc_macro_derive(IntoValue, attributes(flatten_value, unit_case, wit))]
 pub fn derive_into_value(input: TokenStream) -> TokenStream {
     value::derive_into_value(input)
 }
 
-#[proc_macro_derive(FromValueAndType, attributes(flatten_value, unit_case))]
+#[proc_macro_derive(FromValueAndType, attributes(flatten_value, unit_case, wit))]
 pub fn derive_from_value_and_type(input: TokenStream) -> TokenStream {
     value::derive_from_value_and_type(input)
 }
 
 /// Defines a function as an `Operation` that can be used in transactions
+///
+/// If the annotated function is `async`, it defines an `AsyncOperation` instead, to be used in
+/// asynchronous transactions. In this case the compensation function has to be `async` as well.
 #[proc_macro_attribute]
 pub fn golem_operation(attr: TokenStream, item: TokenStream) -> TokenStream {
     golem_operation_impl(attr, item)
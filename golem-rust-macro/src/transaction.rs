@@ -62,7 +62,10 @@ pub fn golem_operation_impl(args: TokenStream, item: TokenStream) -> TokenStream
     let input_args: Vec<proc_macro2::TokenStream> =
         input_names.iter().map(|name| quote! { #name }).collect();
 
+    let is_async = fnsig.asyncness.is_some();
+
     let compensate = match &compensation {
+        Some(_) if is_async => quote! { golem_rust::call_async_compensation_function },
         Some(_) => quote! { golem_rust::call_compensation_function },
         None => quote! {},
     };
@@ -70,7 +73,11 @@ pub fn golem_operation_impl(args: TokenStream, item: TokenStream) -> TokenStream
     let compensation_pattern = quote! { #input_pattern, op_result: #succ };
     let compensation_args = input_args.clone();
 
-    let operation = quote! { operation };
+    let (operation, transaction) = if is_async {
+        (quote! { async_operation }, quote! { AsyncTransaction })
+    } else {
+        (quote! { operation }, quote! { Transaction })
+    };
 
     fnsig.inputs.insert(
         0,
@@ -89,6 +96,36 @@ pub fn golem_operation_impl(args: TokenStream, item: TokenStream) -> TokenStream
     let fnname = fnsig.ident.clone();
     let traitname = Ident::new(&fnname.to_string().to_pascal_case(), fnsig.ident.span());
 
+    let execute = if is_async {
+        quote! {
+            self.execute(
+                golem_rust::#operation(
+                    |#input_pattern| {
+                        #fnname(#(#input_args), *)
+                    },
+                    |#compensation_pattern| async move {
+                        #compensate(#compensation, (op_result,), (#(#compensation_args), *,)).await.map_err(|err| err.0)
+                    }
+                ),
+                (#(#input_args), *)
+            ).await
+        }
+    } else {
+        quote! {
+            self.execute(
+                golem_rust::#operation(
+                    |#input_pattern| {
+                        #fnname(#(#input_args), *)
+                    },
+                    |#compensation_pattern| {
+                        #compensate(#compensation, (op_result,), (#(#compensation_args), *,)).map_err(|err| err.0)
+                    }
+                ),
+                (#(#input_args), *)
+            )
+        }
+    };
+
     let result = quote! {
         #ast
 
@@ -96,19 +133,9 @@ pub fn golem_operation_impl(args: TokenStream, item: TokenStream) -> TokenStream
             #fnsig;
         }
 
-        impl<T: golem_rust::Transaction<#err>> #traitname for &mut T {
+        impl<T: golem_rust::#transaction<#err>> #traitname for &mut T {
             #fnsig {
-                self.execute(
-                    golem_rust::#operation(
-                        |#input_pattern| {
-                            #fnname(#(#input_args), *)
-                        },
-                        |#compensation_pattern| {
-                            #compensate(#compensation, (op_result,), (#(#compensation_args), *,)).map_err(|err| err.0)
-                        }
-                    ),
-                    (#(#input_args), *)
-                )
+                #execute
             }
         }
     };
@@ -12,603 +12,786 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use heck::ToKebabCase;
 use proc_macro::TokenStream;
 use proc_macro2::{Ident, Span};
 use quote::quote;
-use syn::{Data, DeriveInput, Fields, Lit, LitStr, Variant};
+use syn::ext::IdentExt;
+use syn::{
+    parse_quote, Data, DataEnum, DeriveInput, Field, Fields, Generics, Member, Type, Variant,
+    WherePredicate,
+};
+
+use crate::attrs::{wit_name, ContainerAttrs, FieldAttrs, FieldDefault, RenameRule, VariantAttrs};
 
 pub fn derive_into_value(input: TokenStream) -> TokenStream {
     let ast: DeriveInput = syn::parse(input).expect("derive input");
+    into_value_impl(&ast)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+pub fn derive_from_value_and_type(input: TokenStream) -> TokenStream {
+    let ast: DeriveInput = syn::parse(input).expect("derive input");
+    from_value_and_type_impl(&ast)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn into_value_impl(ast: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
     let ident = &ast.ident;
-    let flatten_value = ast
-        .attrs
-        .iter()
-        .any(|attr| attr.path().is_ident("flatten_value"));
-    let ident_lit = LitStr::new(&ident.to_string(), Span::call_site());
+    let container = Container::parse(ast)?;
 
-    let (add_to_builder, add_to_type_builder) = match ast.data {
+    let (add_to_builder, add_to_type_builder) = match &ast.data {
+        _ if container.attrs.bitflags => {
+            let name = &container.name;
+            let owner = &container.owner;
+            (
+                quote! {
+                    golem_rust::value_and_type::bitflags::add_to_builder(self, builder)
+                },
+                container.interned(quote! {
+                    golem_rust::value_and_type::bitflags::add_to_type_builder::<Self, _>(
+                        Some(#name.to_string()),
+                        #owner,
+                        builder,
+                    )
+                }),
+            )
+        }
+        Data::Struct(data) if container.attrs.flags => {
+            let fields = FieldsInfo::parse(&data.fields, container.attrs.rename_all)?;
+            fields.check_flags()?;
+            let values = fields.included().map(|field| {
+                let member = &field.member;
+                quote! { self.#member }
+            });
+            let names = fields.included().map(|field| &field.name);
+            let name = &container.name;
+            let owner = &container.owner;
+            (
+                quote! {
+                    builder.flags(vec![#(#values),*])
+                },
+                container.interned(quote! {
+                    builder.flags(Some(#name.to_string()), #owner, &[#(#names),*])
+                }),
+            )
+        }
+        _ if container.attrs.flags => return Err(flags_on_non_struct(ident)),
         Data::Struct(data) => {
-            let newtype_result = if data.fields.len() == 1 {
-                let field = data.fields.iter().next().unwrap().clone();
-                if field.ident.is_none() || flatten_value {
-                    // single field without an identifier, we consider this a newtype
-                    let field_type = field.ty;
-
-                    let add_to_builder = match field.ident {
-                        None => quote! {
-                            self.0.add_to_builder(builder)
+            let fields = FieldsInfo::parse(&data.fields, container.attrs.rename_all)?;
+            match container.newtype_field(&fields)? {
+                Some(field) => {
+                    let member = &field.member;
+                    let typ = field.ty;
+                    (
+                        quote! {
+                            <#typ as golem_rust::value_and_type::IntoValue>::add_to_builder(self.#member, builder)
                         },
-                        Some(field_name) => quote! {
-                            self.#field_name.add_to_builder(builder)
+                        quote! {
+                            <#typ as golem_rust::value_and_type::IntoValue>::add_to_type_builder(builder)
                         },
-                    };
-                    let add_to_type_builder = quote! {
-                        <#field_type as golem_rust::value_and_type::IntoValue>::add_to_type_builder(builder)
-                    };
-
-                    Some((add_to_builder, add_to_type_builder))
-                } else {
-                    None
+                    )
+                }
+                None => {
+                    let values = fields
+                        .included()
+                        .map(|field| {
+                            let member = &field.member;
+                            quote! { self.#member }
+                        })
+                        .collect::<Vec<_>>();
+                    (
+                        fields.add_to_builder(&values),
+                        container.interned(
+                            fields.add_to_type_builder(&container.name, &container.owner),
+                        ),
+                    )
                 }
-            } else {
-                None
-            };
-
-            match newtype_result {
-                Some(newtype_result) => newtype_result,
-                None => record_or_tuple(&ident_lit, &data.fields),
             }
         }
         Data::Enum(data) => {
-            let is_simple_enum = data
-                .variants
-                .iter()
-                .all(|variant| variant.fields.is_empty());
-
-            if is_simple_enum {
-                let case_branches = data
-                    .variants
-                    .iter()
-                    .enumerate()
-                    .map(|(idx, variant)| {
-                        let case_ident = &variant.ident;
-                        let idx = idx as u32;
-                        quote! {
-                            #ident::#case_ident => builder.enum_value(#idx)
-                        }
-                    })
-                    .collect::<Vec<_>>();
-                let case_labels = data
-                    .variants
-                    .iter()
-                    .map(|variant| variant.ident.to_string().to_kebab_case())
-                    .collect::<Vec<_>>();
-
-                let add_to_builder = quote! {
-                    match self {
-                        #(#case_branches),*
+            let cases = Case::parse_all(data, container.attrs.rename_all)?;
+            if is_simple_enum(data) {
+                let case_branches = cases.iter().enumerate().map(|(idx, case)| {
+                    let case_ident = &case.variant.ident;
+                    let idx = idx as u32;
+                    quote! {
+                        #ident::#case_ident => builder.enum_value(#idx)
                     }
-                };
-
-                let add_to_type_builder = quote! {
-                    builder.r#enum(
-                        Some(#ident_lit.to_string()),
-                        &[#(#case_labels),*]
-                    )
-                };
-
-                (add_to_builder, add_to_type_builder)
+                });
+                let case_names = cases.iter().map(|case| &case.name);
+                let name = &container.name;
+                let owner = &container.owner;
+
+                (
+                    quote! {
+                        match self {
+                            #(#case_branches),*
+                        }
+                    },
+                    container.interned(quote! {
+                        builder.r#enum(Some(#name.to_string()), #owner, &[#(#case_names),*])
+                    }),
+                )
             } else {
-                let case_branches = data
-                    .variants
-                    .iter()
-                    .enumerate()
-                    .map(|(idx, variant)| {
-                        let case_ident = &variant.ident;
-                        let idx = idx as u32;
-
-                        if variant.fields.is_empty() {
+                let case_branches = cases.iter().enumerate().map(|(idx, case)| {
+                    let case_ident = &case.variant.ident;
+                    let pattern = match case.payload {
+                        Payload::Unit => quote! { .. },
+                        _ => case.fields.pattern(),
+                    };
+                    let idx = idx as u32;
+                    let builder = match &case.payload {
+                        Payload::Unit => quote! {
+                            builder.variant_unit(#idx)
+                        },
+                        Payload::Single => {
+                            let field = case.fields.single();
+                            let binding = &field.binding;
+                            let typ = field.ty;
                             quote! {
-                                #ident::#case_ident => {
-                                    builder.variant_unit(#idx)
-                                }
+                                <#typ as golem_rust::value_and_type::IntoValue>::add_to_builder(
+                                    #binding,
+                                    builder.variant(#idx),
+                                ).finish()
                             }
-                        } else if has_single_anonymous_field(&variant.fields) {
-                            // separate inner type
-                            if is_unit_case(variant) {
-                                quote! {
-                                    #ident::#case_ident(inner) => {
-                                       builder.variant_unit(#idx)
-                                    }
-                                }
-                            } else {
-                                quote! {
-                                    #ident::#case_ident(inner) => {
-                                        let builder = builder.variant(#idx);
-                                        inner.add_to_builder(builder).finish()
-                                    }
-                                }
-                            }
-                        } else if has_only_named_fields(&variant.fields) {
-                            // record case
-                            let field_names = variant
+                        }
+                        Payload::Fields => {
+                            let values = case
                                 .fields
-                                .iter()
+                                .included()
                                 .map(|field| {
-                                    let field = field.ident.as_ref().unwrap();
-                                    quote! { #field }
+                                    let binding = &field.binding;
+                                    quote! { #binding }
                                 })
                                 .collect::<Vec<_>>();
-
-                            let field_values = variant.fields.iter().map(|field| {
-                                let field = field.ident.as_ref().unwrap();
-                                quote! {
-                                    let builder = #field.add_to_builder(builder.item());
-                                }
-                            });
-
-                            if is_unit_case(variant) {
-                                quote! {
-                                    #ident::#case_ident { #(#field_names),* } => {
-                                       builder.variant_unit(#idx)
-                                    }
-                                }
-                            } else {
-                                quote! {
-                                    #ident::#case_ident { #(#field_names),* } => {
-                                        let builder = builder.variant(#idx);
-                                        let builder = builder.record();
-                                        vec![#(#field_values)*];
-                                        builder.finish().finish()
-                                    }
-                                }
-                            }
-                        } else {
-                            // tuple case
-                            let field_names = variant
-                                .fields
-                                .iter()
-                                .enumerate()
-                                .map(|(idx, _field)| {
-                                    Ident::new(&format!("f{idx}"), Span::call_site())
-                                })
-                                .collect::<Vec<_>>();
-
-                            let field_values = field_names.iter().map(|field| {
-                                quote! {
-                                    let builder = #field.add_to_builder(builder.item());
-                                }
-                            });
-
-                            if is_unit_case(variant) {
-                                quote! {
-                                    #ident::#case_ident(#(#field_names),*) => {
-                                        builder.variant_unit(#idx)
-                                    }
-                                }
-                            } else {
-                                quote! {
-                                    #ident::#case_ident(#(#field_names),*) => {
-                                        let builder = builder.variant(#idx);
-                                        let builder = builder.tuple();
-                                        vec![#(#field_values)*];
-                                        builder.finish().finish()
-                                    }
-                                }
-                            }
-                        }
-                    })
-                    .collect::<Vec<_>>();
-
-                let case_defs = data.variants.iter()
-                    .map(|variant| {
-                        let case_name = variant.ident.to_string().to_kebab_case();
-                        if is_unit_case(variant) {
+                            let add_to_builder = case.fields.add_to_builder(&values);
                             quote! {
-                                let builder = builder.unit_case(#case_name);
+                                {
+                                    let builder = builder.variant(#idx);
+                                    #add_to_builder
+                                }.finish()
                             }
-                        } else if has_single_anonymous_field(&variant.fields) {
-                            let single_field = variant.fields.iter().next().unwrap();
-                            let typ = &single_field.ty;
+                        }
+                    };
+                    quote! {
+                        #ident::#case_ident { #pattern } => #builder
+                    }
+                });
 
+                let case_defs = cases.iter().map(|case| {
+                    let case_name = &case.name;
+                    match &case.payload {
+                        Payload::Unit => quote! {
+                            let builder = builder.unit_case(#case_name);
+                        },
+                        Payload::Single => {
+                            let typ = case.fields.single().ty;
                             quote! {
                                 let builder = <#typ as golem_rust::value_and_type::IntoValue>::add_to_type_builder(builder.case(#case_name));
                             }
-                        } else {
-                            let (_, inner_add_to_type_builder) = record_or_tuple(&ident_lit, &variant.fields);
-
+                        }
+                        Payload::Fields => {
+                            let type_name = case.type_name(&container);
+                            let case_ident = case.variant.ident.to_string();
+                            let add_to_type_builder = container.interned_as(
+                                quote! {
+                                    &::std::format!("{}::{}", ::core::any::type_name::<Self>(), #case_ident)
+                                },
+                                case.fields
+                                    .add_to_type_builder(&type_name, &container.owner),
+                            );
                             quote! {
-                                let builder = builder.case(#case_name);
-                                #inner_add_to_type_builder
+                                let builder = {
+                                    let builder = builder.case(#case_name);
+                                    #add_to_type_builder
+                                };
                             }
                         }
-                    })
-                    .collect::<Vec<_>>();
-
-                let into_value = quote! {
-                    match self {
-                        #(#case_branches),*
                     }
-                };
-                let get_type = quote! {
-                    let builder = builder.variant(Some(#ident_lit.to_string()));
-                    #(#case_defs)*
-                    builder.finish()
-                };
-
-                (into_value, get_type)
+                });
+                let name = &container.name;
+                let owner = &container.owner;
+
+                (
+                    quote! {
+                        match self {
+                            #(#case_branches),*
+                        }
+                    },
+                    container.interned(quote! {
+                        let builder = builder.variant(Some(#name.to_string()), #owner);
+                        #(#case_defs)*
+                        builder.finish()
+                    }),
+                )
             }
         }
-        Data::Union(_data) => {
-            panic!("Cannot derive IntoValue for unions")
+        Data::Union(_) => {
+            return Err(syn::Error::new_spanned(
+                ident,
+                "Cannot derive IntoValue for unions",
+            ))
         }
     };
 
-    let result = quote! {
-        impl golem_rust::value_and_type::IntoValue for #ident {
-            fn add_to_builder<B: golem_rust::value_and_type::NodeBuilder>(self, builder: B) -> B::Result {
+    let generics = impl_generics(
+        ast,
+        &container.attrs.into_value_bound,
+        quote! { golem_rust::value_and_type::IntoValue },
+    );
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics golem_rust::value_and_type::IntoValue for #ident #ty_generics #where_clause {
+            fn add_to_builder<__B: golem_rust::value_and_type::NodeBuilder>(self, builder: __B) -> __B::Result {
+                use golem_rust::value_and_type::NodeBuilder as _;
                 #add_to_builder
             }
 
-            fn add_to_type_builder<B: golem_rust::value_and_type::TypeNodeBuilder>(builder: B) -> B::Result {
+            fn add_to_type_builder<__B: golem_rust::value_and_type::TypeNodeBuilder>(builder: __B) -> __B::Result {
+                use golem_rust::value_and_type::TypeNodeBuilder as _;
                 #add_to_type_builder
             }
         }
-    };
-
-    result.into()
+    })
 }
 
-fn record_or_tuple(
-    ident_lit: &LitStr,
-    fields: &Fields,
-) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
-    let all_fields_has_names = fields.iter().all(|field| field.ident.is_some());
-
-    if all_fields_has_names {
-        let field_values = fields
-            .iter()
-            .map(|field| {
-                let field_name = field.ident.as_ref().unwrap();
-                quote! {
-                    let builder = self.#field_name.add_to_builder(builder.item());
-                }
-            })
-            .collect::<Vec<_>>();
-
-        let field_defs = fields
-            .iter()
-            .map(|field| {
-                let field_name = field.ident.as_ref().unwrap().to_string().to_kebab_case();
-                let field_type = &field.ty;
-                quote! {
-                    let builder = <#field_type as golem_rust::value_and_type::IntoValue>::add_to_type_builder(builder.field(#field_name));
-                }
-            })
-            .collect::<Vec<_>>();
-
-        let add_to_builder = quote! {
-            let builder = builder.record();
-            #(#field_values)*
-            builder.finish()
-        };
-        let add_to_type_builder = quote! {
-            let builder = builder.record(Some(#ident_lit.to_string()));
-            #(#field_defs)*
-            builder.finish()
-        };
-
-        (add_to_builder, add_to_type_builder)
-    } else {
-        let tuple_field_values = fields
-            .iter()
-            .map(|field| {
-                let field_name = field.ident.as_ref().unwrap();
-                quote! {
-                    let builder = self.#field_name.add_to_builder(builder.item());
+fn from_value_and_type_impl(ast: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let ident = &ast.ident;
+    let container = Container::parse(ast)?;
+
+    let extractor = match &ast.data {
+        _ if container.attrs.bitflags => quote! {
+            golem_rust::value_and_type::bitflags::from_extractor(extractor)
+        },
+        Data::Struct(data) if container.attrs.flags => {
+            let fields = FieldsInfo::parse(&data.fields, container.attrs.rename_all)?;
+            fields.check_flags()?;
+            let mut position = 0usize;
+            let initializers = fields.fields.iter().map(|field| {
+                let member = &field.member;
+                if field.attrs.skip {
+                    let default = field.default_value();
+                    quote! { #member: #default }
+                } else {
+                    let initializer = quote! { #member: flags[#position] };
+                    position += 1;
+                    initializer
                 }
-            })
-            .collect::<Vec<_>>();
-
-        let tuple_field_types = fields
-            .iter()
-            .map(|field| {
-                let field_type = &field.ty;
-                quote! {
-                    let builder = <#field_type as golem_rust::value_and_type::IntoValue>::add_to_type_builder(builder.item());
+            });
+            let initializers = initializers.collect::<Vec<_>>();
+            quote! {
+                let flags = extractor.flags().ok_or_else(|| {
+                    golem_rust::value_and_type::ValueError::unexpected(
+                        golem_rust::value_and_type::ValueKind::Flags,
+                        extractor,
+                    )
+                })?;
+                if flags.len() != #position {
+                    return Err(golem_rust::value_and_type::ValueError::custom(::std::format!(
+                        "Expected {} flags, got {}",
+                        #position,
+                        flags.len()
+                    )));
                 }
-            })
-            .collect::<Vec<_>>();
-
-        let add_to_builder = quote! {
-            let builder = builder.tuple();
-            #(#tuple_field_values)*
-            builder.finish()
-        };
-        let add_to_type_builder = quote! {
-            let builder = builder.tuple(Some(#ident_lit.to_string()))
-            #(#tuple_field_types)*
-            builder.finish()
-        };
-
-        (add_to_builder, add_to_type_builder)
-    }
-}
-
-fn has_single_anonymous_field(fields: &Fields) -> bool {
-    fields.len() == 1 && fields.iter().next().unwrap().ident.is_none()
-}
-
-fn has_only_named_fields(fields: &Fields) -> bool {
-    fields.iter().all(|field| field.ident.is_some())
-}
-
-fn is_unit_case(variant: &Variant) -> bool {
-    variant.fields.is_empty()
-        || variant
-            .attrs
-            .iter()
-            .any(|attr| attr.path().is_ident("unit_case"))
-}
-
-pub fn derive_from_value_and_type(input: TokenStream) -> TokenStream {
-    let ast: DeriveInput = syn::parse(input).expect("derive input");
-    let ident = &ast.ident;
-    let flatten_value = ast
-        .attrs
-        .iter()
-        .any(|attr| attr.path().is_ident("flatten_value"));
-
-    let extractor = match ast.data {
+                Ok(Self { #(#initializers),* })
+            }
+        }
+        _ if container.attrs.flags => return Err(flags_on_non_struct(ident)),
         Data::Struct(data) => {
-            let newtype_result = if data.fields.len() == 1 {
-                let field = data.fields.iter().next().unwrap().clone();
-                if field.ident.is_none() || flatten_value {
-                    // single field without an identifier, we consider this a newtype
-                    let field_type = field.ty;
-
-                    let extractor = match field.ident {
-                        None => quote! {
-                            let inner = <#field_type as golem_rust::value_and_type::FromValueAndType>::from_extractor(
-                                extractor
-                            )?;
-                            Ok(Self(inner))
-                        },
-                        Some(field_name) => quote! {
-                            let #field_name = <#field_type as golem_rust::value_and_type::FromValueAndType>::from_extractor(
-                                extractor
-                            )?;
-                            Ok(Self { #field_name })
-                        },
-                    };
-
-                    Some(extractor)
-                } else {
-                    None
+            let fields = FieldsInfo::parse(&data.fields, container.attrs.rename_all)?;
+            match container.newtype_field(&fields)? {
+                Some(newtype_field) => {
+                    let initializers = fields.fields.iter().map(|field| {
+                        let member = &field.member;
+                        if field.member == newtype_field.member {
+                            let typ = field.ty;
+                            quote! {
+                                #member: <#typ as golem_rust::value_and_type::FromValueAndType>::from_extractor(
+                                    extractor
+                                )?
+                            }
+                        } else {
+                            let default = field.default_value();
+                            quote! { #member: #default }
+                        }
+                    });
+                    quote! {
+                        Ok(Self { #(#initializers),* })
+                    }
+                }
+                None => {
+                    let initializers = fields.extractors(&quote! { extractor }, None);
+                    quote! {
+                        Ok(Self { #initializers })
+                    }
                 }
-            } else {
-                None
-            };
-
-            match newtype_result {
-                Some(newtype_result) => newtype_result,
-                None => record_or_tuple_extractor(&data.fields),
             }
         }
         Data::Enum(data) => {
-            let is_simple_enum = data
-                .variants
-                .iter()
-                .all(|variant| variant.fields.is_empty());
-
-            if is_simple_enum {
-                let case_branches = data
-                    .variants
-                    .iter()
-                    .enumerate()
-                    .map(|(idx, variant)| {
-                        let case_ident = &variant.ident;
-                        let idx = idx as u32;
-                        quote! {
-                            Some(#idx) => Ok(#ident::#case_ident)
-                        }
-                    })
-                    .collect::<Vec<_>>();
-
-                let invalid_case_error = Lit::Str(LitStr::new(
-                    &format!("Invalid {}", ast.ident),
-                    Span::call_site(),
-                ));
+            let cases = Case::parse_all(data, container.attrs.rename_all)?;
+            if is_simple_enum(data) {
+                let case_branches = cases.iter().enumerate().map(|(idx, case)| {
+                    let case_ident = &case.variant.ident;
+                    let idx = idx as u32;
+                    quote! {
+                        Some(#idx) => Ok(#ident::#case_ident)
+                    }
+                });
 
                 quote! {
                     match extractor.enum_value() {
                         #(#case_branches),*,
-                        _ => Err(#invalid_case_error.to_string())
+                        Some(case_idx) => Err(golem_rust::value_and_type::ValueError::invalid_case(case_idx)),
+                        None => Err(golem_rust::value_and_type::ValueError::unexpected(
+                            golem_rust::value_and_type::ValueKind::Enum,
+                            extractor,
+                        )),
                     }
                 }
             } else {
-                let cases = data
-                    .variants
-                    .iter()
-                    .enumerate()
-                    .map(|(idx, variant)| {
-                        let case_ident = &variant.ident;
-                        let idx = idx as u32;
-
-                        if variant.fields.is_empty() {
-                            quote! {
-                                #idx => {
-                                    Ok(#ident::#case_ident)
-                                }
-                            }
-                        } else if has_single_anonymous_field(&variant.fields) {
-                            // separate inner type
-                            if is_unit_case(variant) {
-                                quote! {
-                                    #idx => {
-                                        Ok(#ident::#case_ident)
-                                    }
-                                }
-                            } else {
-                                let single_field = variant.fields.iter().next().unwrap();
-                                let typ = &single_field.ty;
-                                let missing_body_error = Lit::Str(LitStr::new(
-                                    &format!("Missing {case_ident} body"),
-                                    Span::call_site()
-                                ));
+                let case_branches = cases.iter().enumerate().map(|(idx, case)| {
+                    let case_ident = &case.variant.ident;
+                    let case_name = &case.name;
+                    let idx = idx as u32;
 
-                                quote! {
-                                    #idx => {
-                                        Ok(#ident::#case_ident(
-                                            <#typ as golem_rust::value_and_type::FromValueAndType>::from_extractor(
-                                                &inner.ok_or_else(|| #missing_body_error.to_string())?
-                                            )?
-                                        ))
-                                    }
-                                }
-                            }
-                        } else if has_only_named_fields(&variant.fields) {
-                            // record case
-                            if is_unit_case(variant) {
-                                quote! {
-                                    #idx => {
-                                        Ok(#ident::#case_ident)
-                                    }
-                                }
-                            } else {
-                                let field_extractors = variant.fields.iter()
-                                    .enumerate()
-                                    .map(|(idx, field)| {
-                                        let field_name = field.ident.as_ref().unwrap();
-                                        let field_ty = &field.ty;
-                                        let missing_field_error = Lit::Str(LitStr::new(&format!("Missing {field_name} field"), Span::call_site()));
-                                        quote! {
-                                            #field_name: <#field_ty as golem_rust::value_and_type::FromValueAndType>::from_extractor(
-                                                &extractor.field(#idx).ok_or_else(|| #missing_field_error.to_string())?
-                                            )?
-                                        }
-                                    })
-                                    .collect::<Vec<_>>();
+                    let at_case = quote! {
+                        .map_err(|err| err.at_case(#case_name))
+                    };
+                    let inner = quote! {
+                        let inner = inner.ok_or_else(|| {
+                            golem_rust::value_and_type::ValueError::missing_case_value(#case_name)
+                        })?;
+                    };
 
-                                quote! {
+                    match &case.payload {
+                        Payload::Unit => {
+                            let initializers = case.fields.fields.iter().map(|field| {
+                                let member = &field.member;
+                                let default = field.default_value();
+                                quote! { #member: #default }
+                            });
+                            quote! {
+                                #idx => Ok(#ident::#case_ident { #(#initializers),* })
+                            }
+                        }
+                        Payload::Single => {
+                            let field = case.fields.single();
+                            let member = &field.member;
+                            let typ = field.ty;
+                            quote! {
+                                #idx => {
+                                    #inner
                                     Ok(#ident::#case_ident {
-                                        #(#field_extractors),*
+                                        #member: <#typ as golem_rust::value_and_type::FromValueAndType>::from_extractor(&inner)
+                                            #at_case?
                                     })
                                 }
                             }
-                        } else {
-                            // tuple case
-                            if is_unit_case(variant) {
-                                quote! {
-                                    #idx => {
-                                        Ok(#ident::#case_ident)
-                                    }
-                                }
-                            } else {
-                                let field_extractors = variant.fields.iter()
-                                    .enumerate()
-                                    .map(|(idx, field)| {
-                                        let elem_ty = &field.ty;
-                                        let missing_tuple_element_error = Lit::Str(LitStr::new(&format!("Missing tuple element #{idx}"), Span::call_site()));
-                                        quote! {
-                                            <#elem_ty as golem_rust::value_and_type::FromValueAndType>::from_extractor(
-                                                &extractor.tuple_element(#idx).ok_or_else(|| #missing_tuple_element_error.to_string())?
-                                            )?
-                                        }
-                                    })
-                                    .collect::<Vec<_>>();
-
-                                quote! {
-                                    Ok(#ident::#case_ident {
-                                        #(#field_extractors),*
-                                    })
+                        }
+                        Payload::Fields => {
+                            let initializers =
+                                case.fields.extractors(&quote! { &inner }, Some(&at_case));
+                            quote! {
+                                #idx => {
+                                    #inner
+                                    Ok(#ident::#case_ident { #initializers })
                                 }
                             }
                         }
-                    })
-                    .collect::<Vec<_>>();
-
-                let should_be_variant_error = Lit::Str(LitStr::new(
-                    &format!("{} should be variant", ast.ident),
-                    Span::call_site(),
-                ));
-
-                let invalid_case_format = Lit::Str(LitStr::new(
-                    &format!("Invalid {} variant: {{idx}}", ast.ident),
-                    Span::call_site(),
-                ));
+                    }
+                });
 
                 quote! {
-                    let (idx, inner) = extractor
-                        .variant()
-                        .ok_or_else(|| #should_be_variant_error.to_string())?;
-                    match idx {
-                        #(#cases),*,
-                        _ => Err(format!(#invalid_case_format)),
+                    let (case_idx, inner) = extractor.variant().ok_or_else(|| {
+                        golem_rust::value_and_type::ValueError::unexpected(
+                            golem_rust::value_and_type::ValueKind::Variant,
+                            extractor,
+                        )
+                    })?;
+                    match case_idx {
+                        #(#case_branches),*,
+                        _ => Err(golem_rust::value_and_type::ValueError::invalid_case(case_idx)),
                     }
                 }
             }
         }
         Data::Union(_) => {
-            panic!("Cannot derive FromValueAndType for unions")
+            return Err(syn::Error::new_spanned(
+                ident,
+                "Cannot derive FromValueAndType for unions",
+            ))
         }
     };
 
-    let result = quote! {
-        impl golem_rust::value_and_type::FromValueAndType for #ident {
-            fn from_extractor<'a, 'b>(
-                extractor: &'a impl golem_rust::value_and_type::WitValueExtractor<'a, 'b>,
-            ) -> Result<Self, String> {
+    let generics = impl_generics(
+        ast,
+        &container.attrs.from_value_and_type_bound,
+        quote! { golem_rust::value_and_type::FromValueAndType },
+    );
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics golem_rust::value_and_type::FromValueAndType for #ident #ty_generics #where_clause {
+            fn from_extractor<'__a, '__b>(
+                extractor: &'__a impl golem_rust::value_and_type::WitValueExtractor<'__a, '__b>,
+            ) -> Result<Self, golem_rust::value_and_type::ValueError> {
                 #extractor
             }
         }
-    };
+    })
+}
 
-    result.into()
+struct Container {
+    attrs: ContainerAttrs,
+    name: String,
+    /// The owner as an `Option<String>` expression
+    owner: proc_macro2::TokenStream,
 }
 
-fn record_or_tuple_extractor(fields: &Fields) -> proc_macro2::TokenStream {
-    let all_fields_has_names = fields.iter().all(|field| field.ident.is_some());
+impl Container {
+    fn parse(ast: &DeriveInput) -> syn::Result<Self> {
+        let attrs = ContainerAttrs::parse(&ast.attrs)?;
+        let name = attrs.type_name(&ast.ident);
+        let owner = match &attrs.owner {
+            Some(owner) => quote! { Some(#owner.to_string()) },
+            None => quote! { None },
+        };
+        Ok(Self { attrs, name, owner })
+    }
 
-    if all_fields_has_names {
-        let field_extractors = fields.iter()
-            .enumerate()
-            .map(|(idx, field)| {
-                let field_name = field.ident.as_ref().unwrap();
-                let field_ty = &field.ty;
-                let missing_field_error = Lit::Str(LitStr::new(&format!("Missing {field_name} field"), Span::call_site()));
-                quote! {
-                    #field_name: <#field_ty as golem_rust::value_and_type::FromValueAndType>::from_extractor(
-                        &extractor.field(#idx).ok_or_else(|| #missing_field_error.to_string())?
-                    )?
-                }
-            })
-            .collect::<Vec<_>>();
+    /// Wraps the building of the named type so it is added to a type only once, which also
+    /// makes recursive types possible. Types are identified by their Rust type name, as
+    /// different types can have the same WIT name and owner. The type name of a generic type
+    /// includes its type arguments, so each instance is interned separately.
+    fn interned(&self, add_to_type_builder: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+        self.interned_as(
+            quote! { ::core::any::type_name::<Self>() },
+            add_to_type_builder,
+        )
+    }
 
+    /// Interns a type by the given key expression, such as the fields of an enum case
+    fn interned_as(
+        &self,
+        key: proc_macro2::TokenStream,
+        add_to_type_builder: proc_macro2::TokenStream,
+    ) -> proc_macro2::TokenStream {
         quote! {
-            Ok(Self {
-                #(#field_extractors),*
+            builder.interned(#key, |builder| {
+                #add_to_type_builder
             })
         }
-    } else {
-        let field_extractors = fields.iter()
+    }
+
+    /// The single field a struct is represented by: the field of a tuple struct with one field,
+    /// or the only field which is not skipped of a transparent struct
+    fn newtype_field<'a, 'f>(
+        &self,
+        fields: &'a FieldsInfo<'f>,
+    ) -> syn::Result<Option<&'a FieldInfo<'f>>> {
+        if self.attrs.transparent {
+            let mut included = fields.included();
+            match (included.next(), included.next()) {
+                (Some(field), None) => Ok(Some(field)),
+                _ => Err(syn::Error::new(
+                    Span::call_site(),
+                    "Transparent structs must have exactly one field which is not skipped",
+                )),
+            }
+        } else if !fields.named && fields.fields.len() == 1 {
+            Ok(fields.fields.first())
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+struct FieldInfo<'a> {
+    member: Member,
+    /// Variable the field is bound to when matching an enum case
+    binding: Ident,
+    ty: &'a Type,
+    name: String,
+    attrs: FieldAttrs,
+}
+
+impl FieldInfo<'_> {
+    fn default_value(&self) -> proc_macro2::TokenStream {
+        match &self.attrs.default {
+            Some(FieldDefault::Function(path)) => quote! { #path() },
+            Some(FieldDefault::Default) | None => quote! { ::core::default::Default::default() },
+        }
+    }
+}
+
+/// The fields of a struct or enum case, represented by a record if they are named and by a
+/// tuple otherwise. Skipped fields are not part of the value.
+struct FieldsInfo<'a> {
+    named: bool,
+    fields: Vec<FieldInfo<'a>>,
+}
+
+impl<'a> FieldsInfo<'a> {
+    fn parse(fields: &'a Fields, rename_all: Option<RenameRule>) -> syn::Result<Self> {
+        let fields = fields
+            .iter()
             .enumerate()
-            .map(|(idx, field)| {
-                let elem_ty = &field.ty;
-                let missing_tuple_element_error = Lit::Str(LitStr::new(&format!("Missing tuple element #{idx}"), Span::call_site()));
-                quote! {
-                    <#elem_ty as golem_rust::value_and_type::FromValueAndType>::from_extractor(
-                        &extractor.tuple_element(#idx).ok_or_else(|| #missing_tuple_element_error.to_string())?
-                    )?
-                }
-            })
-            .collect::<Vec<_>>();
+            .map(|(idx, field)| FieldInfo::parse(idx, field, rename_all))
+            .collect::<syn::Result<Vec<_>>>()?;
+        Ok(Self {
+            named: fields
+                .iter()
+                .all(|field| matches!(field.member, Member::Named(_))),
+            fields,
+        })
+    }
 
-        quote! {
-            Ok((
-                #(#field_extractors),*
+    /// Flags are represented by structs with named fields, which have to be `bool`s
+    fn check_flags(&self) -> syn::Result<()> {
+        if self.named {
+            Ok(())
+        } else {
+            Err(syn::Error::new(
+                Span::call_site(),
+                "Flags must be represented by a struct with named fields",
             ))
         }
     }
+
+    fn single(&self) -> &FieldInfo<'a> {
+        &self.fields[0]
+    }
+
+    fn included(&self) -> impl Iterator<Item = &FieldInfo<'a>> {
+        self.fields.iter().filter(|field| !field.attrs.skip)
+    }
+
+    /// Pattern binding the fields of an enum case, for use in braces
+    fn pattern(&self) -> proc_macro2::TokenStream {
+        let bindings = self.fields.iter().map(|field| {
+            let member = &field.member;
+            if field.attrs.skip {
+                quote! { #member: _ }
+            } else {
+                let binding = &field.binding;
+                quote! { #member: #binding }
+            }
+        });
+        quote! { #(#bindings),* }
+    }
+
+    /// Adds the given values of the included fields to `builder`
+    fn add_to_builder(&self, values: &[proc_macro2::TokenStream]) -> proc_macro2::TokenStream {
+        let items = self.included().zip(values).map(|(field, value)| {
+            let typ = field.ty;
+            quote! {
+                let builder = <#typ as golem_rust::value_and_type::IntoValue>::add_to_builder(#value, builder.item());
+            }
+        });
+        let container = if self.named {
+            quote! { record }
+        } else {
+            quote! { tuple }
+        };
+        quote! {
+            let builder = builder.#container();
+            #(#items)*
+            builder.finish()
+        }
+    }
+
+    /// Builds the record or tuple type with the given name, and the owner as an `Option<String>`
+    /// expression
+    fn add_to_type_builder(
+        &self,
+        name: &str,
+        owner: &proc_macro2::TokenStream,
+    ) -> proc_macro2::TokenStream {
+        if self.named {
+            let fields = self.included().map(|field| {
+                let typ = field.ty;
+                let field_name = &field.name;
+                quote! {
+                    let builder = <#typ as golem_rust::value_and_type::IntoValue>::add_to_type_builder(builder.field(#field_name));
+                }
+            });
+            quote! {
+                let builder = builder.record(Some(#name.to_string()), #owner);
+                #(#fields)*
+                builder.finish()
+            }
+        } else {
+            let items = self.included().map(|field| {
+                let typ = field.ty;
+                quote! {
+                    let builder = <#typ as golem_rust::value_and_type::IntoValue>::add_to_type_builder(builder.item());
+                }
+            });
+            quote! {
+                let builder = builder.tuple(Some(#name.to_string()), #owner);
+                #(#items)*
+                builder.finish()
+            }
+        }
+    }
+
+    /// Initializers extracting all fields from `source`, for use in braces. The errors are
+    /// extended with the field or element position, and then mapped by `map_err` if given.
+    fn extractors(
+        &self,
+        source: &proc_macro2::TokenStream,
+        map_err: Option<&proc_macro2::TokenStream>,
+    ) -> proc_macro2::TokenStream {
+        let mut position = 0usize;
+        let initializers = self.fields.iter().map(|field| {
+            let member = &field.member;
+            if field.attrs.skip {
+                let default = field.default_value();
+                return quote! { #member: #default };
+            }
+
+            let typ = field.ty;
+            let (get, missing, at) = if self.named {
+                let field_name = &field.name;
+                (
+                    quote! { field },
+                    quote! { golem_rust::value_and_type::ValueError::missing_field(#source, #field_name) },
+                    quote! { at_field(#field_name) },
+                )
+            } else {
+                (
+                    quote! { tuple_element },
+                    quote! { golem_rust::value_and_type::ValueError::missing_element(#source, #position) },
+                    quote! { at_index(#position) },
+                )
+            };
+            let when_missing = if field.attrs.default.is_some() {
+                field.default_value()
+            } else {
+                quote! { return Err(#missing)#map_err }
+            };
+            let initializer = quote! {
+                #member: match golem_rust::value_and_type::WitValueExtractor::#get(#source, #position) {
+                    Some(value) => <#typ as golem_rust::value_and_type::FromValueAndType>::from_extractor(&value)
+                        .map_err(|err| err.#at)#map_err?,
+                    None => #when_missing,
+                }
+            };
+            position += 1;
+            initializer
+        });
+        let initializers = initializers.collect::<Vec<_>>();
+        quote! { #(#initializers),* }
+    }
+}
+
+impl<'a> FieldInfo<'a> {
+    fn parse(idx: usize, field: &'a Field, rename_all: Option<RenameRule>) -> syn::Result<Self> {
+        let attrs = FieldAttrs::parse(&field.attrs)?;
+        let (member, name) = match &field.ident {
+            Some(ident) => (
+                Member::Named(ident.clone()),
+                wit_name(ident, &attrs.rename, rename_all),
+            ),
+            None => (Member::Unnamed(idx.into()), idx.to_string()),
+        };
+        Ok(Self {
+            member,
+            binding: Ident::new(&format!("f{idx}"), Span::call_site()),
+            ty: &field.ty,
+            name,
+            attrs,
+        })
+    }
+}
+
+struct Case<'a> {
+    variant: &'a Variant,
+    name: String,
+    /// Name of the type of the fields given by `#[wit(type_name = "...")]`
+    type_name: Option<String>,
+    fields: FieldsInfo<'a>,
+    payload: Payload,
+}
+
+enum Payload {
+    /// Unit case, used for variants without fields or marked as `unit_case`
+    Unit,
+    /// The case value is the single unnamed field
+    Single,
+    /// The case value is a record or tuple of the fields
+    Fields,
+}
+
+impl<'a> Case<'a> {
+    fn parse_all(data: &'a DataEnum, rename_all: Option<RenameRule>) -> syn::Result<Vec<Self>> {
+        data.variants
+            .iter()
+            .map(|variant| Case::parse(variant, rename_all))
+            .collect()
+    }
+
+    fn parse(variant: &'a Variant, rename_all: Option<RenameRule>) -> syn::Result<Self> {
+        let attrs = VariantAttrs::parse(&variant.attrs)?;
+        let fields = FieldsInfo::parse(&variant.fields, attrs.rename_all)?;
+        Ok(Self {
+            variant,
+            name: wit_name(&variant.ident, &attrs.rename, rename_all),
+            type_name: attrs.type_name,
+            payload: if variant.fields.is_empty() || attrs.unit_case {
+                Payload::Unit
+            } else if !fields.named && fields.fields.len() == 1 && !fields.fields[0].attrs.skip {
+                Payload::Single
+            } else {
+                Payload::Fields
+            },
+            fields,
+        })
+    }
+
+    /// Name of the record or tuple type of the fields, `Parent.Case` unless given explicitly
+    fn type_name(&self, container: &Container) -> String {
+        self.type_name
+            .clone()
+            .unwrap_or_else(|| format!("{}.{}", container.name, self.variant.ident.unraw()))
+    }
+}
+
+fn flags_on_non_struct(ident: &Ident) -> syn::Error {
+    syn::Error::new_spanned(
+        ident,
+        "Flags must be represented by a struct with named fields",
+    )
+}
+
+fn is_simple_enum(data: &DataEnum) -> bool {
+    data.variants
+        .iter()
+        .all(|variant| variant.fields.is_empty())
+}
+
+/// Generics of the implementation, with each type parameter bound by `trait_path` unless
+/// the bounds are given explicitly
+fn impl_generics(
+    ast: &DeriveInput,
+    bound: &Option<Vec<WherePredicate>>,
+    trait_path: proc_macro2::TokenStream,
+) -> Generics {
+    let mut generics = ast.generics.clone();
+    let predicates: Vec<WherePredicate> = match bound {
+        Some(bound) => bound.clone(),
+        None => ast
+            .generics
+            .type_params()
+            .map(|param| {
+                let ident = &param.ident;
+                parse_quote! { #ident: #trait_path }
+            })
+            .collect(),
+    };
+    generics.make_where_clause().predicates.extend(predicates);
+    generics
 }
@@ -18,11 +18,22 @@ use crate::bindings::golem::durability::durability::{
     read_persisted_typed_durable_function_invocation, DurableExecutionState, DurableFunctionType,
     OplogEntryVersion, OplogIndex, PersistedTypedDurableFunctionInvocation, PersistenceLevel,
 };
-use crate::value_and_type::{FromValueAndType, IntoValueAndType};
+#[cfg(feature = "serde")]
+use crate::value_and_type::serde::{SerdeError, TryIntoValueAndType};
+use crate::value_and_type::{
+    bytes_result_into_value_and_type, compat, validate, Bytes, FromValueAndType, IntoValue,
+    IntoValueAndType,
+};
 use golem_wasm_rpc::golem_rpc_0_2_x::types::ValueAndType;
+use golem_wasm_rpc::WitType;
 use std::fmt::{Debug, Display};
 use std::marker::PhantomData;
 
+/// Persists the results of a durable function in live mode and replays them otherwise, using the
+/// `SOk` and `SErr` types converted from and to the function's own result types.
+///
+/// Binary results are persisted as `Bytes` with `persist_bytes`, which builds the payload without
+/// copying the result, and replayed into `Vec<u8>` with the `replay` functions.
 pub struct Durability<SOk, SErr> {
     interface: &'static str,
     function: &'static str,
@@ -30,6 +41,8 @@ pub struct Durability<SOk, SErr> {
     begin_index: OplogIndex,
     durable_execution_state: DurableExecutionState,
     forced_commit: bool,
+    /// The type replayed payloads are validated against, if replay validation is enabled
+    replay_type: Option<WitType>,
     _sok: PhantomData<SOk>,
     _serr: PhantomData<SErr>,
 }
@@ -52,6 +65,7 @@ impl<SOk, SErr> Durability<SOk, SErr> {
             begin_index,
             durable_execution_state,
             forced_commit: false,
+            replay_type: None,
             _sok: PhantomData,
             _serr: PhantomData,
         }
@@ -61,6 +75,17 @@ impl<SOk, SErr> Durability<SOk, SErr> {
         self.forced_commit = true;
     }
 
+    /// Validates replayed payloads against their persisted types, and upgrades them to
+    /// `Result<SOk, SErr>` before converting them, so payloads persisted by older versions of the
+    /// types can be replayed, and corrupted or incompatible ones fail with the path of the first
+    /// invalid part
+    pub fn enable_replay_validation(&mut self)
+    where
+        Result<SOk, SErr>: IntoValue,
+    {
+        self.replay_type = Some(Result::<SOk, SErr>::get_type());
+    }
+
     pub fn is_live(&self) -> bool {
         self.durable_execution_state.is_live
             || matches!(
@@ -105,6 +130,29 @@ impl<SOk, SErr> Durability<SOk, SErr> {
     where
         SIn: Debug + IntoValueAndType,
         Result<SOk, SErr>: IntoValueAndType,
+    {
+        self.persist_value_and_type(input, result.into_value_and_type());
+    }
+
+    /// Persists a result which may fail to convert, such as one with `SerdeValue` cases. Nothing
+    /// is persisted if the conversion fails.
+    #[cfg(feature = "serde")]
+    pub fn try_persist_serializable<SIn>(
+        &self,
+        input: SIn,
+        result: Result<SOk, SErr>,
+    ) -> Result<(), SerdeError>
+    where
+        SIn: Debug + IntoValueAndType,
+        Result<SOk, SErr>: TryIntoValueAndType,
+    {
+        self.persist_value_and_type(input, result.try_into_value_and_type()?);
+        Ok(())
+    }
+
+    fn persist_value_and_type<SIn>(&self, input: SIn, result: ValueAndType)
+    where
+        SIn: Debug + IntoValueAndType,
     {
         let function_name = self.function_name();
         if !matches!(
@@ -114,7 +162,7 @@ impl<SOk, SErr> Durability<SOk, SErr> {
             persist_typed_durable_function_invocation(
                 &function_name,
                 &input.into_value_and_type(),
-                &result.into_value_and_type(),
+                &result,
                 self.function_type,
             );
             end_durable_function(self.function_type, self.begin_index, self.forced_commit);
@@ -138,9 +186,14 @@ impl<SOk, SErr> Durability<SOk, SErr> {
         SErr: FromValueAndType,
     {
         let (value_and_type, _) = self.replay_raw();
-        let result: Result<SOk, SErr> = FromValueAndType::from_value_and_type(value_and_type)
-            .unwrap_or_else(|err| panic!("Unexpected ImportedFunctionInvoked payload: {err}"));
-        result
+        from_replayed_payload(value_and_type, self.replay_type.as_ref()).unwrap_or_else(|err| {
+            panic!(
+                "{} ImportedFunctionInvoked payload for {}: {}",
+                err.0,
+                self.function_name(),
+                err.1
+            )
+        })
     }
 
     pub fn replay<Ok, Err>(&self) -> Result<Ok, Err>
@@ -193,11 +246,56 @@ impl<SOk, SErr> Durability<SOk, SErr> {
     }
 }
 
+impl<SErr> Durability<Bytes, SErr> {
+    /// Persists a binary result as a `list<u8>`, allocating all nodes of the bytes at once
+    pub fn persist_bytes<SIn, Err>(
+        &self,
+        input: SIn,
+        result: Result<Vec<u8>, Err>,
+    ) -> Result<Vec<u8>, Err>
+    where
+        SIn: Debug + IntoValueAndType,
+        SErr: Debug + IntoValue + for<'a> From<&'a Err>,
+    {
+        let result = result.map(Bytes);
+        let serializable_result: Result<&Bytes, SErr> = result.as_ref().map_err(|err| err.into());
+
+        self.persist_value_and_type(input, bytes_result_into_value_and_type(serializable_result));
+        result.map(Bytes::into_inner)
+    }
+}
+
+/// Converts a replayed payload. With a `replay_type`, the payload is validated against its
+/// persisted type, which is upgraded to `replay_type` first, so for example reordered record
+/// fields are read by their names. Fails with the kind of the problem and its description.
+fn from_replayed_payload<T: FromValueAndType>(
+    value_and_type: ValueAndType,
+    replay_type: Option<&WitType>,
+) -> Result<T, (&'static str, String)> {
+    let value_and_type = match replay_type {
+        Some(replay_type) => {
+            validate::validate(&value_and_type).map_err(|err| ("Invalid", err.to_string()))?;
+            let incompatibilities = compat::check(&value_and_type.typ, replay_type);
+            if !incompatibilities.is_empty() {
+                let incompatibilities = incompatibilities
+                    .iter()
+                    .map(|incompatibility| incompatibility.to_string())
+                    .collect::<Vec<_>>();
+                return Err(("Incompatible", incompatibilities.join(", ")));
+            }
+            compat::upgrade(value_and_type, replay_type)
+                .map_err(|err| ("Incompatible", err.to_string()))?
+        }
+        None => value_and_type,
+    };
+    T::from_value_and_type(value_and_type).map_err(|err| ("Unexpected", err.to_string()))
+}
+
 #[cfg(test)]
 mod tests {
     use crate::bindings::golem::durability::durability::DurableFunctionType;
     use crate::value_and_type::type_builder::TypeNodeBuilder;
-    use crate::value_and_type::{FromValueAndType, IntoValue};
+    use crate::value_and_type::{Bytes, FromValueAndType, IntoValue, ValueError, ValueKind};
     use golem_wasm_rpc::{NodeBuilder, WitValueExtractor};
     use std::io::Error;
 
@@ -239,11 +337,12 @@ mod tests {
         impl FromValueAndType for CustomError {
             fn from_extractor<'a, 'b>(
                 extractor: &'a impl WitValueExtractor<'a, 'b>,
-            ) -> Result<Self, String> {
+            ) -> Result<Self, ValueError> {
                 match extractor.enum_value() {
                     Some(0) => Ok(CustomError::Error1),
                     Some(1) => Ok(CustomError::Error2),
-                    _ => Err("Invalid enum value".to_string()),
+                    Some(case_idx) => Err(ValueError::invalid_case(case_idx)),
+                    None => Err(ValueError::unexpected(ValueKind::Enum, extractor)),
                 }
             }
         }
@@ -261,5 +360,71 @@ mod tests {
                 durability.replay()
             }
         }
+
+        fn binary_durable_fn(url: &str) -> Result<Vec<u8>, std::io::Error> {
+            let mut durability = super::Durability::<Bytes, CustomError>::new(
+                "custom",
+                "http-get",
+                DurableFunctionType::ReadRemote,
+            );
+            durability.enable_replay_validation();
+            if durability.is_live() {
+                let result = Ok(vec![0; 1024 * 1024]);
+                durability.persist_bytes(url.to_string(), result)
+            } else {
+                durability.replay()
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "macro"))]
+mod derive_tests {
+    use crate::durability::from_replayed_payload;
+    use crate::value_and_type::{IntoValue, IntoValueAndType};
+    use crate::{FromValueAndType, IntoValue};
+
+    #[derive(Debug, Clone, PartialEq, IntoValue, FromValueAndType)]
+    #[wit(rename = "point")]
+    struct OldPoint {
+        y: u32,
+        x: u8,
+    }
+
+    #[derive(Debug, Clone, PartialEq, IntoValue, FromValueAndType)]
+    #[wit(rename = "point")]
+    struct Point {
+        x: u32,
+        y: u32,
+        label: Option<String>,
+    }
+
+    #[test]
+    fn replayed_payloads_are_upgraded() {
+        let persisted = Ok::<_, String>(OldPoint { y: 2, x: 1 }).into_value_and_type();
+        let replay_type = Result::<Point, String>::get_type();
+        let result = from_replayed_payload::<Result<Point, String>>(persisted, Some(&replay_type));
+        assert_eq!(
+            result,
+            Ok(Ok(Point {
+                x: 1,
+                y: 2,
+                label: None
+            }))
+        );
+    }
+
+    #[test]
+    fn incompatible_payloads_are_reported() {
+        let persisted = Ok::<_, String>(Point {
+            x: 1,
+            y: 2,
+            label: None,
+        })
+        .into_value_and_type();
+        let replay_type = Result::<OldPoint, String>::get_type();
+        let result =
+            from_replayed_payload::<Result<OldPoint, String>>(persisted, Some(&replay_type));
+        assert_eq!(result.map_err(|err| err.0), Err("Incompatible"));
     }
 }
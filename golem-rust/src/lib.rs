@@ -195,6 +195,10 @@ pub use transaction::*;
 #[cfg(feature = "macro")]
 pub use golem_rust_macro::*;
 
+// Allows the derives, which refer to `golem_rust`, to be tested within this crate
+#[cfg(test)]
+extern crate self as golem_rust;
+
 impl Display for PromiseId {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         write!(f, "{}/{}", self.worker_id, self.oplog_idx)
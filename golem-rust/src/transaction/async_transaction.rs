@@ -0,0 +1,468 @@
+// Copyright 2024-2025 Golem Cloud
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt::Debug;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+
+use crate::bindings::golem::api::host::{get_oplog_index, set_oplog_index, OplogIndex};
+use crate::mark_atomic_operation;
+use crate::transaction::observer::Observers;
+use crate::transaction::{TransactionFailure, TransactionObserver, TransactionResult};
+
+type LocalBoxFuture<T> = Pin<Box<dyn Future<Output = T>>>;
+
+/// Asynchronous counterpart of `Operation`, for steps which need to await other asynchronous
+/// work (for example outgoing HTTP requests or RPC calls) while executing or compensating.
+///
+/// Implementations can use `async fn` for both methods.
+/// Operations can also be constructed from async closures using `async_operation`.
+pub trait AsyncOperation: Clone {
+    type In: Clone;
+    type Out: Clone;
+    type Err: Clone;
+
+    /// Executes the operation which may fail with a domain error
+    fn execute(&self, input: Self::In) -> impl Future<Output = Result<Self::Out, Self::Err>>;
+
+    /// Executes a compensation action for the operation.
+    fn compensate(
+        &self,
+        input: Self::In,
+        result: Self::Out,
+    ) -> impl Future<Output = Result<(), Self::Err>>;
+}
+
+/// Constructs an `AsyncOperation` from two functions returning futures: one for executing the
+/// operation, and one for rolling it back. The rollback operation always sees the input and
+/// the output of the operation.
+pub fn async_operation<In, Out, Err, ExecFut, CompFut>(
+    execute_fn: impl Fn(In) -> ExecFut + 'static,
+    compensate_fn: impl Fn(In, Out) -> CompFut + 'static,
+) -> impl AsyncOperation<In = In, Out = Out, Err = Err>
+where
+    In: Clone,
+    Out: Clone,
+    Err: Clone,
+    ExecFut: Future<Output = Result<Out, Err>> + 'static,
+    CompFut: Future<Output = Result<(), Err>> + 'static,
+{
+    FnAsyncOperation {
+        execute_fn: Rc::new(move |input| Box::pin(execute_fn(input)) as LocalBoxFuture<_>),
+        compensate_fn: Rc::new(move |input, result| {
+            Box::pin(compensate_fn(input, result)) as LocalBoxFuture<_>
+        }),
+    }
+}
+
+#[allow(clippy::type_complexity)]
+struct FnAsyncOperation<In, Out, Err> {
+    execute_fn: Rc<dyn Fn(In) -> LocalBoxFuture<Result<Out, Err>>>,
+    compensate_fn: Rc<dyn Fn(In, Out) -> LocalBoxFuture<Result<(), Err>>>,
+}
+
+impl<In, Out, Err> Clone for FnAsyncOperation<In, Out, Err> {
+    fn clone(&self) -> Self {
+        Self {
+            execute_fn: self.execute_fn.clone(),
+            compensate_fn: self.compensate_fn.clone(),
+        }
+    }
+}
+
+impl<In: Clone, Out: Clone, Err: Clone> AsyncOperation for FnAsyncOperation<In, Out, Err> {
+    type In = In;
+    type Out = Out;
+    type Err = Err;
+
+    async fn execute(&self, input: In) -> Result<Out, Err> {
+        (self.execute_fn)(input).await
+    }
+
+    async fn compensate(&self, input: In, result: Out) -> Result<(), Err> {
+        (self.compensate_fn)(input, result).await
+    }
+}
+
+/// Asynchronous version of `fallible_transaction`. If any operation fails, all the already
+/// executed successful operation's compensation actions are awaited one by one in reverse order
+/// and the transaction returns with a failure.
+pub async fn fallible_transaction_async<Out, Err: Clone + 'static>(
+    f: impl AsyncFnOnce(&mut AsyncFallibleTransaction<Err>) -> Result<Out, Err>,
+) -> TransactionResult<Out, Err> {
+    let mut transaction = AsyncFallibleTransaction::new();
+    match f(&mut transaction).await {
+        Ok(output) => Ok(output),
+        Err(error) => Err(transaction.on_fail(error).await),
+    }
+}
+
+/// Asynchronous version of `infallible_transaction`. If any operation returns with a failure, all
+/// the already executed successful operation's compensation actions are awaited in reverse order
+/// and the transaction gets retried, using Golem's active retry policy.
+pub async fn infallible_transaction_async<Out>(
+    f: impl AsyncFnOnce(&mut AsyncInfallibleTransaction) -> Out,
+) -> Out {
+    let oplog_index = get_oplog_index();
+    let _atomic_region = mark_atomic_operation();
+    let mut transaction = AsyncInfallibleTransaction::new(oplog_index);
+    f(&mut transaction).await
+}
+
+/// Asynchronous version of `transaction`, where the transaction mode is determined by the
+/// function's parameter (it can be `AsyncFallibleTransaction` or `AsyncInfallibleTransaction`).
+pub async fn transaction_async<Out, Err, T>(
+    f: impl AsyncFnOnce(&mut T) -> Result<Out, Err>,
+) -> TransactionResult<Out, Err>
+where
+    T: AsyncTransaction<Err>,
+{
+    T::run(f).await
+}
+
+/// Helper struct for coupling an asynchronous compensation action and the result of the operation.
+#[allow(clippy::type_complexity)]
+struct AsyncCompensationAction<Err> {
+    operation: usize,
+    action: Box<dyn Fn() -> LocalBoxFuture<Result<(), Err>>>,
+}
+
+impl<Err> AsyncCompensationAction<Err> {
+    pub async fn execute(&self) -> Result<(), Err> {
+        (self.action)().await
+    }
+}
+
+fn compensation_action<Op>(
+    operation_idx: usize,
+    operation: Op,
+    input: Op::In,
+    output: Op::Out,
+) -> AsyncCompensationAction<Op::Err>
+where
+    Op: AsyncOperation + 'static,
+    Op::In: 'static,
+    Op::Out: 'static,
+{
+    AsyncCompensationAction {
+        operation: operation_idx,
+        action: Box::new(move || {
+            let operation = operation.clone();
+            let input = input.clone();
+            let output = output.clone();
+            Box::pin(async move { operation.compensate(input, output).await })
+        }),
+    }
+}
+
+/// AsyncFallibleTransaction is the asynchronous version of `FallibleTransaction`: a sequence of
+/// operations that are executed in a way that if any of the operations fails all the already
+/// performed operation's compensation actions got executed in reverse order.
+///
+/// In case of fatal errors (panic) and external executor failures it does not perform the
+/// compensation actions and the whole transaction gets retried.
+pub struct AsyncFallibleTransaction<Err> {
+    compensations: Vec<AsyncCompensationAction<Err>>,
+    observers: Observers<Err>,
+}
+
+impl<Err: Clone + 'static> AsyncFallibleTransaction<Err> {
+    fn new() -> Self {
+        Self {
+            compensations: Vec::new(),
+            observers: Observers::new(),
+        }
+    }
+
+    pub async fn execute<OpIn: Clone + 'static, OpOut: Clone + 'static>(
+        &mut self,
+        operation: impl AsyncOperation<In = OpIn, Out = OpOut, Err = Err> + 'static,
+        input: OpIn,
+    ) -> Result<OpOut, Err> {
+        let operation_idx = self.observers.next_operation();
+        self.observers.operation_started(operation_idx);
+        let result = operation.execute(input.clone()).await;
+        self.observers.operation_result(operation_idx, &result);
+        if let Ok(output) = &result {
+            self.compensations.push(compensation_action(
+                operation_idx,
+                operation,
+                input,
+                output.clone(),
+            ));
+        }
+        result
+    }
+
+    async fn on_fail(&mut self, failure: Err) -> TransactionFailure<Err> {
+        for compensation_action in self.compensations.drain(..).rev() {
+            let operation_idx = compensation_action.operation;
+            self.observers.compensation_started(operation_idx);
+            if let Err(compensation_failure) = compensation_action.execute().await {
+                self.observers
+                    .compensation_failed(operation_idx, &compensation_failure);
+                return TransactionFailure::FailedAndRolledBackPartially {
+                    failure,
+                    compensation_failure,
+                };
+            }
+            self.observers.compensation_succeeded(operation_idx);
+        }
+        TransactionFailure::FailedAndRolledBackCompletely(failure)
+    }
+}
+
+impl<Err: Debug + Clone + 'static> AsyncFallibleTransaction<Err> {
+    /// Registers an observer which gets notified about every subsequent step of this transaction
+    pub fn add_observer(&mut self, observer: impl TransactionObserver + 'static) {
+        self.observers.add(observer);
+    }
+}
+
+/// AsyncInfallibleTransaction is the asynchronous version of `InfallibleTransaction`: a sequence
+/// of operations that are executed in a way that if any of the operations or the underlying Golem
+/// executor fails, the whole transaction is going to be retried.
+///
+/// **User level failures** (represented by the `Result::Err` value of an operation) lead to
+/// performing the compensation actions of each already performed operation in reverse order.
+pub struct AsyncInfallibleTransaction {
+    begin_oplog_index: OplogIndex,
+    compensations: Vec<AsyncCompensationAction<Box<dyn Debug>>>,
+    observers: Observers<Box<dyn Debug>>,
+}
+
+impl AsyncInfallibleTransaction {
+    fn new(begin_oplog_index: OplogIndex) -> Self {
+        Self {
+            begin_oplog_index,
+            compensations: Vec::new(),
+            observers: Observers::new(),
+        }
+    }
+
+    /// Registers an observer which gets notified about every subsequent step of this transaction
+    pub fn add_observer(&mut self, observer: impl TransactionObserver + 'static) {
+        self.observers.add(observer);
+    }
+
+    pub async fn execute<
+        OpIn: Clone + 'static,
+        OpOut: Clone + 'static,
+        OpErr: Debug + Clone + 'static,
+    >(
+        &mut self,
+        operation: impl AsyncOperation<In = OpIn, Out = OpOut, Err = OpErr> + 'static,
+        input: OpIn,
+    ) -> OpOut {
+        let operation_idx = self.observers.next_operation();
+        self.observers.operation_started(operation_idx);
+        match operation.execute(input.clone()).await {
+            Ok(output) => {
+                self.observers.operation_succeeded(operation_idx);
+                let action = compensation_action(operation_idx, operation, input, output.clone());
+                self.compensations.push(AsyncCompensationAction {
+                    operation: operation_idx,
+                    action: Box::new(move || {
+                        let compensation = (action.action)();
+                        Box::pin(async move {
+                            compensation
+                                .await
+                                .map_err(|err| Box::new(err) as Box<dyn Debug>)
+                        })
+                    }),
+                });
+                output
+            }
+            Err(err) => {
+                self.observers.operation_failed(operation_idx, &err);
+                self.retry().await;
+                unreachable!()
+            }
+        }
+    }
+
+    /// Stop executing the transaction and retry from the beginning, after executing the compensation actions
+    pub async fn retry(&mut self) {
+        for compensation_action in self.compensations.drain(..).rev() {
+            let operation_idx = compensation_action.operation;
+            self.observers.compensation_started(operation_idx);
+            if let Err(err) = compensation_action.execute().await {
+                self.observers.compensation_failed(operation_idx, &err);
+                panic!("Compensation action failed: {err:?}");
+            }
+            self.observers.compensation_succeeded(operation_idx);
+        }
+        self.observers.transaction_retried();
+        set_oplog_index(self.begin_oplog_index);
+    }
+}
+
+/// A unified interface for the different types of asynchronous transactions, the
+/// asynchronous counterpart of `Transaction`.
+pub trait AsyncTransaction<Err>: Sized {
+    fn execute<OpIn: Clone + 'static, OpOut: Clone + 'static>(
+        &mut self,
+        operation: impl AsyncOperation<In = OpIn, Out = OpOut, Err = Err> + 'static,
+        input: OpIn,
+    ) -> impl Future<Output = Result<OpOut, Err>>;
+
+    fn fail(&mut self, error: Err) -> impl Future<Output = Result<(), Err>>;
+
+    fn run<Out>(
+        f: impl AsyncFnOnce(&mut Self) -> Result<Out, Err>,
+    ) -> impl Future<Output = TransactionResult<Out, Err>>;
+}
+
+impl<Err: Clone + 'static> AsyncTransaction<Err> for AsyncFallibleTransaction<Err> {
+    async fn execute<OpIn: Clone + 'static, OpOut: Clone + 'static>(
+        &mut self,
+        operation: impl AsyncOperation<In = OpIn, Out = OpOut, Err = Err> + 'static,
+        input: OpIn,
+    ) -> Result<OpOut, Err> {
+        AsyncFallibleTransaction::execute(self, operation, input).await
+    }
+
+    async fn fail(&mut self, error: Err) -> Result<(), Err> {
+        Err(error)
+    }
+
+    async fn run<Out>(
+        f: impl AsyncFnOnce(&mut Self) -> Result<Out, Err>,
+    ) -> TransactionResult<Out, Err> {
+        fallible_transaction_async(f).await
+    }
+}
+
+impl<Err: Debug + Clone + 'static> AsyncTransaction<Err> for AsyncInfallibleTransaction {
+    async fn execute<OpIn: Clone + 'static, OpOut: Clone + 'static>(
+        &mut self,
+        operation: impl AsyncOperation<In = OpIn, Out = OpOut, Err = Err> + 'static,
+        input: OpIn,
+    ) -> Result<OpOut, Err> {
+        Ok(AsyncInfallibleTransaction::execute(self, operation, input).await)
+    }
+
+    async fn fail(&mut self, error: Err) -> Result<(), Err> {
+        AsyncInfallibleTransaction::retry(self).await;
+        Err(error)
+    }
+
+    async fn run<Out>(
+        f: impl AsyncFnOnce(&mut Self) -> Result<Out, Err>,
+    ) -> TransactionResult<Out, Err> {
+        Ok(infallible_transaction_async(async |tx| f(tx).await.unwrap()).await)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::future::Future;
+    use std::pin::pin;
+    use std::rc::Rc;
+    use std::task::{Context, Poll, Waker};
+
+    use crate::{async_operation, fallible_transaction_async, TransactionFailure};
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let mut context = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(result) = future.as_mut().poll(&mut context) {
+                return result;
+            }
+        }
+    }
+
+    #[test]
+    fn compensations_run_in_reverse_order() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+
+        let step = |name: &'static str, fail: bool| {
+            let log1 = log.clone();
+            let log2 = log.clone();
+            async_operation(
+                move |input: u32| {
+                    let log = log1.clone();
+                    async move {
+                        log.borrow_mut().push(format!("{name} execute {input}"));
+                        if fail {
+                            Err(format!("{name} failed"))
+                        } else {
+                            Ok(input + 1)
+                        }
+                    }
+                },
+                move |input: u32, output: u32| {
+                    let log = log2.clone();
+                    async move {
+                        log.borrow_mut()
+                            .push(format!("{name} compensate {input} {output}"));
+                        Ok(())
+                    }
+                },
+            )
+        };
+
+        let op1 = step("op1", false);
+        let op2 = step("op2", false);
+        let op3 = step("op3", true);
+
+        let result: Result<u32, _> = block_on(fallible_transaction_async(async |tx| {
+            let a = tx.execute(op1, 1).await?;
+            let b = tx.execute(op2, a).await?;
+            tx.execute(op3, b).await
+        }));
+
+        assert!(matches!(
+            result,
+            Err(TransactionFailure::FailedAndRolledBackCompletely(err)) if err == "op3 failed"
+        ));
+        assert_eq!(
+            *log.borrow(),
+            vec![
+                "op1 execute 1",
+                "op2 execute 2",
+                "op3 execute 3",
+                "op2 compensate 2 3",
+                "op1 compensate 1 2",
+            ]
+        );
+    }
+
+    #[test]
+    fn successful_transaction_does_not_compensate() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let log1 = log.clone();
+
+        let op = async_operation(
+            |input: u32| async move { Ok::<_, String>(input * 2) },
+            move |_: u32, _: u32| {
+                let log = log1.clone();
+                async move {
+                    log.borrow_mut().push("compensate");
+                    Ok(())
+                }
+            },
+        );
+
+        let result = block_on(fallible_transaction_async(async |tx| {
+            tx.execute(op, 21).await
+        }));
+
+        assert_eq!(result.unwrap(), 42);
+        assert!(log.borrow().is_empty());
+    }
+}
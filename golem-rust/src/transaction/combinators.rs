@@ -0,0 +1,331 @@
+// Copyright 2024-2025 Golem Cloud
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::rc::Rc;
+
+use uuid::Uuid;
+
+use crate::generate_idempotency_key;
+use crate::transaction::{operation, Operation};
+
+/// Output of the operations built by combinators which need more than their visible result
+/// to perform the compensation action.
+///
+/// `value` is the result of the operation, while `state` is what the compensation action
+/// needs in addition to it (for example the original output of a mapped operation).
+#[derive(Clone, Debug)]
+pub struct Composite<T, S> {
+    pub value: T,
+    pub state: S,
+}
+
+impl<T, S> Composite<T, S> {
+    pub fn into_value(self) -> T {
+        self.value
+    }
+}
+
+/// Operation returned by `Operation::map_output`
+pub struct MapOutput<Op: Operation, U> {
+    operation: Op,
+    f: Rc<dyn Fn(Op::Out) -> U>,
+}
+
+impl<Op: Operation, U> MapOutput<Op, U> {
+    pub(crate) fn new(operation: Op, f: impl Fn(Op::Out) -> U + 'static) -> Self {
+        Self {
+            operation,
+            f: Rc::new(f),
+        }
+    }
+}
+
+impl<Op: Operation, U> Clone for MapOutput<Op, U> {
+    fn clone(&self) -> Self {
+        Self {
+            operation: self.operation.clone(),
+            f: self.f.clone(),
+        }
+    }
+}
+
+impl<Op: Operation, U: Clone> Operation for MapOutput<Op, U> {
+    type In = Op::In;
+    type Out = Composite<U, Op::Out>;
+    type Err = Op::Err;
+
+    fn execute(&self, input: Self::In) -> Result<Self::Out, Self::Err> {
+        let output = self.operation.execute(input)?;
+        Ok(Composite {
+            value: (self.f)(output.clone()),
+            state: output,
+        })
+    }
+
+    fn compensate(&self, input: Self::In, result: Self::Out) -> Result<(), Self::Err> {
+        self.operation.compensate(input, result.state)
+    }
+}
+
+/// Operation returned by `Operation::map_err`
+pub struct MapErr<Op: Operation, E> {
+    operation: Op,
+    f: Rc<dyn Fn(Op::Err) -> E>,
+}
+
+impl<Op: Operation, E> MapErr<Op, E> {
+    pub(crate) fn new(operation: Op, f: impl Fn(Op::Err) -> E + 'static) -> Self {
+        Self {
+            operation,
+            f: Rc::new(f),
+        }
+    }
+}
+
+impl<Op: Operation, E> Clone for MapErr<Op, E> {
+    fn clone(&self) -> Self {
+        Self {
+            operation: self.operation.clone(),
+            f: self.f.clone(),
+        }
+    }
+}
+
+impl<Op: Operation, E: Clone> Operation for MapErr<Op, E> {
+    type In = Op::In;
+    type Out = Op::Out;
+    type Err = E;
+
+    fn execute(&self, input: Self::In) -> Result<Self::Out, Self::Err> {
+        self.operation.execute(input).map_err(&*self.f)
+    }
+
+    fn compensate(&self, input: Self::In, result: Self::Out) -> Result<(), Self::Err> {
+        self.operation.compensate(input, result).map_err(&*self.f)
+    }
+}
+
+/// Operation returned by `Operation::and_then`.
+///
+/// If the second operation fails and the compensation action of the first one fails too, the
+/// error of the compensation action is returned and the error of the second operation is lost.
+#[derive(Clone)]
+pub struct AndThen<First, Second> {
+    first: First,
+    second: Second,
+}
+
+impl<First, Second> AndThen<First, Second> {
+    pub(crate) fn new(first: First, second: Second) -> Self {
+        Self { first, second }
+    }
+}
+
+impl<First, Second> Operation for AndThen<First, Second>
+where
+    First: Operation,
+    Second: Operation<In = First::Out, Err = First::Err>,
+{
+    type In = First::In;
+    type Out = Composite<Second::Out, First::Out>;
+    type Err = First::Err;
+
+    fn execute(&self, input: Self::In) -> Result<Self::Out, Self::Err> {
+        let first_output = self.first.execute(input.clone())?;
+        match self.second.execute(first_output.clone()) {
+            Ok(value) => Ok(Composite {
+                value,
+                state: first_output,
+            }),
+            Err(error) => {
+                self.first.compensate(input, first_output)?;
+                Err(error)
+            }
+        }
+    }
+
+    fn compensate(&self, input: Self::In, result: Self::Out) -> Result<(), Self::Err> {
+        self.second.compensate(result.state.clone(), result.value)?;
+        self.first.compensate(input, result.state)
+    }
+}
+
+/// Operation returned by `sequence`
+#[derive(Clone)]
+pub struct Sequence<Op: Operation> {
+    steps: Vec<(Op, Op::In)>,
+}
+
+impl<Op: Operation> Sequence<Op> {
+    /// Compensates the first `result.len()` steps in reverse order
+    fn compensate_all(&self, result: Vec<Op::Out>) -> Result<(), Op::Err> {
+        for ((operation, input), output) in self.steps.iter().zip(result).rev() {
+            operation.compensate(input.clone(), output)?;
+        }
+        Ok(())
+    }
+}
+
+impl<Op: Operation> Operation for Sequence<Op> {
+    type In = ();
+    type Out = Vec<Op::Out>;
+    type Err = Op::Err;
+
+    fn execute(&self, _input: Self::In) -> Result<Self::Out, Self::Err> {
+        let mut outputs = Vec::with_capacity(self.steps.len());
+        for (operation, input) in &self.steps {
+            match operation.execute(input.clone()) {
+                Ok(output) => outputs.push(output),
+                Err(error) => {
+                    self.compensate_all(outputs)?;
+                    return Err(error);
+                }
+            }
+        }
+        Ok(outputs)
+    }
+
+    fn compensate(&self, _input: Self::In, result: Self::Out) -> Result<(), Self::Err> {
+        self.compensate_all(result)
+    }
+}
+
+/// Operation returned by `with_idempotency_key`
+#[derive(Clone)]
+pub struct WithIdempotencyKey<Op> {
+    operation: Op,
+}
+
+impl<In: Clone, Op: Operation<In = (Uuid, In)>> Operation for WithIdempotencyKey<Op> {
+    type In = In;
+    type Out = Composite<Op::Out, Uuid>;
+    type Err = Op::Err;
+
+    fn execute(&self, input: Self::In) -> Result<Self::Out, Self::Err> {
+        let key = generate_idempotency_key();
+        Ok(Composite {
+            value: self.operation.execute((key, input))?,
+            state: key,
+        })
+    }
+
+    fn compensate(&self, input: Self::In, result: Self::Out) -> Result<(), Self::Err> {
+        self.operation
+            .compensate((result.state, input), result.value)
+    }
+}
+
+/// Combines a list of operations of the same type, each paired with its input, into a single
+/// operation executing them in order. The combined operation takes `()` as its input.
+///
+/// If one of the operations fails, the already executed ones are compensated in reverse order
+/// before returning the error. If one of these compensation actions fails, its error is returned
+/// instead. The compensation action of the sequence compensates all operations in reverse order.
+pub fn sequence<Op: Operation>(steps: Vec<(Op, Op::In)>) -> Sequence<Op> {
+    Sequence { steps }
+}
+
+/// Constructs an `Operation` which has nothing to undo, such as a read-only step.
+pub fn no_compensation<In: Clone, Out: Clone, Err: Clone>(
+    execute_fn: impl Fn(In) -> Result<Out, Err> + 'static,
+) -> impl Operation<In = In, Out = Out, Err = Err> {
+    operation(execute_fn, |_, _| Ok(()))
+}
+
+/// Wraps an operation which expects an idempotency key besides its input. The key is generated
+/// with `generate_idempotency_key` when the operation is executed, and the compensation action
+/// receives the same key.
+pub fn with_idempotency_key<In: Clone, Op: Operation<In = (Uuid, In)>>(
+    operation: Op,
+) -> WithIdempotencyKey<Op> {
+    WithIdempotencyKey { operation }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use crate::{fallible_transaction, no_compensation, operation, sequence, Operation};
+
+    #[test]
+    fn and_then_compensates_both_in_reverse_order() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+
+        let log1 = log.clone();
+        let reserve = operation(
+            |amount: u64| Ok::<_, String>(amount * 2),
+            move |_: u64, reserved| {
+                log1.borrow_mut().push(format!("release {reserved}"));
+                Ok(())
+            },
+        );
+        let log2 = log.clone();
+        let charge = operation(
+            |reserved: u64| Ok(format!("charge-{reserved}")),
+            move |_: u64, charge: String| {
+                log2.borrow_mut().push(format!("refund {charge}"));
+                Ok(())
+            },
+        );
+        let fail = no_compensation(|_: ()| Err::<(), _>("failed".to_string()));
+
+        let result = fallible_transaction(|tx| {
+            let charge = tx.execute(reserve.and_then(charge).map_output(|c| c.value.len()), 5)?;
+            assert_eq!(charge.value, 9);
+            tx.execute(fail, ())?;
+            Ok(())
+        });
+
+        assert!(result.is_err());
+        assert_eq!(*log.borrow(), vec!["refund charge-10", "release 10"]);
+    }
+
+    #[test]
+    fn failing_sequence_compensates_executed_operations() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+
+        let step = |log: Rc<RefCell<Vec<String>>>| {
+            operation(
+                |input: u32| {
+                    if input == 0 {
+                        Err("zero".to_string())
+                    } else {
+                        Ok(input)
+                    }
+                },
+                move |input: u32, _| {
+                    log.borrow_mut().push(format!("compensate {input}"));
+                    Ok(())
+                },
+            )
+            .map_err(|err| format!("step failed: {err}"))
+        };
+        let steps = sequence(vec![
+            (step(log.clone()), 1),
+            (step(log.clone()), 2),
+            (step(log.clone()), 0),
+        ]);
+
+        let result = fallible_transaction(|tx| tx.execute(steps, ()));
+
+        assert_eq!(
+            result.err().map(|failure| failure.to_string()),
+            Some(
+                "Transaction failed with step failed: zero and rolled back completely.".to_string()
+            )
+        );
+        assert_eq!(*log.borrow(), vec!["compensate 2", "compensate 1"]);
+    }
+}
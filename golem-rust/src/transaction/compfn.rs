@@ -12,6 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::future::Future;
+
 pub fn call_compensation_function<In, Out, Err>(
     f: impl CompensationFunction<In, Out, Err>,
     result: impl TupleOrUnit<Out>,
@@ -20,6 +22,14 @@ pub fn call_compensation_function<In, Out, Err>(
     f.call(result, input)
 }
 
+pub fn call_async_compensation_function<In, Out, Err>(
+    f: impl AsyncCompensationFunction<In, Out, Err>,
+    result: impl TupleOrUnit<Out>,
+    input: impl TupleOrUnit<In>,
+) -> impl Future<Output = Result<(), Err>> {
+    f.call(result, input)
+}
+
 pub trait TupleOrUnit<T> {
     fn into(self) -> T;
 }
@@ -28,6 +38,14 @@ pub trait CompensationFunction<In, Out, Err> {
     fn call(self, result: impl TupleOrUnit<Out>, input: impl TupleOrUnit<In>) -> Result<(), Err>;
 }
 
+pub trait AsyncCompensationFunction<In, Out, Err> {
+    fn call(
+        self,
+        result: impl TupleOrUnit<Out>,
+        input: impl TupleOrUnit<In>,
+    ) -> impl Future<Output = Result<(), Err>>;
+}
+
 impl<F, Err> CompensationFunction<(), (), (Err,)> for F
 where
     F: FnOnce() -> Result<(), Err>,
@@ -56,6 +74,36 @@ where
     }
 }
 
+impl<F, Fut, Err> AsyncCompensationFunction<(), (), (Err,)> for F
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<(), Err>>,
+{
+    async fn call(
+        self,
+        _result: impl TupleOrUnit<()>,
+        _input: impl TupleOrUnit<()>,
+    ) -> Result<(), (Err,)> {
+        self().await.map_err(|e| (e,))?;
+        Ok(())
+    }
+}
+
+impl<F, Fut, Out, Err> AsyncCompensationFunction<(), (Out,), (Err,)> for F
+where
+    F: FnOnce(Out) -> Fut,
+    Fut: Future<Output = Result<(), Err>>,
+{
+    async fn call(
+        self,
+        out: impl TupleOrUnit<(Out,)>,
+        _input: impl TupleOrUnit<()>,
+    ) -> Result<(), (Err,)> {
+        let (out,) = out.into();
+        self(out).await.map_err(|err| (err,))
+    }
+}
+
 impl<T> TupleOrUnit<()> for T {
     fn into(self) {}
 }
@@ -80,6 +128,27 @@ macro_rules! compensation_function {
     }
 }
 
+macro_rules! async_compensation_function {
+    ($($ty:ident),*) => {
+        impl<F, Fut, $($ty),*, Out, Err> AsyncCompensationFunction<($($ty),*,), (Out,), (Err,)> for F
+        where
+            F: FnOnce(Out, $($ty),*) -> Fut,
+            Fut: Future<Output = Result<(), Err>>,
+        {
+            async fn call(
+                self,
+                out: impl TupleOrUnit<(Out,)>,
+                input: impl TupleOrUnit<($($ty),*,)>,
+            ) -> Result<(), (Err,)> {
+                #[allow(non_snake_case)]
+                let ( $($ty,)+ ) = input.into();
+                let (out,) = out.into();
+                self(out, $($ty),*).await.map_err(|err| (err,))
+            }
+        }
+    }
+}
+
 macro_rules! tuple_or_unit {
     ($($ty:ident),*) => {
         impl<$($ty),*> TupleOrUnit<($($ty,)*)> for ($($ty,)*) {
@@ -113,3 +182,4 @@ macro_rules! generate_for_tuples {
 
 generate_for_tuples!(tuple_or_unit);
 generate_for_tuples!(compensation_function);
+generate_for_tuples!(async_compensation_function);
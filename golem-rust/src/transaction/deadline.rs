@@ -0,0 +1,88 @@
+// Copyright 2024-2025 Golem Cloud
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt::{Debug, Display, Formatter};
+use std::time::Duration;
+
+use golem_wasm_rpc::wasi::clocks::wall_clock::{now, Datetime};
+
+/// The point in time after which a transaction is no longer allowed to continue.
+///
+/// Deadlines are based on the wall clock. Relative deadlines are resolved to a point in time
+/// when the transaction starts. As Golem records the result of every wall clock query in the
+/// oplog, the resolved deadline stays the same when the worker gets recovered or the
+/// transaction gets retried.
+#[derive(Clone, Copy, Debug)]
+pub enum Deadline {
+    /// The transaction must finish within the given duration from its start
+    After(Duration),
+    /// The transaction must finish before the given point in time
+    At(Datetime),
+}
+
+impl Deadline {
+    pub(crate) fn resolve(self) -> Datetime {
+        match self {
+            Deadline::After(timeout) => from_duration(to_duration(now()) + timeout),
+            Deadline::At(datetime) => datetime,
+        }
+    }
+}
+
+impl From<Duration> for Deadline {
+    fn from(value: Duration) -> Self {
+        Deadline::After(value)
+    }
+}
+
+impl From<Datetime> for Deadline {
+    fn from(value: Datetime) -> Self {
+        Deadline::At(value)
+    }
+}
+
+/// Error indicating that the deadline of a transaction has passed.
+///
+/// Fallible transactions with a deadline require their error type to be constructible from this,
+/// as it is returned from the `execute` call which was attempted after the deadline.
+#[derive(Clone, Copy, Debug)]
+pub struct DeadlineExceeded {
+    pub deadline: Datetime,
+}
+
+impl Display for DeadlineExceeded {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Transaction deadline exceeded ({}.{:09}s)",
+            self.deadline.seconds, self.deadline.nanoseconds
+        )
+    }
+}
+
+/// Checks whether the given deadline has passed, using the wall clock
+pub(crate) fn is_exceeded(deadline: Datetime) -> bool {
+    to_duration(now()) >= to_duration(deadline)
+}
+
+fn to_duration(datetime: Datetime) -> Duration {
+    Duration::new(datetime.seconds, datetime.nanoseconds)
+}
+
+fn from_duration(duration: Duration) -> Datetime {
+    Datetime {
+        seconds: duration.as_secs(),
+        nanoseconds: duration.subsec_nanos(),
+    }
+}
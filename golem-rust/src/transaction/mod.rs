@@ -12,15 +12,29 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod async_transaction;
+mod combinators;
 mod compfn;
+mod deadline;
+mod observer;
+mod rdbms;
 
+use std::convert::Infallible;
 use std::fmt::{Debug, Display, Formatter};
 use std::rc::Rc;
 
 use crate::bindings::golem::api::host::{get_oplog_index, set_oplog_index, OplogIndex};
 use crate::mark_atomic_operation;
+use crate::transaction::deadline::is_exceeded;
+use crate::transaction::observer::Observers;
+use golem_wasm_rpc::wasi::clocks::wall_clock::Datetime;
 
+pub use async_transaction::*;
+pub use combinators::*;
 pub use compfn::*;
+pub use deadline::{Deadline, DeadlineExceeded};
+pub use observer::{AuditObserver, TransactionObserver};
+pub use rdbms::*;
 
 /// Represents an atomic operation of the transaction which has a rollback action.
 ///
@@ -36,6 +50,37 @@ pub trait Operation: Clone {
 
     /// Executes a compensation action for the operation.
     fn compensate(&self, input: Self::In, result: Self::Out) -> Result<(), Self::Err>;
+
+    /// Transforms the output of the operation. The compensation action still receives the
+    /// original output, kept in the `state` of the returned `Composite`.
+    fn map_output<U: Clone>(self, f: impl Fn(Self::Out) -> U + 'static) -> MapOutput<Self, U>
+    where
+        Self: Sized,
+    {
+        MapOutput::new(self, f)
+    }
+
+    /// Transforms the errors of both the operation and its compensation action.
+    fn map_err<E: Clone>(self, f: impl Fn(Self::Err) -> E + 'static) -> MapErr<Self, E>
+    where
+        Self: Sized,
+    {
+        MapErr::new(self, f)
+    }
+
+    /// Composes this operation with another one which receives this operation's output as its
+    /// input. If the second operation fails, the first one gets compensated before returning
+    /// the error, or the error of the compensation action if that fails too. The compensation of
+    /// the composite operation undoes both in reverse order.
+    fn and_then<Next: Operation<In = Self::Out, Err = Self::Err>>(
+        self,
+        next: Next,
+    ) -> AndThen<Self, Next>
+    where
+        Self: Sized,
+    {
+        AndThen::new(self, next)
+    }
 }
 
 /// Constructs an `Operation` from two closures: one for executing the operation,
@@ -87,6 +132,7 @@ pub type TransactionResult<Out, Err> = Result<Out, TransactionFailure<Err>>;
 
 /// The result of a transaction execution that failed.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum TransactionFailure<Err> {
     /// One of the operations failed with an error, and the transaction was fully rolled back.
     FailedAndRolledBackCompletely(Err),
@@ -96,6 +142,13 @@ pub enum TransactionFailure<Err> {
         failure: Err,
         compensation_failure: Err,
     },
+    /// The deadline of the transaction passed before it could complete. The compensation actions
+    /// of the already performed operations were executed, unless one of them failed with
+    /// `compensation_failure`.
+    TimedOut {
+        deadline: Datetime,
+        compensation_failure: Option<Err>,
+    },
 }
 
 impl<Err: Display> Display for TransactionFailure<Err> {
@@ -111,6 +164,22 @@ impl<Err: Display> Display for TransactionFailure<Err> {
                 f,
                 "Transaction failed with {failure} and rolled back partially; compensation failed with: {compensation_failure}."
             ),
+            TransactionFailure::TimedOut {
+                deadline,
+                compensation_failure: None,
+            } => write!(
+                f,
+                "{} and the transaction rolled back completely.",
+                DeadlineExceeded { deadline: *deadline }
+            ),
+            TransactionFailure::TimedOut {
+                deadline,
+                compensation_failure: Some(compensation_failure),
+            } => write!(
+                f,
+                "{} and the transaction rolled back partially; compensation failed with: {compensation_failure}.",
+                DeadlineExceeded { deadline: *deadline }
+            ),
         }
     }
 }
@@ -128,6 +197,22 @@ pub fn fallible_transaction<Out, Err: Clone + 'static>(
     }
 }
 
+/// Same as `fallible_transaction`, but once the given deadline has passed, no more operations
+/// are executed. The `execute` call attempted after the deadline returns with an error constructed
+/// from `DeadlineExceeded`, and when the transaction fails, the compensation actions are executed
+/// and the result is `TransactionFailure::TimedOut`.
+pub fn fallible_transaction_with_deadline<Out, Err: From<DeadlineExceeded> + Clone + 'static>(
+    deadline: impl Into<Deadline>,
+    f: impl FnOnce(&mut FallibleTransaction<Err>) -> Result<Out, Err>,
+) -> TransactionResult<Out, Err> {
+    let mut transaction = FallibleTransaction::new();
+    transaction.deadline = Some((deadline.into().resolve(), Err::from));
+    match f(&mut transaction) {
+        Ok(output) => Ok(output),
+        Err(error) => Err(transaction.on_fail(error)),
+    }
+}
+
 /// Retry the transaction in case of failure. If any operation returns with a failure, all
 /// the already executed successful operation's compensation actions are executed in reverse order
 /// and the transaction gets retried, using Golem's active retry policy.
@@ -138,6 +223,31 @@ pub fn infallible_transaction<Out>(f: impl FnOnce(&mut InfallibleTransaction) ->
     f(&mut transaction)
 }
 
+/// Same as `infallible_transaction`, but the transaction is only retried until the given deadline.
+///
+/// If an operation fails or is about to be started after the deadline, the compensation actions
+/// are executed and, instead of retrying, the transaction returns with
+/// `TransactionFailure::TimedOut`. As failing compensation actions panic in infallible
+/// transactions, its `compensation_failure` is always `None`.
+pub fn infallible_transaction_with_deadline<Out>(
+    deadline: impl Into<Deadline>,
+    f: impl FnOnce(&mut InfallibleTransaction) -> Out,
+) -> TransactionResult<Out, Infallible> {
+    let deadline = deadline.into().resolve();
+    let oplog_index = get_oplog_index();
+    let _atomic_region = mark_atomic_operation();
+    // Retrying the transaction jumps back here, so this check is what ends a timed out transaction
+    if is_exceeded(deadline) {
+        return Err(TransactionFailure::TimedOut {
+            deadline,
+            compensation_failure: None,
+        });
+    }
+    let mut transaction = InfallibleTransaction::new(oplog_index);
+    transaction.deadline = Some(deadline);
+    Ok(f(&mut transaction))
+}
+
 /// Same as `infallible_transaction`, but with strong rollback guarantees. The compensation actions
 /// are guaranteed to be always executed before the transaction gets retried, even if it
 /// fails due to a panic or an external executor failure.
@@ -164,6 +274,7 @@ where
 /// Helper struct for coupling compensation action and the result of the operation.
 #[allow(clippy::type_complexity)]
 struct CompensationAction<Err> {
+    operation: usize,
     action: Box<dyn Fn() -> Result<(), Err>>,
 }
 
@@ -179,14 +290,21 @@ impl<Err> CompensationAction<Err> {
 ///
 /// In case of fatal errors (panic) and external executor failures it does not perform the
 /// compensation actions and the whole transaction gets retried.
+#[allow(clippy::type_complexity)]
 pub struct FallibleTransaction<Err> {
     compensations: Vec<CompensationAction<Err>>,
+    observers: Observers<Err>,
+    deadline: Option<(Datetime, fn(DeadlineExceeded) -> Err)>,
+    timed_out: bool,
 }
 
 impl<Err: Clone + 'static> FallibleTransaction<Err> {
     fn new() -> Self {
         Self {
             compensations: Vec::new(),
+            observers: Observers::new(),
+            deadline: None,
+            timed_out: false,
         }
     }
 
@@ -195,11 +313,24 @@ impl<Err: Clone + 'static> FallibleTransaction<Err> {
         operation: impl Operation<In = OpIn, Out = OpOut, Err = Err> + 'static,
         input: OpIn,
     ) -> Result<OpOut, Err> {
+        if let Some((deadline, to_error)) = self.deadline {
+            if self.timed_out || is_exceeded(deadline) {
+                if !self.timed_out {
+                    self.timed_out = true;
+                    self.observers.transaction_timed_out();
+                }
+                return Err(to_error(DeadlineExceeded { deadline }));
+            }
+        }
+        let operation_idx = self.observers.next_operation();
+        self.observers.operation_started(operation_idx);
         let result = operation.execute(input.clone());
+        self.observers.operation_result(operation_idx, &result);
         if let Ok(output) = &result {
             let cloned_op = operation.clone();
             let cloned_out = output.clone();
             self.compensations.push(CompensationAction {
+                operation: operation_idx,
                 action: Box::new(move || cloned_op.compensate(input.clone(), cloned_out.clone())),
             });
         }
@@ -207,15 +338,41 @@ impl<Err: Clone + 'static> FallibleTransaction<Err> {
     }
 
     fn on_fail(&mut self, failure: Err) -> TransactionFailure<Err> {
-        for compensation_action in self.compensations.drain(..).rev() {
-            if let Err(compensation_failure) = compensation_action.execute() {
-                return TransactionFailure::FailedAndRolledBackPartially {
+        let compensation_result = self.compensate();
+        match (self.deadline, self.timed_out) {
+            (Some((deadline, _)), true) => TransactionFailure::TimedOut {
+                deadline,
+                compensation_failure: compensation_result.err(),
+            },
+            _ => match compensation_result {
+                Ok(()) => TransactionFailure::FailedAndRolledBackCompletely(failure),
+                Err(compensation_failure) => TransactionFailure::FailedAndRolledBackPartially {
                     failure,
                     compensation_failure,
-                };
+                },
+            },
+        }
+    }
+
+    fn compensate(&mut self) -> Result<(), Err> {
+        for compensation_action in self.compensations.drain(..).rev() {
+            let operation_idx = compensation_action.operation;
+            self.observers.compensation_started(operation_idx);
+            if let Err(compensation_failure) = compensation_action.execute() {
+                self.observers
+                    .compensation_failed(operation_idx, &compensation_failure);
+                return Err(compensation_failure);
             }
+            self.observers.compensation_succeeded(operation_idx);
         }
-        TransactionFailure::FailedAndRolledBackCompletely(failure)
+        Ok(())
+    }
+}
+
+impl<Err: Debug + Clone + 'static> FallibleTransaction<Err> {
+    /// Registers an observer which gets notified about every subsequent step of this transaction
+    pub fn add_observer(&mut self, observer: impl TransactionObserver + 'static) {
+        self.observers.add(observer);
     }
 }
 
@@ -231,7 +388,9 @@ impl<Err: Clone + 'static> FallibleTransaction<Err> {
 /// rollback actions.
 pub struct InfallibleTransaction {
     begin_oplog_index: OplogIndex,
-    compensations: Vec<CompensationAction<()>>,
+    compensations: Vec<CompensationAction<Box<dyn Debug>>>,
+    observers: Observers<Box<dyn Debug>>,
+    deadline: Option<Datetime>,
 }
 
 impl InfallibleTransaction {
@@ -239,9 +398,16 @@ impl InfallibleTransaction {
         Self {
             begin_oplog_index,
             compensations: Vec::new(),
+            observers: Observers::new(),
+            deadline: None,
         }
     }
 
+    /// Registers an observer which gets notified about every subsequent step of this transaction
+    pub fn add_observer(&mut self, observer: impl TransactionObserver + 'static) {
+        self.observers.add(observer);
+    }
+
     pub fn execute<
         OpIn: Clone + 'static,
         OpOut: Clone + 'static,
@@ -251,31 +417,53 @@ impl InfallibleTransaction {
         operation: impl Operation<In = OpIn, Out = OpOut, Err = OpErr> + 'static,
         input: OpIn,
     ) -> OpOut {
+        if self.deadline.is_some_and(is_exceeded) {
+            self.retry();
+            unreachable!()
+        }
+        let operation_idx = self.observers.next_operation();
+        self.observers.operation_started(operation_idx);
         match operation.execute(input.clone()) {
             Ok(output) => {
+                self.observers.operation_succeeded(operation_idx);
                 let cloned_op = operation.clone();
                 let cloned_out = output.clone();
                 self.compensations.push(CompensationAction {
+                    operation: operation_idx,
                     action: Box::new(move || {
                         cloned_op
                             .compensate(input.clone(), cloned_out.clone())
-                            .expect("Compensation action failed");
-                        Ok(())
+                            .map_err(|err| Box::new(err) as Box<dyn Debug>)
                     }),
                 });
                 output
             }
-            Err(_) => {
+            Err(err) => {
+                self.observers.operation_failed(operation_idx, &err);
                 self.retry();
                 unreachable!()
             }
         }
     }
 
-    /// Stop executing the transaction and retry from the beginning, after executing the compensation actions
+    /// Stop executing the transaction and retry from the beginning, after executing the compensation actions.
+    ///
+    /// If the transaction has a deadline which already passed, it is not going to be retried
+    /// but returns with `TransactionFailure::TimedOut` after the compensation actions.
     pub fn retry(&mut self) {
         for compensation_action in self.compensations.drain(..).rev() {
-            let _ = compensation_action.execute();
+            let operation_idx = compensation_action.operation;
+            self.observers.compensation_started(operation_idx);
+            if let Err(err) = compensation_action.execute() {
+                self.observers.compensation_failed(operation_idx, &err);
+                panic!("Compensation action failed: {err:?}");
+            }
+            self.observers.compensation_succeeded(operation_idx);
+        }
+        if self.deadline.is_some_and(is_exceeded) {
+            self.observers.transaction_timed_out();
+        } else {
+            self.observers.transaction_retried();
         }
         set_oplog_index(self.begin_oplog_index);
     }
@@ -336,9 +524,15 @@ impl<Err: Debug + Clone + 'static> Transaction<Err> for InfallibleTransaction {
 #[cfg(test)]
 mod tests {
     use std::cell::RefCell;
+    use std::fmt::Debug;
     use std::rc::Rc;
+    use std::time::Duration;
 
-    use crate::{fallible_transaction, infallible_transaction, operation};
+    use crate::{
+        fallible_transaction, fallible_transaction_with_deadline, infallible_transaction,
+        infallible_transaction_with_deadline, operation, DeadlineExceeded, TransactionFailure,
+        TransactionObserver,
+    };
 
     // Not a real test, just verifying that the code compiles
     #[test]
@@ -433,6 +627,126 @@ mod tests {
         println!("{log:?}");
         println!("{result:?}");
     }
+
+    // Not a real test, just verifying that the code compiles
+    #[test]
+    #[ignore]
+    fn tx_deadline_test() {
+        #[allow(dead_code)]
+        #[derive(Clone, Debug)]
+        enum Error {
+            Domain(String),
+            TimedOut(DeadlineExceeded),
+        }
+
+        impl From<DeadlineExceeded> for Error {
+            fn from(value: DeadlineExceeded) -> Self {
+                Error::TimedOut(value)
+            }
+        }
+
+        let op1 = operation(
+            |input: String| Ok::<_, Error>(input.len()),
+            |_: String, _| Ok(()),
+        );
+        let op2 = operation(
+            |_: ()| Err::<(), _>(Error::Domain("op2 error".to_string())),
+            |_: (), _| Ok(()),
+        );
+
+        let result = fallible_transaction_with_deadline(Duration::from_secs(30), |tx| {
+            let length = tx.execute(op1.clone(), "hello".to_string())?;
+            tx.execute(op2.clone(), ())?;
+            Ok(length)
+        });
+        if let Err(TransactionFailure::TimedOut { deadline, .. }) = result {
+            println!("Timed out at {deadline:?}");
+        }
+
+        let result = infallible_transaction_with_deadline(Duration::from_secs(30), |tx| {
+            let length = tx.execute(op1, "hello".to_string());
+            tx.execute(op2, ());
+            length
+        });
+        if let Err(TransactionFailure::TimedOut { deadline, .. }) = result {
+            println!("Timed out at {deadline:?}");
+        }
+    }
+
+    #[test]
+    fn observer_sees_operations_and_compensations() {
+        struct RecordingObserver(Rc<RefCell<Vec<String>>>);
+
+        impl TransactionObserver for RecordingObserver {
+            fn operation_started(&self, operation: usize) {
+                self.0.borrow_mut().push(format!("started {operation}"));
+            }
+
+            fn operation_succeeded(&self, operation: usize) {
+                self.0.borrow_mut().push(format!("succeeded {operation}"));
+            }
+
+            fn operation_failed(&self, operation: usize, error: &dyn Debug) {
+                self.0
+                    .borrow_mut()
+                    .push(format!("failed {operation} {error:?}"));
+            }
+
+            fn compensation_started(&self, operation: usize) {
+                self.0
+                    .borrow_mut()
+                    .push(format!("compensating {operation}"));
+            }
+
+            fn compensation_succeeded(&self, operation: usize) {
+                self.0.borrow_mut().push(format!("compensated {operation}"));
+            }
+
+            fn compensation_failed(&self, operation: usize, error: &dyn Debug) {
+                self.0
+                    .borrow_mut()
+                    .push(format!("compensation failed {operation} {error:?}"));
+            }
+        }
+
+        let events = Rc::new(RefCell::new(Vec::new()));
+
+        let op1 = operation(|_: ()| Ok::<_, String>(()), |_: (), _| Ok(()));
+        let op2 = operation(
+            |_: ()| Ok::<_, String>(()),
+            |_: (), _| Err("op2 compensation error".to_string()),
+        );
+        let op3 = operation(
+            |_: ()| Err::<(), _>("op3 error".to_string()),
+            |_: (), _| Ok(()),
+        );
+
+        let result = fallible_transaction(|tx| {
+            tx.add_observer(RecordingObserver(events.clone()));
+            tx.execute(op1, ())?;
+            tx.execute(op2, ())?;
+            tx.execute(op3, ())?;
+            Ok(())
+        });
+
+        assert!(matches!(
+            result,
+            Err(TransactionFailure::FailedAndRolledBackPartially { .. })
+        ));
+        assert_eq!(
+            *events.borrow(),
+            vec![
+                "started 0",
+                "succeeded 0",
+                "started 1",
+                "succeeded 1",
+                "started 2",
+                "failed 2 \"op3 error\"",
+                "compensating 1",
+                "compensation failed 1 \"op2 compensation error\"",
+            ]
+        );
+    }
 }
 
 #[cfg(test)]
@@ -440,7 +754,10 @@ mod tests {
 mod macro_tests {
     use golem_rust_macro::golem_operation;
 
-    use crate::{fallible_transaction, infallible_transaction};
+    use crate::{
+        fallible_transaction, fallible_transaction_async, infallible_transaction,
+        infallible_transaction_async,
+    };
 
     mod golem_rust {
         pub use crate::*;
@@ -490,6 +807,32 @@ mod macro_tests {
         Ok(())
     }
 
+    #[golem_operation(compensation=test_async_compensation)]
+    async fn test_async_operation(input1: u64, input2: String) -> Result<bool, String> {
+        println!("Async op input: {input1}, {input2}");
+        Ok(true)
+    }
+
+    async fn test_async_compensation(
+        result: bool,
+        input1: u64,
+        input2: String,
+    ) -> Result<(), String> {
+        println!("Async compensation for {result}: {input1}, {input2}");
+        Ok(())
+    }
+
+    #[golem_operation(compensation=test_async_compensation_2)]
+    async fn test_async_operation_2(input: u64) -> Result<(), String> {
+        println!("Async op input: {input}");
+        Ok(())
+    }
+
+    async fn test_async_compensation_2() -> Result<(), String> {
+        println!("Async compensation, not using any input");
+        Ok(())
+    }
+
     // Not a real test, just verifying that the code compiles
     #[test]
     #[ignore]
@@ -519,4 +862,30 @@ mod macro_tests {
 
         println!("{result:?}");
     }
+
+    // Not a real test, just verifying that the code compiles
+    #[allow(dead_code)]
+    async fn async_tx_test_1() {
+        let result = fallible_transaction_async(async |tx| {
+            println!("Executing the annotated async function as an operation directly");
+            tx.test_async_operation(1, "test".to_string()).await?;
+            tx.test_async_operation_2(2).await?;
+            Ok(11)
+        })
+        .await;
+
+        println!("{result:?}");
+    }
+
+    // Not a real test, just verifying that the code compiles
+    #[allow(dead_code)]
+    async fn async_tx_test_2() {
+        let result = infallible_transaction_async(async |tx| {
+            let _ = tx.test_async_operation(1, "test".to_string()).await;
+            11
+        })
+        .await;
+
+        println!("{result:?}");
+    }
 }
@@ -0,0 +1,233 @@
+// Copyright 2024-2025 Golem Cloud
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+use crate::bindings::golem::api::context::{start_span, AttributeValue, Span};
+use crate::bindings::wasi::logging::logging::{log, Level};
+
+/// Receives notifications about the steps performed by a transaction.
+///
+/// Observers are registered per transaction with `add_observer`. Operations are identified by
+/// their zero-based position in the transaction, and compensation events refer to the position
+/// of the operation being compensated. All methods have empty default implementations.
+pub trait TransactionObserver {
+    fn operation_started(&self, _operation: usize) {}
+    fn operation_succeeded(&self, _operation: usize) {}
+    fn operation_failed(&self, _operation: usize, _error: &dyn Debug) {}
+    fn compensation_started(&self, _operation: usize) {}
+    fn compensation_succeeded(&self, _operation: usize) {}
+    fn compensation_failed(&self, _operation: usize, _error: &dyn Debug) {}
+    fn transaction_retried(&self) {}
+    fn transaction_timed_out(&self) {}
+}
+
+/// The set of observers registered for a single transaction
+pub(crate) struct Observers<Err> {
+    observers: Vec<Box<dyn TransactionObserver>>,
+    as_debug: Option<fn(&Err) -> &dyn Debug>,
+    next_operation: usize,
+}
+
+impl<Err> Observers<Err> {
+    pub fn new() -> Self {
+        Self {
+            observers: Vec::new(),
+            as_debug: None,
+            next_operation: 0,
+        }
+    }
+
+    /// Assigns the next operation index
+    pub fn next_operation(&mut self) -> usize {
+        let operation = self.next_operation;
+        self.next_operation += 1;
+        operation
+    }
+
+    pub fn operation_started(&self, operation: usize) {
+        self.notify(|observer| observer.operation_started(operation));
+    }
+
+    pub fn operation_succeeded(&self, operation: usize) {
+        self.notify(|observer| observer.operation_succeeded(operation));
+    }
+
+    pub fn operation_failed(&self, operation: usize, error: &dyn Debug) {
+        self.notify(|observer| observer.operation_failed(operation, error));
+    }
+
+    pub fn compensation_started(&self, operation: usize) {
+        self.notify(|observer| observer.compensation_started(operation));
+    }
+
+    pub fn compensation_succeeded(&self, operation: usize) {
+        self.notify(|observer| observer.compensation_succeeded(operation));
+    }
+
+    pub fn compensation_failed(&self, operation: usize, error: &Err) {
+        if let Some(as_debug) = self.as_debug {
+            self.notify(|observer| observer.compensation_failed(operation, as_debug(error)));
+        }
+    }
+
+    pub fn transaction_retried(&self) {
+        self.notify(|observer| observer.transaction_retried());
+    }
+
+    pub fn transaction_timed_out(&self) {
+        self.notify(|observer| observer.transaction_timed_out());
+    }
+
+    pub fn operation_result<Out>(&self, operation: usize, result: &Result<Out, Err>) {
+        match result {
+            Ok(_) => self.operation_succeeded(operation),
+            Err(error) => {
+                if let Some(as_debug) = self.as_debug {
+                    self.operation_failed(operation, as_debug(error))
+                }
+            }
+        }
+    }
+
+    fn notify(&self, f: impl Fn(&dyn TransactionObserver)) {
+        for observer in &self.observers {
+            f(observer.as_ref());
+        }
+    }
+}
+
+impl<Err: Debug> Observers<Err> {
+    pub fn add(&mut self, observer: impl TransactionObserver + 'static) {
+        self.as_debug = Some(as_debug::<Err>);
+        self.observers.push(Box::new(observer));
+    }
+}
+
+fn as_debug<T: Debug>(value: &T) -> &dyn Debug {
+    value
+}
+
+/// A `TransactionObserver` recording the transaction for auditing purposes.
+///
+/// It starts a span (using Golem's invocation context API) for the whole transaction and for each
+/// operation and compensation action, and emits log lines for every event through `wasi:logging`.
+pub struct AuditObserver {
+    name: String,
+    _transaction_span: Span,
+    spans: RefCell<HashMap<(bool, usize), Span>>,
+}
+
+impl AuditObserver {
+    /// Creates an observer for a transaction identified by `name` in the spans and log lines.
+    pub fn new(name: impl Into<String>) -> Self {
+        let name = name.into();
+        let transaction_span = start_span(&name);
+        Self {
+            name,
+            _transaction_span: transaction_span,
+            spans: RefCell::new(HashMap::new()),
+        }
+    }
+
+    fn start(&self, compensation: bool, operation: usize) {
+        let kind = if compensation {
+            "compensation"
+        } else {
+            "operation"
+        };
+        let span = start_span(&format!("{}/{kind}-{operation}", self.name));
+        span.set_attribute("transaction", &AttributeValue::String(self.name.clone()));
+        span.set_attribute("operation", &AttributeValue::String(operation.to_string()));
+        self.spans
+            .borrow_mut()
+            .insert((compensation, operation), span);
+        log(
+            Level::Info,
+            &self.name,
+            &format!("{kind} {operation} started"),
+        );
+    }
+
+    fn finish(&self, compensation: bool, operation: usize, error: Option<&dyn Debug>) {
+        let kind = if compensation {
+            "compensation"
+        } else {
+            "operation"
+        };
+        let outcome = match error {
+            Some(error) => {
+                let level = if compensation {
+                    Level::Error
+                } else {
+                    Level::Warn
+                };
+                log(
+                    level,
+                    &self.name,
+                    &format!("{kind} {operation} failed: {error:?}"),
+                );
+                "failed"
+            }
+            None => {
+                log(
+                    Level::Info,
+                    &self.name,
+                    &format!("{kind} {operation} succeeded"),
+                );
+                "succeeded"
+            }
+        };
+        if let Some(span) = self.spans.borrow_mut().remove(&(compensation, operation)) {
+            span.set_attribute("outcome", &AttributeValue::String(outcome.to_string()));
+            span.finish();
+        }
+    }
+}
+
+impl TransactionObserver for AuditObserver {
+    fn operation_started(&self, operation: usize) {
+        self.start(false, operation);
+    }
+
+    fn operation_succeeded(&self, operation: usize) {
+        self.finish(false, operation, None);
+    }
+
+    fn operation_failed(&self, operation: usize, error: &dyn Debug) {
+        self.finish(false, operation, Some(error));
+    }
+
+    fn compensation_started(&self, operation: usize) {
+        self.start(true, operation);
+    }
+
+    fn compensation_succeeded(&self, operation: usize) {
+        self.finish(true, operation, None);
+    }
+
+    fn compensation_failed(&self, operation: usize, error: &dyn Debug) {
+        self.finish(true, operation, Some(error));
+    }
+
+    fn transaction_retried(&self) {
+        log(Level::Warn, &self.name, "transaction retried");
+    }
+
+    fn transaction_timed_out(&self) {
+        log(Level::Error, &self.name, "transaction timed out");
+    }
+}
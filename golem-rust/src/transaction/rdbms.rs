@@ -0,0 +1,428 @@
+// Copyright 2024-2025 Golem Cloud
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::cell::Cell;
+use std::fmt::Debug;
+use std::rc::Rc;
+
+use crate::bindings::golem::rdbms::{mysql, postgres};
+use crate::transaction::{operation, Operation};
+
+/// Common interface of the `db-transaction` resources of the supported `golem:rdbms` databases.
+pub trait RdbmsTransaction: Sized + 'static {
+    type Connection;
+    type Value: 'static;
+    type Result: 'static;
+    type Error: Clone + Debug + 'static;
+
+    fn begin(connection: &Self::Connection) -> Result<Self, Self::Error>;
+    fn query(&self, statement: &str, params: Vec<Self::Value>)
+        -> Result<Self::Result, Self::Error>;
+    fn execute(&self, statement: &str, params: Vec<Self::Value>) -> Result<u64, Self::Error>;
+    fn commit(&self) -> Result<(), Self::Error>;
+    fn rollback(&self) -> Result<(), Self::Error>;
+
+    /// Constructs a database error not coming from the database itself
+    fn other_error(message: String) -> Self::Error;
+}
+
+impl RdbmsTransaction for postgres::DbTransaction {
+    type Connection = postgres::DbConnection;
+    type Value = postgres::DbValue;
+    type Result = postgres::DbResult;
+    type Error = postgres::Error;
+
+    fn begin(connection: &Self::Connection) -> Result<Self, Self::Error> {
+        connection.begin_transaction()
+    }
+
+    fn query(
+        &self,
+        statement: &str,
+        params: Vec<Self::Value>,
+    ) -> Result<Self::Result, Self::Error> {
+        postgres::DbTransaction::query(self, statement, params)
+    }
+
+    fn execute(&self, statement: &str, params: Vec<Self::Value>) -> Result<u64, Self::Error> {
+        postgres::DbTransaction::execute(self, statement, params)
+    }
+
+    fn commit(&self) -> Result<(), Self::Error> {
+        postgres::DbTransaction::commit(self)
+    }
+
+    fn rollback(&self) -> Result<(), Self::Error> {
+        postgres::DbTransaction::rollback(self)
+    }
+
+    fn other_error(message: String) -> Self::Error {
+        postgres::Error::Other(message)
+    }
+}
+
+impl RdbmsTransaction for mysql::DbTransaction {
+    type Connection = mysql::DbConnection;
+    type Value = mysql::DbValue;
+    type Result = mysql::DbResult;
+    type Error = mysql::Error;
+
+    fn begin(connection: &Self::Connection) -> Result<Self, Self::Error> {
+        connection.begin_transaction()
+    }
+
+    fn query(
+        &self,
+        statement: &str,
+        params: Vec<Self::Value>,
+    ) -> Result<Self::Result, Self::Error> {
+        mysql::DbTransaction::query(self, statement, &params)
+    }
+
+    fn execute(&self, statement: &str, params: Vec<Self::Value>) -> Result<u64, Self::Error> {
+        mysql::DbTransaction::execute(self, statement, &params)
+    }
+
+    fn commit(&self) -> Result<(), Self::Error> {
+        mysql::DbTransaction::commit(self)
+    }
+
+    fn rollback(&self) -> Result<(), Self::Error> {
+        mysql::DbTransaction::rollback(self)
+    }
+
+    fn other_error(message: String) -> Self::Error {
+        mysql::Error::Other(message)
+    }
+}
+
+/// A statement to be executed in a `SharedDbTransaction`.
+///
+/// As database values may contain non-clonable resources, the parameters are provided by a
+/// function, which is called every time the statement gets executed.
+pub struct DbStatement<V> {
+    statement: String,
+    params: Rc<dyn Fn() -> Vec<V>>,
+}
+
+impl<V: 'static> DbStatement<V> {
+    pub fn new(statement: impl Into<String>, params: impl Fn() -> Vec<V> + 'static) -> Self {
+        Self {
+            statement: statement.into(),
+            params: Rc::new(params),
+        }
+    }
+
+    /// A statement without any parameters
+    pub fn without_params(statement: impl Into<String>) -> Self {
+        Self::new(statement, Vec::new)
+    }
+
+    pub fn statement(&self) -> &str {
+        &self.statement
+    }
+}
+
+impl<V> Clone for DbStatement<V> {
+    fn clone(&self) -> Self {
+        Self {
+            statement: self.statement.clone(),
+            params: self.params.clone(),
+        }
+    }
+}
+
+/// The state of a `SharedDbTransaction`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DbTransactionState {
+    Open,
+    Committed,
+    RolledBack,
+}
+
+/// A database transaction whose statements can be executed as operations of a
+/// `fallible_transaction` or `infallible_transaction`.
+///
+/// The compensation action of every statement executed through `execute` or `query` is rolling back
+/// the database transaction, so if the surrounding transaction fails, the database transaction is
+/// rolled back together with the compensation of the other steps. Failures of the database
+/// are converted to the surrounding transaction's error type, triggering the compensation of
+/// the already performed steps. As the failed operation itself has no compensation action, a
+/// failed statement or query rolls back the database transaction right away, so it is rolled
+/// back even if it was the first one.
+///
+/// The `commit` operation is expected to be the last step touching the database. Once committed,
+/// the database changes are no longer undone by the compensation actions.
+pub struct SharedDbTransaction<T: RdbmsTransaction> {
+    transaction: Rc<T>,
+    state: Rc<Cell<DbTransactionState>>,
+}
+
+impl<T: RdbmsTransaction> Clone for SharedDbTransaction<T> {
+    fn clone(&self) -> Self {
+        Self {
+            transaction: self.transaction.clone(),
+            state: self.state.clone(),
+        }
+    }
+}
+
+impl<T: RdbmsTransaction> SharedDbTransaction<T> {
+    /// Begins a new database transaction on the given connection
+    pub fn begin(connection: &T::Connection) -> Result<Self, T::Error> {
+        Ok(Self::new(T::begin(connection)?))
+    }
+
+    /// Wraps an already started database transaction
+    pub fn new(transaction: T) -> Self {
+        Self {
+            transaction: Rc::new(transaction),
+            state: Rc::new(Cell::new(DbTransactionState::Open)),
+        }
+    }
+
+    pub fn state(&self) -> DbTransactionState {
+        self.state.get()
+    }
+
+    /// An operation executing a statement in the database transaction, returning the number of
+    /// affected rows.
+    pub fn execute<Err: From<T::Error> + Clone + 'static>(
+        &self,
+    ) -> impl Operation<In = DbStatement<T::Value>, Out = u64, Err = Err> {
+        let this = self.clone();
+        let compensate = self.clone();
+        operation(
+            move |statement: DbStatement<T::Value>| {
+                Ok(this.run(|transaction| {
+                    transaction.execute(&statement.statement, (statement.params)())
+                })?)
+            },
+            move |_, _| Ok(compensate.rollback()?),
+        )
+    }
+
+    /// An operation running a query in the database transaction.
+    pub fn query<Err: From<T::Error> + Clone + 'static>(
+        &self,
+    ) -> impl Operation<In = DbStatement<T::Value>, Out = Rc<T::Result>, Err = Err> {
+        let this = self.clone();
+        let compensate = self.clone();
+        operation(
+            move |statement: DbStatement<T::Value>| {
+                Ok(Rc::new(this.run(|transaction| {
+                    transaction.query(&statement.statement, (statement.params)())
+                })?))
+            },
+            move |_, _| Ok(compensate.rollback()?),
+        )
+    }
+
+    /// An operation committing the database transaction.
+    pub fn commit<Err: From<T::Error> + Clone + 'static>(
+        &self,
+    ) -> impl Operation<In = (), Out = (), Err = Err> {
+        let this = self.clone();
+        operation(
+            move |_: ()| {
+                this.check_open()?;
+                this.transaction.commit()?;
+                this.state.set(DbTransactionState::Committed);
+                Ok(())
+            },
+            |_, _| Ok(()),
+        )
+    }
+
+    /// Rolls back the database transaction if it is still open. Calling it on an already rolled
+    /// back or committed transaction does nothing.
+    pub fn rollback(&self) -> Result<(), T::Error> {
+        if self.state.get() == DbTransactionState::Open {
+            self.transaction.rollback()?;
+            self.state.set(DbTransactionState::RolledBack);
+        }
+        Ok(())
+    }
+
+    /// Runs a statement, rolling back the database transaction if it fails. The error of the
+    /// statement is returned even if the rollback fails too.
+    fn run<R>(&self, f: impl FnOnce(&T) -> Result<R, T::Error>) -> Result<R, T::Error> {
+        self.check_open()?;
+        f(&self.transaction).inspect_err(|_| {
+            let _ = self.rollback();
+        })
+    }
+
+    fn check_open(&self) -> Result<(), T::Error> {
+        match self.state.get() {
+            DbTransactionState::Open => Ok(()),
+            DbTransactionState::Committed => Err(T::other_error(
+                "Database transaction is already committed".to_string(),
+            )),
+            DbTransactionState::RolledBack => Err(T::other_error(
+                "Database transaction is already rolled back".to_string(),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use crate::bindings::golem::rdbms::{mysql, postgres};
+    use crate::{
+        fallible_transaction, operation, DbStatement, DbTransactionState, RdbmsTransaction,
+        SharedDbTransaction,
+    };
+
+    #[allow(dead_code)]
+    #[derive(Clone, Debug)]
+    enum SagaError {
+        Postgres(postgres::Error),
+        MySql(mysql::Error),
+        Remote(String),
+        Fake(String),
+    }
+
+    impl From<postgres::Error> for SagaError {
+        fn from(value: postgres::Error) -> Self {
+            Self::Postgres(value)
+        }
+    }
+
+    impl From<mysql::Error> for SagaError {
+        fn from(value: mysql::Error) -> Self {
+            Self::MySql(value)
+        }
+    }
+
+    impl From<String> for SagaError {
+        fn from(value: String) -> Self {
+            Self::Fake(value)
+        }
+    }
+
+    /// Database transaction logging the calls, failing the statements starting with `FAIL`
+    struct FakeTransaction {
+        log: Rc<RefCell<Vec<String>>>,
+    }
+
+    impl RdbmsTransaction for FakeTransaction {
+        type Connection = Rc<RefCell<Vec<String>>>;
+        type Value = ();
+        type Result = ();
+        type Error = String;
+
+        fn begin(connection: &Self::Connection) -> Result<Self, Self::Error> {
+            Ok(Self {
+                log: connection.clone(),
+            })
+        }
+
+        fn query(&self, statement: &str, params: Vec<()>) -> Result<(), Self::Error> {
+            self.execute(statement, params).map(|_| ())
+        }
+
+        fn execute(&self, statement: &str, _params: Vec<()>) -> Result<u64, Self::Error> {
+            self.log.borrow_mut().push(statement.to_string());
+            if statement.starts_with("FAIL") {
+                Err(format!("{statement} failed"))
+            } else {
+                Ok(1)
+            }
+        }
+
+        fn commit(&self) -> Result<(), Self::Error> {
+            self.log.borrow_mut().push("COMMIT".to_string());
+            Ok(())
+        }
+
+        fn rollback(&self) -> Result<(), Self::Error> {
+            self.log.borrow_mut().push("ROLLBACK".to_string());
+            Ok(())
+        }
+
+        fn other_error(message: String) -> Self::Error {
+            message
+        }
+    }
+
+    #[test]
+    fn failing_first_statement_rolls_back() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let db = SharedDbTransaction::<FakeTransaction>::begin(&log).unwrap();
+
+        let result = fallible_transaction(|tx| {
+            tx.execute(db.execute(), DbStatement::without_params("FAIL INSERT"))?;
+            tx.execute(db.commit::<SagaError>(), ())
+        });
+
+        assert!(result.is_err());
+        assert_eq!(db.state(), DbTransactionState::RolledBack);
+        assert_eq!(*log.borrow(), vec!["FAIL INSERT", "ROLLBACK"]);
+    }
+
+    #[test]
+    fn failing_saga_rolls_back_once() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let db = SharedDbTransaction::<FakeTransaction>::begin(&log).unwrap();
+
+        let result = fallible_transaction(|tx| {
+            tx.execute(db.execute(), DbStatement::without_params("INSERT"))?;
+            tx.execute(db.query(), DbStatement::without_params("SELECT"))?;
+            Err::<(), _>(SagaError::Remote("shipping failed".to_string()))
+        });
+
+        assert!(result.is_err());
+        assert_eq!(*log.borrow(), vec!["INSERT", "SELECT", "ROLLBACK"]);
+    }
+
+    // Not a real test, just verifying that the database operations can be mixed with other
+    // operations
+    #[test]
+    #[ignore]
+    fn db_transaction_saga_test() {
+        let pg_connection = postgres::DbConnection::open("postgres://localhost/orders").unwrap();
+        let mysql_connection = mysql::DbConnection::open("mysql://localhost/stock").unwrap();
+
+        let reserve_payment = operation(
+            |amount: u64| Ok::<_, SagaError>(format!("payment-{amount}")),
+            |_: u64, _: String| Ok(()),
+        );
+
+        let result = fallible_transaction(|tx| {
+            let orders = SharedDbTransaction::<postgres::DbTransaction>::begin(&pg_connection)?;
+            let stock = SharedDbTransaction::<mysql::DbTransaction>::begin(&mysql_connection)?;
+
+            let payment = tx.execute(reserve_payment, 100)?;
+            tx.execute(
+                orders.execute(),
+                DbStatement::new("INSERT INTO orders (payment) VALUES ($1)", move || {
+                    vec![postgres::DbValue::Text(payment.clone())]
+                }),
+            )?;
+            tx.execute(
+                stock.execute(),
+                DbStatement::without_params("UPDATE stock SET count = count - 1"),
+            )?;
+            tx.execute(orders.commit(), ())?;
+            tx.execute(stock.commit(), ())?;
+            Err::<(), _>(SagaError::Remote("shipping failed".to_string()))
+        });
+
+        println!("{result:?}");
+    }
+}
@@ -0,0 +1,303 @@
+// Copyright 2024-2025 Golem Cloud
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::cell::RefCell;
+use std::ops::{Deref, DerefMut};
+
+use golem_wasm_rpc::golem_rpc_0_2_x::types::ValueAndType;
+use golem_wasm_rpc::{NodeIndex, WitNode, WitValue, WitValueExtractor};
+
+use crate::value_and_type::{
+    FromValueAndType, FromValueRef, IntoValue, ListRef, NodeBuilder, TypeNodeBuilder, ValueError,
+    ValueKind, ValueRef,
+};
+
+/// Byte buffer represented by a `list<u8>`, like `Vec<u8>`.
+///
+/// A `list<u8>` takes a node per byte in a `WitValue`, so large binary payloads such as HTTP
+/// bodies are slow to persist and replay. On its own, or as the `ok` case of a result persisted by
+/// `Durability::persist_bytes`, all nodes of a `Bytes` value are allocated at once, and extraction
+/// reads the bytes into a vector allocated with the length of the list. Nested in other values, it
+/// is built item by item like `Vec<u8>`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Bytes(pub Vec<u8>);
+
+impl Bytes {
+    pub fn into_inner(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+impl Deref for Bytes {
+    type Target = Vec<u8>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for Bytes {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl AsRef<[u8]> for Bytes {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<Vec<u8>> for Bytes {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self(bytes)
+    }
+}
+
+impl From<&[u8]> for Bytes {
+    fn from(bytes: &[u8]) -> Self {
+        Self(bytes.to_vec())
+    }
+}
+
+impl From<Bytes> for Vec<u8> {
+    fn from(bytes: Bytes) -> Self {
+        bytes.0
+    }
+}
+
+impl IntoValue for Bytes {
+    fn add_to_builder<T: NodeBuilder>(self, builder: T) -> T::Result {
+        add_bytes_to_builder(&self.0, builder)
+    }
+
+    fn add_to_type_builder<T: TypeNodeBuilder>(builder: T) -> T::Result {
+        add_bytes_to_type_builder(builder)
+    }
+
+    fn into_value(self) -> WitValue {
+        bytes_value(&self.0)
+    }
+}
+
+/// Builds the value without taking the buffer, so it can still be returned after persisting it
+impl IntoValue for &Bytes {
+    fn add_to_builder<T: NodeBuilder>(self, builder: T) -> T::Result {
+        add_bytes_to_builder(&self.0, builder)
+    }
+
+    fn add_to_type_builder<T: TypeNodeBuilder>(builder: T) -> T::Result {
+        add_bytes_to_type_builder(builder)
+    }
+
+    fn into_value(self) -> WitValue {
+        bytes_value(&self.0)
+    }
+}
+
+impl FromValueAndType for Bytes {
+    fn from_value_and_type(value_and_type: ValueAndType) -> Result<Self, ValueError> {
+        Self::from_wit_value(&value_and_type.value)
+            .map_err(|err| err.with_expected_type(value_and_type.typ))
+    }
+
+    fn from_extractor<'a, 'b>(
+        extractor: &'a impl WitValueExtractor<'a, 'b>,
+    ) -> Result<Self, ValueError> {
+        bytes_from_extractor(extractor).map(Bytes)
+    }
+}
+
+impl<'a> FromValueRef<'a> for Bytes {
+    fn from_value_ref(value: ValueRef<'a>) -> Result<Self, ValueError> {
+        ListRef::from_value_ref(value)?.bytes().map(Bytes)
+    }
+}
+
+/// Adds a `list<u8>` through the builder. Nested builders can only add the items one by one, the
+/// nodes are only allocated at once by `bytes_value` and `bytes_result_into_value_and_type`.
+pub(crate) fn add_bytes_to_builder<T: NodeBuilder>(bytes: &[u8], builder: T) -> T::Result {
+    builder.list_fn(bytes, |byte, item| item.u8(*byte))
+}
+
+pub(crate) fn add_bytes_to_type_builder<T: TypeNodeBuilder>(builder: T) -> T::Result {
+    builder.list(None, None).u8().finish()
+}
+
+/// A `list<u8>` value with all nodes allocated at once
+pub(crate) fn bytes_value(bytes: &[u8]) -> WitValue {
+    let mut nodes = Vec::with_capacity(bytes.len() + 1);
+    push_list_nodes(&mut nodes, bytes);
+    WitValue { nodes }
+}
+
+/// Converts a durable function result with a binary `ok` case, allocating the nodes of the bytes
+/// at once
+#[cfg(feature = "durability")]
+pub(crate) fn bytes_result_into_value_and_type<E: IntoValue>(
+    result: Result<&Bytes, E>,
+) -> ValueAndType {
+    let typ = Result::<&Bytes, E>::get_type();
+    let value = match result {
+        Ok(bytes) => {
+            let mut nodes = Vec::with_capacity(bytes.len() + 2);
+            nodes.push(WitNode::ResultValue(Ok(Some(1))));
+            push_list_nodes(&mut nodes, bytes);
+            WitValue { nodes }
+        }
+        Err(err) => Err::<&Bytes, E>(err).into_value(),
+    };
+    ValueAndType { value, typ }
+}
+
+/// Pushes the list node followed by the nodes of its items
+fn push_list_nodes(nodes: &mut Vec<WitNode>, bytes: &[u8]) {
+    let first_item = (nodes.len() + 1) as NodeIndex;
+    nodes.push(WitNode::ListValue(
+        (first_item..first_item + bytes.len() as NodeIndex).collect(),
+    ));
+    nodes.extend(bytes.iter().map(|byte| WitNode::PrimU8(*byte)));
+}
+
+/// Extracts a `list<u8>` into a vector allocated with the length of the list, without collecting
+/// the items first
+pub(crate) fn bytes_from_extractor<'a, 'b>(
+    extractor: &'a impl WitValueExtractor<'a, 'b>,
+) -> Result<Vec<u8>, ValueError> {
+    // Collecting unit values does not allocate
+    let len = extractor
+        .list_elements(|_| ())
+        .ok_or_else(|| ValueError::unexpected(ValueKind::List, extractor))?
+        .len();
+    let bytes = RefCell::new(Vec::with_capacity(len));
+    let error = RefCell::new(None);
+    extractor.list_elements(|elem| {
+        if error.borrow().is_some() {
+            return;
+        }
+        match elem.u8() {
+            Some(byte) => bytes.borrow_mut().push(byte),
+            None => {
+                let idx = bytes.borrow().len();
+                *error.borrow_mut() =
+                    Some(ValueError::unexpected(ValueKind::U8, &elem).at_index(idx));
+            }
+        }
+    });
+    match error.into_inner() {
+        Some(err) => Err(err),
+        None => Ok(bytes.into_inner()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use golem_wasm_rpc::{Value, WitTypeNode, WitValue};
+
+    use crate::value_and_type::{
+        Bytes, FromValueAndType, FromValueRef, IntoValue, IntoValueAndType, PathSegment,
+    };
+
+    #[test]
+    fn byte_lists() {
+        let bytes = Bytes((0..=255).collect());
+        let value_and_type = bytes.clone().into_value_and_type();
+        assert_eq!(value_and_type.value.nodes.len(), 257);
+        assert!(matches!(
+            value_and_type.typ.nodes[0].type_,
+            WitTypeNode::ListType(1)
+        ));
+        assert!(matches!(
+            value_and_type.typ.nodes[1].type_,
+            WitTypeNode::PrimU8Type
+        ));
+        assert_eq!(
+            Value::from(value_and_type.value.clone()),
+            Value::from(bytes.0.clone().into_value())
+        );
+        assert_eq!(
+            Bytes::from_wit_value(&value_and_type.value),
+            Ok(bytes.clone())
+        );
+        assert_eq!(
+            Bytes::from_extractor(&value_and_type.value),
+            Ok(bytes.clone())
+        );
+        assert_eq!(Bytes::from_value_and_type(value_and_type), Ok(bytes));
+    }
+
+    #[test]
+    fn nested() {
+        let value = Some(Bytes(vec![1, 2, 3])).into_value();
+        assert_eq!(
+            Value::from(value.clone()),
+            Value::from(Some(vec![1u8, 2, 3]).into_value())
+        );
+        assert_eq!(
+            Option::<Bytes>::from_extractor(&value),
+            Ok(Some(Bytes(vec![1, 2, 3])))
+        );
+        assert_eq!(
+            Option::<Bytes>::from_wit_value(&value),
+            Ok(Some(Bytes(vec![1, 2, 3])))
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "durability")]
+    fn results() {
+        use crate::value_and_type::binary::bytes_result_into_value_and_type;
+        use golem_wasm_rpc::WitNode;
+
+        let bytes = Bytes(vec![1, 2, 3]);
+        let value_and_type = bytes_result_into_value_and_type(Ok::<_, String>(&bytes));
+        assert_eq!(value_and_type.value.nodes.len(), 5);
+        assert!(matches!(
+            value_and_type.value.nodes[0],
+            WitNode::ResultValue(Ok(Some(1)))
+        ));
+        assert!(matches!(
+            &value_and_type.value.nodes[1],
+            WitNode::ListValue(items) if items == &[2, 3, 4]
+        ));
+        assert_eq!(
+            Value::from(value_and_type.value.clone()),
+            Value::from(Ok::<_, String>(vec![1u8, 2, 3]).into_value())
+        );
+        assert_eq!(
+            format!("{:?}", value_and_type.typ),
+            format!("{:?}", Result::<Vec<u8>, String>::get_type())
+        );
+        assert_eq!(
+            Result::<Bytes, String>::from_value_and_type(value_and_type),
+            Ok(Ok(bytes))
+        );
+
+        let value_and_type = bytes_result_into_value_and_type(Err::<&Bytes, _>("failed"));
+        assert_eq!(
+            Value::from(value_and_type.value),
+            Value::from(Err::<Vec<u8>, _>("failed").into_value())
+        );
+    }
+
+    #[test]
+    fn invalid_items() {
+        let value: WitValue = vec![1u16, 2].into_value();
+        let err = Bytes::from_extractor(&value).unwrap_err();
+        assert_eq!(err.path(), &[PathSegment::Index(0)]);
+        assert_eq!(Bytes::from_wit_value(&value), Err(err));
+        assert!(Bytes::from_extractor(&1u8.into_value()).is_err());
+        assert!(Bytes::from_wit_value(&1u8.into_value()).is_err());
+    }
+}
@@ -0,0 +1,75 @@
+// Copyright 2024-2025 Golem Cloud
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Representation of `bitflags` types as WIT flags.
+//!
+//! These functions implement the derives of types marked with `#[wit(bitflags)]`, and can be
+//! used to implement `IntoValue` and `FromValueAndType` by hand as well. Every named flag
+//! becomes a WIT flag, with its name converted to kebab-case (`WRITE_ALL` becomes `write-all`).
+
+use ::bitflags::{Flag, Flags};
+
+use crate::value_and_type::{
+    NodeBuilder, TypeNodeBuilder, ValueError, ValueKind, WitValueExtractor,
+};
+
+fn named_flags<F: Flags>() -> impl Iterator<Item = &'static Flag<F>> {
+    F::FLAGS.iter().filter(|flag| flag.is_named())
+}
+
+fn flag_value<F: Flags>(flag: &Flag<F>) -> F {
+    F::from_bits_retain(flag.value().bits())
+}
+
+pub fn add_to_builder<F: Flags, B: NodeBuilder>(flags: F, builder: B) -> B::Result {
+    builder.flags(
+        named_flags::<F>()
+            .map(|flag| flags.contains(flag_value(flag)))
+            .collect(),
+    )
+}
+
+pub fn add_to_type_builder<F: Flags, B: TypeNodeBuilder>(
+    name: Option<String>,
+    owner: Option<String>,
+    builder: B,
+) -> B::Result {
+    let names = named_flags::<F>()
+        .map(|flag| flag.name().to_lowercase().replace('_', "-"))
+        .collect::<Vec<_>>();
+    let names = names.iter().map(String::as_str).collect::<Vec<_>>();
+    builder.flags(name, owner, &names)
+}
+
+pub fn from_extractor<'a, 'b, F: Flags>(
+    extractor: &'a impl WitValueExtractor<'a, 'b>,
+) -> Result<F, ValueError> {
+    let values = extractor
+        .flags()
+        .ok_or_else(|| ValueError::unexpected(ValueKind::Flags, extractor))?;
+    let flag_count = named_flags::<F>().count();
+    if values.len() != flag_count {
+        return Err(ValueError::custom(format!(
+            "Expected {flag_count} flags, got {}",
+            values.len()
+        )));
+    }
+    let mut result = F::empty();
+    for (flag, set) in named_flags::<F>().zip(values) {
+        if *set {
+            result.insert(flag_value(flag));
+        }
+    }
+    Ok(result)
+}
@@ -0,0 +1,397 @@
+// Copyright 2024-2025 Golem Cloud
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Implementations for time, UUID and other commonly used types. The ones coming from optional
+// dependencies are enabled by the feature with the same name as the crate.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[cfg(feature = "bytes")]
+use crate::value_and_type::binary::{
+    add_bytes_to_builder, add_bytes_to_type_builder, bytes_from_extractor, bytes_value,
+};
+#[cfg(any(feature = "url", feature = "rust_decimal"))]
+use crate::value_and_type::ValueKind;
+use crate::value_and_type::{
+    FromValueAndType, IntoValue, NodeBuilder, TypeNodeBuilder, ValueError,
+};
+#[cfg(feature = "bytes")]
+use golem_wasm_rpc::WitValue;
+use golem_wasm_rpc::WitValueExtractor;
+
+const RPC_TYPES_OWNER: &str = "golem:rpc@0.2.2/types";
+
+/// Points in time are represented by a `timestamp` record of the signed seconds since the Unix
+/// epoch and the nanoseconds within that second. It is laid out like the `datetime` record of
+/// `wasi:clocks/wall-clock`, but can also hold times before the epoch.
+fn add_timestamp_to_builder<T: NodeBuilder>(
+    seconds: i64,
+    nanoseconds: u32,
+    builder: T,
+) -> T::Result {
+    let mut builder = builder.record();
+    builder = builder.item().s64(seconds);
+    builder = builder.item().u32(nanoseconds);
+    builder.finish()
+}
+
+fn add_timestamp_to_type_builder<T: TypeNodeBuilder>(builder: T) -> T::Result {
+    builder.interned("timestamp", |builder| {
+        let mut builder = builder.record(Some("timestamp".to_string()), None);
+        builder = builder.field("seconds").s64();
+        builder = builder.field("nanoseconds").u32();
+        builder.finish()
+    })
+}
+
+fn timestamp_from_extractor<'a, 'b>(
+    extractor: &'a impl WitValueExtractor<'a, 'b>,
+) -> Result<(i64, u32), ValueError> {
+    let seconds = record_field(extractor, 0, "seconds")?;
+    let nanoseconds = record_field(extractor, 1, "nanoseconds")?;
+    Ok((seconds, nanoseconds))
+}
+
+fn out_of_range(what: &str, seconds: impl std::fmt::Display, nanoseconds: u32) -> ValueError {
+    ValueError::custom(format!(
+        "{what} of {seconds} seconds and {nanoseconds} nanoseconds is out of range"
+    ))
+}
+
+fn record_field<'a, 'b, T: FromValueAndType>(
+    extractor: &'a impl WitValueExtractor<'a, 'b>,
+    idx: usize,
+    name: &str,
+) -> Result<T, ValueError> {
+    let field = extractor
+        .field(idx)
+        .ok_or_else(|| ValueError::missing_field(extractor, name))?;
+    T::from_extractor(&field).map_err(|err| err.at_field(name))
+}
+
+/// Durations are represented by a `duration` record of whole seconds and the nanoseconds within
+/// the last second, which can hold any `Duration`
+impl IntoValue for Duration {
+    fn add_to_builder<T: NodeBuilder>(self, builder: T) -> T::Result {
+        let mut builder = builder.record();
+        builder = builder.item().u64(self.as_secs());
+        builder = builder.item().u32(self.subsec_nanos());
+        builder.finish()
+    }
+
+    fn add_to_type_builder<T: TypeNodeBuilder>(builder: T) -> T::Result {
+        builder.interned("duration", |builder| {
+            let mut builder = builder.record(Some("duration".to_string()), None);
+            builder = builder.field("seconds").u64();
+            builder = builder.field("nanoseconds").u32();
+            builder.finish()
+        })
+    }
+}
+
+impl FromValueAndType for Duration {
+    fn from_extractor<'a, 'b>(
+        extractor: &'a impl WitValueExtractor<'a, 'b>,
+    ) -> Result<Self, ValueError> {
+        let seconds = record_field(extractor, 0, "seconds")?;
+        let nanoseconds: u32 = record_field(extractor, 1, "nanoseconds")?;
+        Duration::from_secs(seconds)
+            .checked_add(Duration::from_nanos(nanoseconds.into()))
+            .ok_or_else(|| out_of_range("Duration", seconds, nanoseconds))
+    }
+}
+
+impl IntoValue for SystemTime {
+    fn add_to_builder<T: NodeBuilder>(self, builder: T) -> T::Result {
+        // SystemTime is at most i64::MAX seconds away from the epoch on every platform
+        let (seconds, nanoseconds) = match self.duration_since(UNIX_EPOCH) {
+            Ok(since_epoch) => (since_epoch.as_secs() as i128, since_epoch.subsec_nanos()),
+            Err(err) => {
+                let before_epoch = err.duration();
+                let seconds = -(before_epoch.as_secs() as i128);
+                match before_epoch.subsec_nanos() {
+                    0 => (seconds, 0),
+                    nanos => (seconds - 1, 1_000_000_000 - nanos),
+                }
+            }
+        };
+        add_timestamp_to_builder(seconds as i64, nanoseconds, builder)
+    }
+
+    fn add_to_type_builder<T: TypeNodeBuilder>(builder: T) -> T::Result {
+        add_timestamp_to_type_builder(builder)
+    }
+}
+
+impl FromValueAndType for SystemTime {
+    fn from_extractor<'a, 'b>(
+        extractor: &'a impl WitValueExtractor<'a, 'b>,
+    ) -> Result<Self, ValueError> {
+        let (seconds, nanoseconds) = timestamp_from_extractor(extractor)?;
+        let whole_seconds = Duration::from_secs(seconds.unsigned_abs());
+        if seconds >= 0 {
+            UNIX_EPOCH.checked_add(whole_seconds)
+        } else {
+            UNIX_EPOCH.checked_sub(whole_seconds)
+        }
+        .and_then(|time| time.checked_add(Duration::from_nanos(nanoseconds.into())))
+        .ok_or_else(|| out_of_range("Timestamp", seconds, nanoseconds))
+    }
+}
+
+/// UUIDs are represented by the `uuid` record of `golem:rpc/types`
+impl IntoValue for uuid::Uuid {
+    fn add_to_builder<T: NodeBuilder>(self, builder: T) -> T::Result {
+        let (high_bits, low_bits) = self.as_u64_pair();
+        let mut builder = builder.record();
+        builder = builder.item().u64(high_bits);
+        builder = builder.item().u64(low_bits);
+        builder.finish()
+    }
+
+    fn add_to_type_builder<T: TypeNodeBuilder>(builder: T) -> T::Result {
+        builder.interned("golem:rpc@0.2.2/types.uuid", |builder| {
+            let mut builder =
+                builder.record(Some("uuid".to_string()), Some(RPC_TYPES_OWNER.to_string()));
+            builder = builder.field("high-bits").u64();
+            builder = builder.field("low-bits").u64();
+            builder.finish()
+        })
+    }
+}
+
+impl FromValueAndType for uuid::Uuid {
+    fn from_extractor<'a, 'b>(
+        extractor: &'a impl WitValueExtractor<'a, 'b>,
+    ) -> Result<Self, ValueError> {
+        let high_bits = record_field(extractor, 0, "high-bits")?;
+        let low_bits = record_field(extractor, 1, "low-bits")?;
+        Ok(uuid::Uuid::from_u64_pair(high_bits, low_bits))
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl IntoValue for chrono::DateTime<chrono::Utc> {
+    fn add_to_builder<T: NodeBuilder>(self, builder: T) -> T::Result {
+        add_timestamp_to_builder(self.timestamp(), self.timestamp_subsec_nanos(), builder)
+    }
+
+    fn add_to_type_builder<T: TypeNodeBuilder>(builder: T) -> T::Result {
+        add_timestamp_to_type_builder(builder)
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl FromValueAndType for chrono::DateTime<chrono::Utc> {
+    fn from_extractor<'a, 'b>(
+        extractor: &'a impl WitValueExtractor<'a, 'b>,
+    ) -> Result<Self, ValueError> {
+        let (seconds, nanoseconds) = timestamp_from_extractor(extractor)?;
+        chrono::DateTime::from_timestamp(seconds, nanoseconds)
+            .ok_or_else(|| out_of_range("Timestamp", seconds, nanoseconds))
+    }
+}
+
+#[cfg(feature = "time")]
+impl IntoValue for time::OffsetDateTime {
+    fn add_to_builder<T: NodeBuilder>(self, builder: T) -> T::Result {
+        add_timestamp_to_builder(self.unix_timestamp(), self.nanosecond(), builder)
+    }
+
+    fn add_to_type_builder<T: TypeNodeBuilder>(builder: T) -> T::Result {
+        add_timestamp_to_type_builder(builder)
+    }
+}
+
+#[cfg(feature = "time")]
+impl FromValueAndType for time::OffsetDateTime {
+    fn from_extractor<'a, 'b>(
+        extractor: &'a impl WitValueExtractor<'a, 'b>,
+    ) -> Result<Self, ValueError> {
+        let (seconds, nanoseconds) = timestamp_from_extractor(extractor)?;
+        let nanos_since_epoch = i128::from(seconds) * 1_000_000_000 + i128::from(nanoseconds);
+        time::OffsetDateTime::from_unix_timestamp_nanos(nanos_since_epoch)
+            .map_err(|_| out_of_range("Timestamp", seconds, nanoseconds))
+    }
+}
+
+/// URLs are represented by their string form
+#[cfg(feature = "url")]
+impl IntoValue for url::Url {
+    fn add_to_builder<T: NodeBuilder>(self, builder: T) -> T::Result {
+        builder.string(self.as_str())
+    }
+
+    fn add_to_type_builder<T: TypeNodeBuilder>(builder: T) -> T::Result {
+        builder.string()
+    }
+}
+
+#[cfg(feature = "url")]
+impl FromValueAndType for url::Url {
+    fn from_extractor<'a, 'b>(
+        extractor: &'a impl WitValueExtractor<'a, 'b>,
+    ) -> Result<Self, ValueError> {
+        let url = extractor
+            .string()
+            .ok_or_else(|| ValueError::unexpected(ValueKind::String, extractor))?;
+        url::Url::parse(url).map_err(|err| ValueError::custom(format!("Invalid URL {url}: {err}")))
+    }
+}
+
+/// Decimals are represented by their string form to keep them exact
+#[cfg(feature = "rust_decimal")]
+impl IntoValue for rust_decimal::Decimal {
+    fn add_to_builder<T: NodeBuilder>(self, builder: T) -> T::Result {
+        builder.string(&self.to_string())
+    }
+
+    fn add_to_type_builder<T: TypeNodeBuilder>(builder: T) -> T::Result {
+        builder.string()
+    }
+}
+
+#[cfg(feature = "rust_decimal")]
+impl FromValueAndType for rust_decimal::Decimal {
+    fn from_extractor<'a, 'b>(
+        extractor: &'a impl WitValueExtractor<'a, 'b>,
+    ) -> Result<Self, ValueError> {
+        let decimal = extractor
+            .string()
+            .ok_or_else(|| ValueError::unexpected(ValueKind::String, extractor))?;
+        decimal
+            .parse()
+            .map_err(|err| ValueError::custom(format!("Invalid decimal {decimal}: {err}")))
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl IntoValue for bytes::Bytes {
+    fn add_to_builder<T: NodeBuilder>(self, builder: T) -> T::Result {
+        add_bytes_to_builder(&self, builder)
+    }
+
+    fn add_to_type_builder<T: TypeNodeBuilder>(builder: T) -> T::Result {
+        add_bytes_to_type_builder(builder)
+    }
+
+    fn into_value(self) -> WitValue {
+        bytes_value(&self)
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl FromValueAndType for bytes::Bytes {
+    fn from_extractor<'a, 'b>(
+        extractor: &'a impl WitValueExtractor<'a, 'b>,
+    ) -> Result<Self, ValueError> {
+        bytes_from_extractor(extractor).map(bytes::Bytes::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    use crate::value_and_type::test_utils::roundtrip;
+    use crate::value_and_type::{FromValueAndType, IntoValue};
+    use golem_wasm_rpc::{NodeBuilder, WitValue, WitValueBuilderExtensions};
+
+    #[test]
+    fn std_time_roundtrip() {
+        roundtrip(Duration::from_millis(1500));
+        roundtrip(Duration::MAX);
+        roundtrip(UNIX_EPOCH + Duration::new(1_700_000_000, 123_456_789));
+        roundtrip(UNIX_EPOCH - Duration::new(1, 0));
+        roundtrip(UNIX_EPOCH - Duration::new(1_700_000_000, 123_456_789));
+    }
+
+    #[test]
+    fn system_time_is_a_timestamp() {
+        let value = (UNIX_EPOCH + Duration::new(10, 20)).into_value();
+        assert_eq!(value, timestamp(10, 20));
+
+        let value = (UNIX_EPOCH - Duration::new(10, 20)).into_value();
+        assert_eq!(value, timestamp(-11, 999_999_980));
+
+        let typ = SystemTime::get_type();
+        assert_eq!(typ.nodes[0].name.as_deref(), Some("timestamp"));
+    }
+
+    #[test]
+    fn out_of_range_times_are_rejected() {
+        let value = WitValue::builder()
+            .record()
+            .item()
+            .u64(u64::MAX)
+            .item()
+            .u32(1_000_000_000)
+            .finish();
+        assert!(Duration::from_extractor(&value).is_err());
+        assert!(SystemTime::from_extractor(&timestamp(i64::MAX, u32::MAX)).is_err());
+    }
+
+    fn timestamp(seconds: i64, nanoseconds: u32) -> WitValue {
+        WitValue::builder()
+            .record()
+            .item()
+            .s64(seconds)
+            .item()
+            .u32(nanoseconds)
+            .finish()
+    }
+
+    #[test]
+    fn uuid_roundtrip() {
+        roundtrip(uuid::Uuid::new_v4());
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn chrono_roundtrip() {
+        roundtrip(chrono::DateTime::from_timestamp(1_700_000_000, 5).unwrap());
+        roundtrip(chrono::DateTime::from_timestamp(-1_700_000_000, 5).unwrap());
+        assert!(chrono::DateTime::<chrono::Utc>::from_extractor(&timestamp(i64::MAX, 0)).is_err());
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn time_roundtrip() {
+        roundtrip(
+            time::OffsetDateTime::from_unix_timestamp_nanos(1_700_000_000_000_000_005).unwrap(),
+        );
+        roundtrip(
+            time::OffsetDateTime::from_unix_timestamp_nanos(-1_700_000_000_000_000_005).unwrap(),
+        );
+        assert!(time::OffsetDateTime::from_extractor(&timestamp(i64::MAX, 0)).is_err());
+    }
+
+    #[cfg(feature = "url")]
+    #[test]
+    fn url_roundtrip() {
+        roundtrip(url::Url::parse("https://golem.cloud/docs?page=1").unwrap());
+    }
+
+    #[cfg(feature = "rust_decimal")]
+    #[test]
+    fn decimal_roundtrip() {
+        roundtrip("-1234.5678".parse::<rust_decimal::Decimal>().unwrap());
+    }
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn bytes_roundtrip() {
+        roundtrip(bytes::Bytes::from_static(b"golem"));
+    }
+}
@@ -0,0 +1,754 @@
+// Copyright 2024-2025 Golem Cloud
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Compatibility between versions of a type, and upgrading values to newer versions.
+//!
+//! Values of an old type can be read as a new type if, recursively:
+//! - the types are the same primitive type, or the new one is a wider integer type which can
+//!   represent all values of the old one, or `f64` in place of `f32`
+//! - the new type is a record whose fields exist in the old record, or are of option types,
+//!   which are `none` in upgraded values. Fields removed from the record are dropped.
+//! - the new type is a variant, enum or flags with all the cases or flags of the old one.
+//!   Cases are matched by name, so they can also be reordered.
+//! - the new type is an option of a type which the old type can be read as
+//! - both are tuples of the same length, lists, options or results of compatible types, or
+//!   handles of the same resource
+//!
+//! A variant or result case without a value can get a value of an option type, which is `none`
+//! in upgraded values.
+//!
+//! `upgrade` only fails for the parts of the old type which are actually used by the value, so
+//! for example values not using a removed variant case can still be upgraded.
+
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+
+use golem_wasm_rpc::golem_rpc_0_2_x::types::ValueAndType;
+use golem_wasm_rpc::{NodeIndex, Value, WitType, WitTypeNode};
+
+use crate::value_and_type::error::write_at_path;
+use crate::value_and_type::{
+    FromValueAndType, IntoValue, PathSegment, ValueError, ValueErrorKind, ValueKind,
+};
+
+/// A part of the old type which cannot be read as the new type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Incompatibility {
+    path: Vec<PathSegment>,
+    message: String,
+}
+
+impl Incompatibility {
+    /// Path of the incompatible part in the old type. List elements have no segment in the
+    /// path, and the inner types of options are denoted by the `some` case.
+    pub fn path(&self) -> &[PathSegment] {
+        &self.path
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl Display for Incompatibility {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write_at_path(f, &self.path)?;
+        write!(f, "{}", self.message)
+    }
+}
+
+/// The reasons values of the old type cannot be read as the new type, empty if they can
+pub fn check(old: &WitType, new: &WitType) -> Vec<Incompatibility> {
+    let mut checker = Checker {
+        old,
+        new,
+        visited: HashSet::new(),
+        path: Vec::new(),
+        incompatibilities: Vec::new(),
+    };
+    checker.check(0, 0);
+    checker.incompatibilities
+}
+
+/// Whether all values of the old type can be read as the new type
+pub fn is_compatible(old: &WitType, new: &WitType) -> bool {
+    check(old, new).is_empty()
+}
+
+/// Converts the value of an old type to the new type
+pub fn upgrade(value: ValueAndType, new: &WitType) -> Result<ValueAndType, ValueError> {
+    let upgraded = upgrade_node(value.value.into(), &value.typ, 0, new, 0)
+        .map_err(|err| err.with_expected_type(new.clone()))?;
+    Ok(ValueAndType {
+        value: upgraded.into(),
+        typ: new.clone(),
+    })
+}
+
+/// Converts the value of an old type to the Rust type, upgrading it to the type's current
+/// `WitType` first
+pub fn upgrade_to<T: IntoValue + FromValueAndType>(value: ValueAndType) -> Result<T, ValueError> {
+    T::from_value_and_type(upgrade(value, &T::get_type())?)
+}
+
+struct Checker<'t> {
+    old: &'t WitType,
+    new: &'t WitType,
+    /// Pairs of nodes already checked, or being checked in case of recursive types
+    visited: HashSet<(NodeIndex, NodeIndex)>,
+    path: Vec<PathSegment>,
+    incompatibilities: Vec<Incompatibility>,
+}
+
+impl Checker<'_> {
+    fn check(&mut self, old_idx: NodeIndex, new_idx: NodeIndex) {
+        if !self.visited.insert((old_idx, new_idx)) {
+            return;
+        }
+        let old = &self.old.nodes[old_idx as usize].type_;
+        let new = &self.new.nodes[new_idx as usize].type_;
+        match (old, new) {
+            (WitTypeNode::RecordType(old_fields), WitTypeNode::RecordType(new_fields)) => {
+                for (name, new_field) in new_fields {
+                    self.path.push(PathSegment::Field(name.clone()));
+                    match old_fields.iter().find(|(old_name, _)| old_name == name) {
+                        Some((_, old_field)) => self.check(*old_field, *new_field),
+                        None if is_option(self.new, *new_field) => {}
+                        None => self.report("added field is not optional"),
+                    }
+                    self.path.pop();
+                }
+            }
+            (WitTypeNode::VariantType(old_cases), WitTypeNode::VariantType(new_cases)) => {
+                for (name, old_case) in old_cases {
+                    self.path.push(PathSegment::Case(name.clone()));
+                    match new_cases.iter().find(|(new_name, _)| new_name == name) {
+                        Some((_, new_case)) => self.check_case(*old_case, *new_case),
+                        None => self.report("case removed"),
+                    }
+                    self.path.pop();
+                }
+            }
+            (WitTypeNode::EnumType(old_cases), WitTypeNode::EnumType(new_cases)) => {
+                for name in old_cases.iter().filter(|name| !new_cases.contains(name)) {
+                    self.path.push(PathSegment::Case(name.clone()));
+                    self.report("case removed");
+                    self.path.pop();
+                }
+            }
+            (WitTypeNode::FlagsType(old_flags), WitTypeNode::FlagsType(new_flags)) => {
+                for name in old_flags.iter().filter(|name| !new_flags.contains(name)) {
+                    self.report(format!("flag {name} removed"));
+                }
+            }
+            (WitTypeNode::TupleType(old_items), WitTypeNode::TupleType(new_items)) => {
+                if old_items.len() != new_items.len() {
+                    self.report(format!(
+                        "tuple of {} elements cannot be read as a tuple of {}",
+                        old_items.len(),
+                        new_items.len()
+                    ));
+                } else {
+                    for (idx, (old_item, new_item)) in old_items.iter().zip(new_items).enumerate() {
+                        self.path.push(PathSegment::Index(idx));
+                        self.check(*old_item, *new_item);
+                        self.path.pop();
+                    }
+                }
+            }
+            (WitTypeNode::ListType(old_item), WitTypeNode::ListType(new_item)) => {
+                self.check(*old_item, *new_item)
+            }
+            (WitTypeNode::OptionType(old_inner), WitTypeNode::OptionType(new_inner)) => {
+                self.path.push(PathSegment::Case("some".to_string()));
+                self.check(*old_inner, *new_inner);
+                self.path.pop();
+            }
+            (_, WitTypeNode::OptionType(new_inner)) => {
+                self.path.push(PathSegment::Case("some".to_string()));
+                self.check(old_idx, *new_inner);
+                self.path.pop();
+            }
+            (
+                WitTypeNode::ResultType((old_ok, old_err)),
+                WitTypeNode::ResultType((new_ok, new_err)),
+            ) => {
+                self.path.push(PathSegment::Case("ok".to_string()));
+                self.check_case(*old_ok, *new_ok);
+                self.path.pop();
+                self.path.push(PathSegment::Case("err".to_string()));
+                self.check_case(*old_err, *new_err);
+                self.path.pop();
+            }
+            (WitTypeNode::HandleType(old_handle), WitTypeNode::HandleType(new_handle))
+                if old_handle == new_handle => {}
+            (WitTypeNode::HandleType(_), WitTypeNode::HandleType(_)) => {
+                self.report("handle of a different resource type")
+            }
+            (old, new) if is_primitive(old) && widens(old, new) => {}
+            (old, new) => self.report(format!(
+                "{} cannot be read as {}",
+                ValueKind::of_type(old),
+                ValueKind::of_type(new)
+            )),
+        }
+    }
+
+    fn check_case(&mut self, old_case: Option<NodeIndex>, new_case: Option<NodeIndex>) {
+        match (old_case, new_case) {
+            (Some(old_case), Some(new_case)) => self.check(old_case, new_case),
+            (None, None) => {}
+            (None, Some(new_case)) if is_option(self.new, new_case) => {}
+            (None, Some(_)) => self.report("added case value is not optional"),
+            (Some(_), None) => self.report("case value removed"),
+        }
+    }
+
+    fn report(&mut self, message: impl Into<String>) {
+        self.incompatibilities.push(Incompatibility {
+            path: self.path.clone(),
+            message: message.into(),
+        });
+    }
+}
+
+fn upgrade_node(
+    value: Value,
+    old_type: &WitType,
+    old_idx: NodeIndex,
+    new_type: &WitType,
+    new_idx: NodeIndex,
+) -> Result<Value, ValueError> {
+    let old = &old_type.nodes[old_idx as usize].type_;
+    let new = &new_type.nodes[new_idx as usize].type_;
+    let upgraded = match (value, old, new) {
+        (
+            Value::Record(mut values),
+            WitTypeNode::RecordType(old_fields),
+            WitTypeNode::RecordType(new_fields),
+        ) if values.len() == old_fields.len() => {
+            // Taking the values out of the old record, as fields can be reordered
+            let mut values = values.drain(..).map(Some).collect::<Vec<_>>();
+            let mut fields = Vec::with_capacity(new_fields.len());
+            for (name, new_field) in new_fields {
+                let old_field = old_fields.iter().position(|(old_name, _)| old_name == name);
+                let field = match old_field.and_then(|idx| Some((idx, values[idx].take()?))) {
+                    Some((idx, value)) => {
+                        upgrade_node(value, old_type, old_fields[idx].1, new_type, *new_field)
+                            .map_err(|err| err.at_field(name))?
+                    }
+                    None if is_option(new_type, *new_field) => Value::Option(None),
+                    None => return Err(ValueError::new(ValueErrorKind::Missing).at_field(name)),
+                };
+                fields.push(field);
+            }
+            Value::Record(fields)
+        }
+        (
+            Value::Variant {
+                case_idx,
+                case_value,
+            },
+            WitTypeNode::VariantType(old_cases),
+            WitTypeNode::VariantType(new_cases),
+        ) => {
+            let (name, old_case) = old_cases
+                .get(case_idx as usize)
+                .ok_or_else(|| ValueError::invalid_case(case_idx))?;
+            let new_case_idx = new_cases
+                .iter()
+                .position(|(new_name, _)| new_name == name)
+                .ok_or_else(|| removed_case(name))?;
+            Value::Variant {
+                case_idx: new_case_idx as u32,
+                case_value: upgrade_case(
+                    case_value,
+                    old_type,
+                    *old_case,
+                    new_type,
+                    new_cases[new_case_idx].1,
+                    name,
+                )?,
+            }
+        }
+        (
+            Value::Enum(case_idx),
+            WitTypeNode::EnumType(old_cases),
+            WitTypeNode::EnumType(new_cases),
+        ) => {
+            let name = old_cases
+                .get(case_idx as usize)
+                .ok_or_else(|| ValueError::invalid_case(case_idx))?;
+            let new_case_idx = new_cases
+                .iter()
+                .position(|new_name| new_name == name)
+                .ok_or_else(|| removed_case(name))?;
+            Value::Enum(new_case_idx as u32)
+        }
+        (
+            Value::Flags(set),
+            WitTypeNode::FlagsType(old_flags),
+            WitTypeNode::FlagsType(new_flags),
+        ) => {
+            let mut flags = vec![false; new_flags.len()];
+            for (name, _) in old_flags.iter().zip(set).filter(|(_, set)| *set) {
+                let new_flag_idx = new_flags
+                    .iter()
+                    .position(|new_name| new_name == name)
+                    .ok_or_else(|| {
+                        ValueError::custom(format!("flag {name} does not exist in the new type"))
+                    })?;
+                flags[new_flag_idx] = true;
+            }
+            Value::Flags(flags)
+        }
+        (
+            Value::Tuple(items),
+            WitTypeNode::TupleType(old_items),
+            WitTypeNode::TupleType(new_items),
+        ) if items.len() == old_items.len() && old_items.len() == new_items.len() => Value::Tuple(
+            items
+                .into_iter()
+                .zip(old_items.iter().zip(new_items))
+                .enumerate()
+                .map(|(idx, (item, (old_item, new_item)))| {
+                    upgrade_node(item, old_type, *old_item, new_type, *new_item)
+                        .map_err(|err| err.at_index(idx))
+                })
+                .collect::<Result<_, _>>()?,
+        ),
+        (Value::List(items), WitTypeNode::ListType(old_item), WitTypeNode::ListType(new_item)) => {
+            Value::List(
+                items
+                    .into_iter()
+                    .enumerate()
+                    .map(|(idx, item)| {
+                        upgrade_node(item, old_type, *old_item, new_type, *new_item)
+                            .map_err(|err| err.at_index(idx))
+                    })
+                    .collect::<Result<_, _>>()?,
+            )
+        }
+        (Value::Option(None), WitTypeNode::OptionType(_), WitTypeNode::OptionType(_)) => {
+            Value::Option(None)
+        }
+        (
+            Value::Option(inner),
+            WitTypeNode::OptionType(old_inner),
+            WitTypeNode::OptionType(new_inner),
+        ) => Value::Option(upgrade_case(
+            inner,
+            old_type,
+            Some(*old_inner),
+            new_type,
+            Some(*new_inner),
+            "some",
+        )?),
+        (value, _, WitTypeNode::OptionType(new_inner)) => Value::Option(Some(Box::new(
+            upgrade_node(value, old_type, old_idx, new_type, *new_inner)
+                .map_err(|err| err.at_case("some"))?,
+        ))),
+        (
+            Value::Result(result),
+            WitTypeNode::ResultType((old_ok, old_err)),
+            WitTypeNode::ResultType((new_ok, new_err)),
+        ) => Value::Result(match result {
+            Ok(value) => Ok(upgrade_case(
+                value, old_type, *old_ok, new_type, *new_ok, "ok",
+            )?),
+            Err(value) => Err(upgrade_case(
+                value, old_type, *old_err, new_type, *new_err, "err",
+            )?),
+        }),
+        (
+            handle @ Value::Handle { .. },
+            WitTypeNode::HandleType(old_handle),
+            WitTypeNode::HandleType(new_handle),
+        ) if old_handle == new_handle => handle,
+        (value, old, new) if is_primitive(old) && widens(old, new) => {
+            widen(value, new).ok_or_else(|| mismatch(old))?
+        }
+        (_, old, new) if ValueKind::of_type(old) == ValueKind::of_type(new) => {
+            return Err(mismatch(old))
+        }
+        (_, old, new) => {
+            return Err(ValueError::custom(format!(
+                "{} cannot be read as {}",
+                ValueKind::of_type(old),
+                ValueKind::of_type(new)
+            )))
+        }
+    };
+    Ok(upgraded)
+}
+
+fn upgrade_case(
+    value: Option<Box<Value>>,
+    old_type: &WitType,
+    old_case: Option<NodeIndex>,
+    new_type: &WitType,
+    new_case: Option<NodeIndex>,
+    case: &str,
+) -> Result<Option<Box<Value>>, ValueError> {
+    match (value, old_case, new_case) {
+        (Some(value), Some(old_case), Some(new_case)) => {
+            upgrade_node(*value, old_type, old_case, new_type, new_case)
+                .map(|value| Some(Box::new(value)))
+                .map_err(|err| err.at_case(case))
+        }
+        (None, None, None) => Ok(None),
+        (None, None, Some(new_case)) if is_option(new_type, new_case) => {
+            Ok(Some(Box::new(Value::Option(None))))
+        }
+        (_, Some(_), None) => {
+            Err(ValueError::custom("case value does not exist in the new type").at_case(case))
+        }
+        (_, None, Some(_)) => Err(ValueError::missing_case_value(case)),
+        (_, old_case, _) => Err(ValueError::custom(format!(
+            "case value does not match the old type, which {} one",
+            if old_case.is_some() {
+                "has"
+            } else {
+                "does not have"
+            }
+        ))
+        .at_case(case)),
+    }
+}
+
+/// The value does not match the old type, or has a kind not handled by `upgrade_node`
+fn mismatch(old: &WitTypeNode) -> ValueError {
+    ValueError::custom(format!(
+        "value does not match the old type {}",
+        ValueKind::of_type(old)
+    ))
+}
+
+fn removed_case(name: &str) -> ValueError {
+    ValueError::custom(format!("case {name} does not exist in the new type"))
+}
+
+fn is_option(typ: &WitType, node_idx: NodeIndex) -> bool {
+    matches!(
+        typ.nodes[node_idx as usize].type_,
+        WitTypeNode::OptionType(_)
+    )
+}
+
+fn is_primitive(node: &WitTypeNode) -> bool {
+    matches!(
+        node,
+        WitTypeNode::PrimBoolType
+            | WitTypeNode::PrimU8Type
+            | WitTypeNode::PrimU16Type
+            | WitTypeNode::PrimU32Type
+            | WitTypeNode::PrimU64Type
+            | WitTypeNode::PrimS8Type
+            | WitTypeNode::PrimS16Type
+            | WitTypeNode::PrimS32Type
+            | WitTypeNode::PrimS64Type
+            | WitTypeNode::PrimF32Type
+            | WitTypeNode::PrimF64Type
+            | WitTypeNode::PrimCharType
+            | WitTypeNode::PrimStringType
+    )
+}
+
+/// Whether all values of the old primitive type can be represented by the new one
+fn widens(old: &WitTypeNode, new: &WitTypeNode) -> bool {
+    use WitTypeNode::*;
+
+    std::mem::discriminant(old) == std::mem::discriminant(new)
+        || matches!(
+            (old, new),
+            (
+                PrimU8Type,
+                PrimU16Type | PrimU32Type | PrimU64Type | PrimS16Type | PrimS32Type | PrimS64Type
+            ) | (
+                PrimU16Type,
+                PrimU32Type | PrimU64Type | PrimS32Type | PrimS64Type
+            ) | (PrimU32Type, PrimU64Type | PrimS64Type)
+                | (PrimS8Type, PrimS16Type | PrimS32Type | PrimS64Type)
+                | (PrimS16Type, PrimS32Type | PrimS64Type)
+                | (PrimS32Type, PrimS64Type)
+                | (PrimF32Type, PrimF64Type)
+        )
+}
+
+/// Converts a primitive value to the new type if it can represent it
+fn widen(value: Value, new: &WitTypeNode) -> Option<Value> {
+    let number = match (value, new) {
+        (value @ Value::Bool(_), WitTypeNode::PrimBoolType)
+        | (value @ Value::F32(_), WitTypeNode::PrimF32Type)
+        | (value @ Value::F64(_), WitTypeNode::PrimF64Type)
+        | (value @ Value::Char(_), WitTypeNode::PrimCharType)
+        | (value @ Value::String(_), WitTypeNode::PrimStringType) => return Some(value),
+        (Value::F32(value), WitTypeNode::PrimF64Type) => return Some(Value::F64(value.into())),
+        (Value::U8(value), _) => i128::from(value),
+        (Value::U16(value), _) => i128::from(value),
+        (Value::U32(value), _) => i128::from(value),
+        (Value::U64(value), _) => i128::from(value),
+        (Value::S8(value), _) => i128::from(value),
+        (Value::S16(value), _) => i128::from(value),
+        (Value::S32(value), _) => i128::from(value),
+        (Value::S64(value), _) => i128::from(value),
+        _ => return None,
+    };
+    match new {
+        WitTypeNode::PrimU8Type => u8::try_from(number).ok().map(Value::U8),
+        WitTypeNode::PrimU16Type => u16::try_from(number).ok().map(Value::U16),
+        WitTypeNode::PrimU32Type => u32::try_from(number).ok().map(Value::U32),
+        WitTypeNode::PrimU64Type => u64::try_from(number).ok().map(Value::U64),
+        WitTypeNode::PrimS8Type => i8::try_from(number).ok().map(Value::S8),
+        WitTypeNode::PrimS16Type => i16::try_from(number).ok().map(Value::S16),
+        WitTypeNode::PrimS32Type => i32::try_from(number).ok().map(Value::S32),
+        WitTypeNode::PrimS64Type => i64::try_from(number).ok().map(Value::S64),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use golem_wasm_rpc::Value;
+
+    use crate::value_and_type::compat::{check, is_compatible, upgrade, upgrade_to};
+    use crate::value_and_type::{IntoValue, IntoValueAndType, PathSegment};
+
+    #[test]
+    fn primitives() {
+        assert!(is_compatible(&u8::get_type(), &u32::get_type()));
+        assert!(is_compatible(&u32::get_type(), &i64::get_type()));
+        assert!(is_compatible(&f32::get_type(), &f64::get_type()));
+        assert!(!is_compatible(&u32::get_type(), &i32::get_type()));
+
+        let incompatibilities = check(&<(u64, String)>::get_type(), &<(u8, String)>::get_type());
+        assert_eq!(incompatibilities.len(), 1);
+        assert_eq!(incompatibilities[0].path(), &[PathSegment::Index(0)]);
+        assert_eq!(
+            incompatibilities[0].to_string(),
+            "at [0]: u64 cannot be read as u8"
+        );
+
+        assert_eq!(upgrade_to::<u64>(5u8.into_value_and_type()), Ok(5));
+        assert_eq!(upgrade_to::<f64>(1.5f32.into_value_and_type()), Ok(1.5));
+        assert_eq!(
+            upgrade_to::<Vec<Option<i32>>>(vec![1u16, 2].into_value_and_type()),
+            Ok(vec![Some(1), Some(2)])
+        );
+        assert!(upgrade_to::<(u8, u8)>((1u8,).into_value_and_type()).is_err());
+    }
+
+    #[test]
+    fn none_options() {
+        let upgraded = upgrade(None::<u8>.into_value_and_type(), &Option::<u8>::get_type());
+        assert_eq!(
+            upgraded.map(|upgraded| Value::from(upgraded.value)),
+            Ok(Value::Option(None))
+        );
+        assert_eq!(
+            upgrade_to::<Option<u64>>(None::<u8>.into_value_and_type()),
+            Ok(None)
+        );
+        assert_eq!(
+            upgrade_to::<Option<Option<u32>>>(Some(None::<u8>).into_value_and_type()),
+            Ok(Some(None))
+        );
+        assert_eq!(
+            upgrade_to::<Vec<(Option<String>, Result<Option<u16>, ()>)>>(
+                vec![(None::<String>, Ok::<_, ()>(None::<u8>))].into_value_and_type()
+            ),
+            Ok(vec![(None, Ok(None))])
+        );
+    }
+
+    #[test]
+    fn upgrading_to_the_same_type() {
+        let values = [
+            (
+                None::<u8>,
+                vec![Some("a".to_string()), None],
+                Ok::<_, ()>(Some(None::<u32>)),
+            )
+                .into_value_and_type(),
+            (
+                Some(1u8),
+                Vec::<Option<String>>::new(),
+                Err::<Option<Option<u32>>, ()>(()),
+            )
+                .into_value_and_type(),
+            (
+                None::<u8>,
+                vec![None::<String>],
+                Ok::<Option<Option<u32>>, ()>(None),
+            )
+                .into_value_and_type(),
+        ];
+        for value in values {
+            let typ = value.typ.clone();
+            let upgraded = upgrade(value.clone(), &typ).map(|upgraded| Value::from(upgraded.value));
+            assert_eq!(upgraded, Ok(Value::from(value.value)));
+        }
+    }
+
+    #[cfg(feature = "proptest")]
+    #[test]
+    fn upgrading_generated_values_to_the_same_type() {
+        use proptest::test_runner::TestRunner;
+
+        use crate::value_and_type::proptest::values;
+
+        let typ = <(
+            Option<u8>,
+            Vec<Option<String>>,
+            Result<Option<Option<u32>>, Option<()>>,
+            [Option<bool>; 2],
+        )>::get_type();
+        TestRunner::default()
+            .run(&values(&typ), |value| {
+                let upgraded =
+                    upgrade(value.clone(), &typ).map(|upgraded| Value::from(upgraded.value));
+                proptest::prop_assert_eq!(upgraded, Ok(Value::from(value.value)));
+                Ok(())
+            })
+            .unwrap();
+    }
+}
+
+#[cfg(all(test, feature = "macro"))]
+mod derive_tests {
+    use crate::value_and_type::compat::{check, is_compatible, upgrade, upgrade_to};
+    use crate::value_and_type::{IntoValue, IntoValueAndType, PathSegment};
+    use crate::{FromValueAndType, IntoValue};
+
+    #[derive(Debug, Clone, PartialEq, IntoValue, FromValueAndType)]
+    #[wit(rename = "status")]
+    enum StatusV1 {
+        Open,
+        Closed,
+    }
+
+    #[derive(Debug, Clone, PartialEq, IntoValue, FromValueAndType)]
+    #[wit(rename = "status")]
+    enum StatusV2 {
+        Open,
+        Cancelled,
+        Closed,
+    }
+
+    #[derive(Debug, Clone, PartialEq, IntoValue, FromValueAndType)]
+    #[wit(rename = "payment")]
+    enum PaymentV1 {
+        Cash,
+        Card(String),
+    }
+
+    #[derive(Debug, Clone, PartialEq, IntoValue, FromValueAndType)]
+    #[wit(rename = "payment")]
+    enum PaymentV2 {
+        Card(String),
+        Cash,
+        Voucher(u32),
+    }
+
+    #[derive(Debug, Clone, PartialEq, IntoValue, FromValueAndType)]
+    #[wit(rename = "order")]
+    struct OrderV1 {
+        id: u32,
+        status: StatusV1,
+        payment: PaymentV1,
+        comment: String,
+    }
+
+    #[derive(Debug, Clone, PartialEq, IntoValue, FromValueAndType)]
+    #[wit(rename = "order")]
+    struct OrderV2 {
+        status: StatusV2,
+        id: u64,
+        payment: PaymentV2,
+        discount: Option<u8>,
+    }
+
+    #[derive(IntoValue, FromValueAndType)]
+    struct Tree {
+        children: Vec<Tree>,
+    }
+
+    #[test]
+    fn evolved_types() {
+        assert!(is_compatible(&OrderV1::get_type(), &OrderV2::get_type()));
+
+        let order = OrderV1 {
+            id: 7,
+            status: StatusV1::Closed,
+            payment: PaymentV1::Card("1234".to_string()),
+            comment: "dropped".to_string(),
+        };
+        assert_eq!(
+            upgrade_to::<OrderV2>(order.into_value_and_type()),
+            Ok(OrderV2 {
+                status: StatusV2::Closed,
+                id: 7,
+                payment: PaymentV2::Card("1234".to_string()),
+                discount: None,
+            })
+        );
+
+        let incompatibilities = check(&OrderV2::get_type(), &OrderV1::get_type())
+            .iter()
+            .map(|incompatibility| incompatibility.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            incompatibilities,
+            vec![
+                "at id: u64 cannot be read as u32",
+                "at status::cancelled: case removed",
+                "at payment::voucher: case removed",
+                "at comment: added field is not optional",
+            ]
+        );
+    }
+
+    #[test]
+    fn upgrading_values_of_incompatible_types() {
+        assert_eq!(
+            upgrade_to::<PaymentV1>(PaymentV2::Cash.into_value_and_type()),
+            Ok(PaymentV1::Cash)
+        );
+
+        let err = upgrade(
+            PaymentV2::Voucher(10).into_value_and_type(),
+            &PaymentV1::get_type(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "case voucher does not exist in the new type"
+        );
+
+        let order = OrderV2 {
+            status: StatusV2::Open,
+            id: 1,
+            payment: PaymentV2::Cash,
+            discount: Some(5),
+        };
+        let err = upgrade_to::<OrderV1>(order.into_value_and_type()).unwrap_err();
+        assert_eq!(err.path(), &[PathSegment::Field("id".to_string())]);
+        assert_eq!(err.to_string(), "at id: u64 cannot be read as u32");
+    }
+
+    #[test]
+    fn recursive_types() {
+        assert!(is_compatible(&Tree::get_type(), &Tree::get_type()));
+    }
+}
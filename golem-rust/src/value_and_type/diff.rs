@@ -0,0 +1,308 @@
+// Copyright 2024-2025 Golem Cloud
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Structural differences between two values.
+//!
+//! Record fields are matched by name, so values of different versions of a record type can also
+//! be compared, with fields only present in one of them reported as added or removed. Elements
+//! of lists and tuples are compared by index, and the extra elements of the longer one are
+//! reported as added or removed. The values of variant, option and result cases are compared if
+//! both values are of the same case, otherwise the whole values are reported as changed, as are
+//! differing primitives, enums, flags and handles.
+
+use std::fmt::{Display, Formatter};
+
+use golem_wasm_rpc::golem_rpc_0_2_x::types::ValueAndType;
+
+use crate::value_and_type::error::write_at_path;
+use crate::value_and_type::{DynValue, PathSegment, ValueError};
+
+/// A part of the value which differs between the old and the new value
+#[derive(Debug, Clone, PartialEq)]
+pub struct Difference {
+    path: Vec<PathSegment>,
+    change: Change,
+}
+
+impl Difference {
+    /// Path of the differing part, in the format of `DynValue::get_path`
+    pub fn path(&self) -> &[PathSegment] {
+        &self.path
+    }
+
+    pub fn change(&self) -> &Change {
+        &self.change
+    }
+}
+
+impl Display for Difference {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write_at_path(f, &self.path)?;
+        write!(f, "{}", self.change)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    /// The record field or list or tuple element only exists in the new value
+    Added(DynValue),
+    /// The record field or list or tuple element only exists in the old value
+    Removed(DynValue),
+    Changed {
+        old: DynValue,
+        new: DynValue,
+    },
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Change::Added(new) => write!(f, "added {new}"),
+            Change::Removed(old) => write!(f, "removed {old}"),
+            Change::Changed { old, new } => write!(f, "changed from {old} to {new}"),
+        }
+    }
+}
+
+/// The differences between the values, empty if they are equal. Fails if either value does not
+/// conform to its type.
+pub fn diff(old: &ValueAndType, new: &ValueAndType) -> Result<Vec<Difference>, ValueError> {
+    Ok(diff_dyn(
+        &DynValue::from_value_and_type(old)?,
+        &DynValue::from_value_and_type(new)?,
+    ))
+}
+
+/// The differences between the values, empty if they are equal
+pub fn diff_dyn(old: &DynValue, new: &DynValue) -> Vec<Difference> {
+    let mut differ = Differ {
+        path: Vec::new(),
+        differences: Vec::new(),
+    };
+    differ.diff(old, new);
+    differ.differences
+}
+
+struct Differ {
+    path: Vec<PathSegment>,
+    differences: Vec<Difference>,
+}
+
+impl Differ {
+    fn diff(&mut self, old: &DynValue, new: &DynValue) {
+        match (old, new) {
+            (DynValue::Record(old_fields), DynValue::Record(new_fields)) => {
+                for (name, old_value) in old_fields {
+                    let segment = PathSegment::Field(name.clone());
+                    match new_fields.iter().find(|(new_name, _)| new_name == name) {
+                        Some((_, new_value)) => self.diff_at(segment, old_value, new_value),
+                        None => self.report_at(segment, Change::Removed(old_value.clone())),
+                    }
+                }
+                for (name, new_value) in new_fields {
+                    if !old_fields.iter().any(|(old_name, _)| old_name == name) {
+                        self.report_at(
+                            PathSegment::Field(name.clone()),
+                            Change::Added(new_value.clone()),
+                        );
+                    }
+                }
+            }
+            (DynValue::Tuple(old_items), DynValue::Tuple(new_items))
+            | (DynValue::List(old_items), DynValue::List(new_items)) => {
+                for (idx, old_value) in old_items.iter().enumerate() {
+                    let segment = PathSegment::Index(idx);
+                    match new_items.get(idx) {
+                        Some(new_value) => self.diff_at(segment, old_value, new_value),
+                        None => self.report_at(segment, Change::Removed(old_value.clone())),
+                    }
+                }
+                for (idx, new_value) in new_items.iter().enumerate().skip(old_items.len()) {
+                    self.report_at(PathSegment::Index(idx), Change::Added(new_value.clone()));
+                }
+            }
+            (
+                DynValue::Variant {
+                    case: old_case,
+                    value: Some(old_value),
+                },
+                DynValue::Variant {
+                    case: new_case,
+                    value: Some(new_value),
+                },
+            ) if old_case == new_case => {
+                self.diff_at(PathSegment::Case(old_case.clone()), old_value, new_value)
+            }
+            (DynValue::Option(Some(old_value)), DynValue::Option(Some(new_value))) => {
+                self.diff_at(PathSegment::Case("some".to_string()), old_value, new_value)
+            }
+            (DynValue::Result(Ok(Some(old_value))), DynValue::Result(Ok(Some(new_value)))) => {
+                self.diff_at(PathSegment::Case("ok".to_string()), old_value, new_value)
+            }
+            (DynValue::Result(Err(Some(old_value))), DynValue::Result(Err(Some(new_value)))) => {
+                self.diff_at(PathSegment::Case("err".to_string()), old_value, new_value)
+            }
+            _ if old != new => self.report(Change::Changed {
+                old: old.clone(),
+                new: new.clone(),
+            }),
+            _ => {}
+        }
+    }
+
+    fn diff_at(&mut self, segment: PathSegment, old: &DynValue, new: &DynValue) {
+        self.path.push(segment);
+        self.diff(old, new);
+        self.path.pop();
+    }
+
+    fn report_at(&mut self, segment: PathSegment, change: Change) {
+        self.path.push(segment);
+        self.report(change);
+        self.path.pop();
+    }
+
+    fn report(&mut self, change: Change) {
+        self.differences.push(Difference {
+            path: self.path.clone(),
+            change,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::value_and_type::diff::{diff, diff_dyn, Change};
+    use crate::value_and_type::{DynValue, IntoValueAndType, PathSegment};
+
+    #[test]
+    fn std_types() {
+        let old = (vec![1u8, 2, 3], Some("a".to_string()), Ok::<u8, u8>(1)).into_value_and_type();
+        let new = (vec![1u8, 4], None::<String>, Err::<u8, u8>(1)).into_value_and_type();
+        let differences = diff(&old, &new)
+            .unwrap()
+            .iter()
+            .map(|difference| difference.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            differences,
+            vec![
+                "at [0][1]: changed from 2 to 4",
+                "at [0][2]: removed 3",
+                r#"at [1]: changed from some("a") to none"#,
+                "at [2]: changed from ok(1) to err(1)",
+            ]
+        );
+
+        assert_eq!(diff(&old, &old), Ok(vec![]));
+    }
+
+    #[test]
+    fn records_by_name() {
+        let old = DynValue::Record(vec![
+            ("id".to_string(), DynValue::U64(1)),
+            ("name".to_string(), DynValue::String("old".to_string())),
+        ]);
+        let new = DynValue::Record(vec![
+            ("name".to_string(), DynValue::String("new".to_string())),
+            ("id".to_string(), DynValue::U64(1)),
+            ("tags".to_string(), DynValue::List(vec![])),
+        ]);
+        let differences = diff_dyn(&old, &new);
+        assert_eq!(differences.len(), 2);
+        assert_eq!(
+            differences[0].path(),
+            &[PathSegment::Field("name".to_string())]
+        );
+        assert_eq!(
+            differences[1].change(),
+            &Change::Added(DynValue::List(vec![]))
+        );
+        assert_eq!(differences[1].to_string(), "at tags: added []");
+    }
+}
+
+#[cfg(all(test, feature = "macro"))]
+mod derive_tests {
+    use crate::value_and_type::diff::diff;
+    use crate::value_and_type::IntoValueAndType;
+    use crate::{FromValueAndType, IntoValue};
+
+    #[derive(Debug, Clone, IntoValue, FromValueAndType)]
+    struct Item {
+        sku: String,
+        quantity: u32,
+    }
+
+    #[derive(Debug, Clone, IntoValue, FromValueAndType)]
+    enum Status {
+        Open,
+        Shipped { tracking: String },
+    }
+
+    #[derive(Debug, Clone, IntoValue, FromValueAndType)]
+    struct Order {
+        id: u64,
+        items: Vec<Item>,
+        status: Status,
+    }
+
+    #[test]
+    fn named_types() {
+        let old = Order {
+            id: 1,
+            items: vec![Item {
+                sku: "a-1".to_string(),
+                quantity: 1,
+            }],
+            status: Status::Shipped {
+                tracking: "x".to_string(),
+            },
+        };
+        let mut new = old.clone();
+        new.items[0].quantity = 2;
+        new.items.push(Item {
+            sku: "b-2".to_string(),
+            quantity: 1,
+        });
+        new.status = Status::Shipped {
+            tracking: "y".to_string(),
+        };
+
+        let differences = diff(
+            &old.clone().into_value_and_type(),
+            &new.into_value_and_type(),
+        )
+        .unwrap()
+        .iter()
+        .map(|difference| difference.to_string())
+        .collect::<Vec<_>>();
+        assert_eq!(
+            differences,
+            vec![
+                "at items[0].quantity: changed from 1 to 2",
+                r#"at items[1]: added {sku: "b-2", quantity: 1}"#,
+                r#"at status::shipped.tracking: changed from "x" to "y""#,
+            ]
+        );
+
+        let mut open = old.clone();
+        open.status = Status::Open;
+        let differences = diff(&old.into_value_and_type(), &open.into_value_and_type()).unwrap();
+        assert_eq!(
+            differences[0].to_string(),
+            r#"at status: changed from shipped({tracking: "x"}) to open"#
+        );
+    }
+}
@@ -0,0 +1,571 @@
+// Copyright 2024-2025 Golem Cloud
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt::{Display, Formatter};
+
+use golem_wasm_rpc::golem_rpc_0_2_x::types::ValueAndType;
+use golem_wasm_rpc::{NodeIndex, WitNodePointer, WitType, WitTypeNode, WitValueExtractor};
+
+use crate::value_and_type::{PathSegment, ValueError, ValueKind};
+
+/// Owned value of a type only known at runtime, with the names of record fields, variant cases,
+/// enum values and flags resolved from its `WitType`.
+///
+/// Parts of the value can be accessed by paths such as `order.items[0].sku`, see `DynValue::get`.
+/// `Display` prints the value in a compact form, or indented with the alternate flag (`{:#}`).
+#[derive(Debug, Clone, PartialEq)]
+pub enum DynValue {
+    Bool(bool),
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    S8(i8),
+    S16(i16),
+    S32(i32),
+    S64(i64),
+    F32(f32),
+    F64(f64),
+    Char(char),
+    String(String),
+    Record(Vec<(String, DynValue)>),
+    Variant {
+        case: String,
+        value: Option<Box<DynValue>>,
+    },
+    Enum(String),
+    /// Names of the set flags
+    Flags(Vec<String>),
+    Tuple(Vec<DynValue>),
+    List(Vec<DynValue>),
+    Option(Option<Box<DynValue>>),
+    Result(Result<Option<Box<DynValue>>, Option<Box<DynValue>>>),
+    Handle {
+        uri: String,
+        resource_id: u64,
+    },
+}
+
+impl DynValue {
+    pub fn from_value_and_type(value_and_type: &ValueAndType) -> Result<Self, ValueError> {
+        Self::from_extractor(&value_and_type.value, &value_and_type.typ)
+    }
+
+    /// Extracts a value of the given type
+    pub fn from_extractor<'a, 'b>(
+        extractor: &'a impl WitValueExtractor<'a, 'b>,
+        typ: &WitType,
+    ) -> Result<Self, ValueError> {
+        from_node(extractor, typ, 0).map_err(|err| err.with_expected_type(typ.clone()))
+    }
+
+    pub fn kind(&self) -> ValueKind {
+        match self {
+            DynValue::Bool(_) => ValueKind::Bool,
+            DynValue::U8(_) => ValueKind::U8,
+            DynValue::U16(_) => ValueKind::U16,
+            DynValue::U32(_) => ValueKind::U32,
+            DynValue::U64(_) => ValueKind::U64,
+            DynValue::S8(_) => ValueKind::S8,
+            DynValue::S16(_) => ValueKind::S16,
+            DynValue::S32(_) => ValueKind::S32,
+            DynValue::S64(_) => ValueKind::S64,
+            DynValue::F32(_) => ValueKind::F32,
+            DynValue::F64(_) => ValueKind::F64,
+            DynValue::Char(_) => ValueKind::Char,
+            DynValue::String(_) => ValueKind::String,
+            DynValue::Record(_) => ValueKind::Record,
+            DynValue::Variant { .. } => ValueKind::Variant,
+            DynValue::Enum(_) => ValueKind::Enum,
+            DynValue::Flags(_) => ValueKind::Flags,
+            DynValue::Tuple(_) => ValueKind::Tuple,
+            DynValue::List(_) => ValueKind::List,
+            DynValue::Option(_) => ValueKind::Option,
+            DynValue::Result(_) => ValueKind::Result,
+            DynValue::Handle { .. } => ValueKind::Handle,
+        }
+    }
+
+    /// The part of the value at the given path, in the same format as the paths of `ValueError`:
+    /// `.name` selects a record field, `[idx]` a list or tuple element and `::name` the value of
+    /// a variant, option (`some`) or result (`ok` and `err`) case, which has to be the actual case.
+    /// The leading field name has no dot.
+    ///
+    /// Field and element selections look through `some`, so `order.items[0]` also works when
+    /// `order` or `items` is optional. Returns `None` if the part does not exist or the path is
+    /// malformed.
+    pub fn get(&self, path: &str) -> Option<&DynValue> {
+        self.get_path(&parse_path(path)?)
+    }
+
+    pub fn get_path(&self, path: &[PathSegment]) -> Option<&DynValue> {
+        path.iter()
+            .try_fold(self, |value, segment| value.child(segment))
+    }
+
+    fn child(&self, segment: &PathSegment) -> Option<&DynValue> {
+        match (self, segment) {
+            (DynValue::Record(fields), PathSegment::Field(name)) => fields
+                .iter()
+                .find(|(field_name, _)| field_name == name)
+                .map(|(_, value)| value),
+            (DynValue::Tuple(items) | DynValue::List(items), PathSegment::Index(idx)) => {
+                items.get(*idx)
+            }
+            (
+                DynValue::Variant {
+                    case,
+                    value: Some(value),
+                },
+                PathSegment::Case(name),
+            ) if case == name => Some(value),
+            (DynValue::Option(Some(value)), PathSegment::Case(name)) if name == "some" => {
+                Some(value)
+            }
+            (DynValue::Result(Ok(Some(value))), PathSegment::Case(name)) if name == "ok" => {
+                Some(value)
+            }
+            (DynValue::Result(Err(Some(value))), PathSegment::Case(name)) if name == "err" => {
+                Some(value)
+            }
+            (DynValue::Option(Some(value)), PathSegment::Field(_) | PathSegment::Index(_)) => {
+                value.child(segment)
+            }
+            _ => None,
+        }
+    }
+
+    fn fmt_indented(&self, f: &mut Formatter<'_>, indent: Option<usize>) -> std::fmt::Result {
+        match self {
+            DynValue::Bool(value) => write!(f, "{value}"),
+            DynValue::U8(value) => write!(f, "{value}"),
+            DynValue::U16(value) => write!(f, "{value}"),
+            DynValue::U32(value) => write!(f, "{value}"),
+            DynValue::U64(value) => write!(f, "{value}"),
+            DynValue::S8(value) => write!(f, "{value}"),
+            DynValue::S16(value) => write!(f, "{value}"),
+            DynValue::S32(value) => write!(f, "{value}"),
+            DynValue::S64(value) => write!(f, "{value}"),
+            DynValue::F32(value) => write!(f, "{value}"),
+            DynValue::F64(value) => write!(f, "{value}"),
+            DynValue::Char(value) => write!(f, "{value:?}"),
+            DynValue::String(value) => write!(f, "{value:?}"),
+            DynValue::Record(fields) => fmt_items(
+                f,
+                ('{', '}'),
+                fields
+                    .iter()
+                    .map(|(name, value)| (Some(name.as_str()), value)),
+                indent,
+            ),
+            DynValue::Variant { case, value } => fmt_case(f, case, value.as_deref(), indent),
+            DynValue::Enum(case) => write!(f, "{case}"),
+            DynValue::Flags(flags) => write!(f, "{{{}}}", flags.join(", ")),
+            DynValue::Tuple(items) => {
+                fmt_items(f, ('(', ')'), items.iter().map(|item| (None, item)), indent)
+            }
+            DynValue::List(items) => {
+                fmt_items(f, ('[', ']'), items.iter().map(|item| (None, item)), indent)
+            }
+            DynValue::Option(Some(value)) => fmt_case(f, "some", Some(value), indent),
+            DynValue::Option(None) => write!(f, "none"),
+            DynValue::Result(Ok(value)) => fmt_case(f, "ok", value.as_deref(), indent),
+            DynValue::Result(Err(value)) => fmt_case(f, "err", value.as_deref(), indent),
+            DynValue::Handle { uri, resource_id } => write!(f, "handle({uri:?}, {resource_id})"),
+        }
+    }
+}
+
+impl Display for DynValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let indent = if f.alternate() { Some(0) } else { None };
+        self.fmt_indented(f, indent)
+    }
+}
+
+fn fmt_case(
+    f: &mut Formatter<'_>,
+    case: &str,
+    value: Option<&DynValue>,
+    indent: Option<usize>,
+) -> std::fmt::Result {
+    match value {
+        Some(value) => {
+            write!(f, "{case}(")?;
+            value.fmt_indented(f, indent)?;
+            write!(f, ")")
+        }
+        None => write!(f, "{case}"),
+    }
+}
+
+/// Writes the items between the delimiters, one per line when indenting
+fn fmt_items<'v>(
+    f: &mut Formatter<'_>,
+    (open, close): (char, char),
+    items: impl ExactSizeIterator<Item = (Option<&'v str>, &'v DynValue)>,
+    indent: Option<usize>,
+) -> std::fmt::Result {
+    if items.len() == 0 {
+        return write!(f, "{open}{close}");
+    }
+    write!(f, "{open}")?;
+    for (idx, (label, value)) in items.enumerate() {
+        match indent {
+            Some(level) => write!(f, "\n{}", "  ".repeat(level + 1))?,
+            None if idx > 0 => write!(f, ", ")?,
+            None => {}
+        }
+        if let Some(label) = label {
+            write!(f, "{label}: ")?;
+        }
+        value.fmt_indented(f, indent.map(|level| level + 1))?;
+        if indent.is_some() {
+            write!(f, ",")?;
+        }
+    }
+    if let Some(level) = indent {
+        write!(f, "\n{}", "  ".repeat(level))?;
+    }
+    write!(f, "{close}")
+}
+
+fn from_node<'a, 'b>(
+    extractor: &'a impl WitValueExtractor<'a, 'b>,
+    typ: &WitType,
+    node_idx: NodeIndex,
+) -> Result<DynValue, ValueError> {
+    let node = typ
+        .nodes
+        .get(node_idx as usize)
+        .ok_or_else(|| ValueError::custom(format!("type node {node_idx} does not exist")))?;
+    let unexpected = |kind| ValueError::unexpected(kind, extractor);
+    match &node.type_ {
+        WitTypeNode::PrimBoolType => extractor
+            .bool()
+            .map(DynValue::Bool)
+            .ok_or_else(|| unexpected(ValueKind::Bool)),
+        WitTypeNode::PrimU8Type => extractor
+            .u8()
+            .map(DynValue::U8)
+            .ok_or_else(|| unexpected(ValueKind::U8)),
+        WitTypeNode::PrimU16Type => extractor
+            .u16()
+            .map(DynValue::U16)
+            .ok_or_else(|| unexpected(ValueKind::U16)),
+        WitTypeNode::PrimU32Type => extractor
+            .u32()
+            .map(DynValue::U32)
+            .ok_or_else(|| unexpected(ValueKind::U32)),
+        WitTypeNode::PrimU64Type => extractor
+            .u64()
+            .map(DynValue::U64)
+            .ok_or_else(|| unexpected(ValueKind::U64)),
+        WitTypeNode::PrimS8Type => extractor
+            .s8()
+            .map(DynValue::S8)
+            .ok_or_else(|| unexpected(ValueKind::S8)),
+        WitTypeNode::PrimS16Type => extractor
+            .s16()
+            .map(DynValue::S16)
+            .ok_or_else(|| unexpected(ValueKind::S16)),
+        WitTypeNode::PrimS32Type => extractor
+            .s32()
+            .map(DynValue::S32)
+            .ok_or_else(|| unexpected(ValueKind::S32)),
+        WitTypeNode::PrimS64Type => extractor
+            .s64()
+            .map(DynValue::S64)
+            .ok_or_else(|| unexpected(ValueKind::S64)),
+        WitTypeNode::PrimF32Type => extractor
+            .f32()
+            .map(DynValue::F32)
+            .ok_or_else(|| unexpected(ValueKind::F32)),
+        WitTypeNode::PrimF64Type => extractor
+            .f64()
+            .map(DynValue::F64)
+            .ok_or_else(|| unexpected(ValueKind::F64)),
+        WitTypeNode::PrimCharType => extractor
+            .char()
+            .map(DynValue::Char)
+            .ok_or_else(|| unexpected(ValueKind::Char)),
+        WitTypeNode::PrimStringType => extractor
+            .string()
+            .map(|value| DynValue::String(value.to_string()))
+            .ok_or_else(|| unexpected(ValueKind::String)),
+        WitTypeNode::RecordType(fields) => fields
+            .iter()
+            .enumerate()
+            .map(|(idx, (name, field_type))| {
+                let field = extractor
+                    .field(idx)
+                    .ok_or_else(|| ValueError::missing_field(extractor, name))?;
+                let value =
+                    from_node(&field, typ, *field_type).map_err(|err| err.at_field(name))?;
+                Ok((name.clone(), value))
+            })
+            .collect::<Result<_, _>>()
+            .map(DynValue::Record),
+        WitTypeNode::VariantType(cases) => {
+            let (case_idx, value) = extractor
+                .variant()
+                .ok_or_else(|| unexpected(ValueKind::Variant))?;
+            let (case, case_type) = cases
+                .get(case_idx as usize)
+                .ok_or_else(|| ValueError::invalid_case(case_idx))?;
+            let value = case_value(value, *case_type, typ, case)?;
+            Ok(DynValue::Variant {
+                case: case.clone(),
+                value,
+            })
+        }
+        WitTypeNode::EnumType(cases) => {
+            let case_idx = extractor
+                .enum_value()
+                .ok_or_else(|| unexpected(ValueKind::Enum))?;
+            cases
+                .get(case_idx as usize)
+                .map(|case| DynValue::Enum(case.clone()))
+                .ok_or_else(|| ValueError::invalid_case(case_idx))
+        }
+        WitTypeNode::FlagsType(names) => {
+            let flags = extractor
+                .flags()
+                .ok_or_else(|| unexpected(ValueKind::Flags))?;
+            Ok(DynValue::Flags(
+                names
+                    .iter()
+                    .zip(flags)
+                    .filter(|(_, set)| **set)
+                    .map(|(name, _)| name.clone())
+                    .collect(),
+            ))
+        }
+        WitTypeNode::TupleType(items) => items
+            .iter()
+            .enumerate()
+            .map(|(idx, item_type)| {
+                let item = extractor
+                    .tuple_element(idx)
+                    .ok_or_else(|| ValueError::missing_element(extractor, idx))?;
+                from_node(&item, typ, *item_type).map_err(|err| err.at_index(idx))
+            })
+            .collect::<Result<_, _>>()
+            .map(DynValue::Tuple),
+        WitTypeNode::ListType(item_type) => extractor
+            .list_elements(|item| from_node(&item, typ, *item_type))
+            .ok_or_else(|| unexpected(ValueKind::List))?
+            .into_iter()
+            .enumerate()
+            .map(|(idx, item)| item.map_err(|err| err.at_index(idx)))
+            .collect::<Result<_, _>>()
+            .map(DynValue::List),
+        WitTypeNode::OptionType(inner_type) => {
+            match extractor
+                .option()
+                .ok_or_else(|| unexpected(ValueKind::Option))?
+            {
+                Some(value) => Ok(DynValue::Option(case_value(
+                    Some(value),
+                    Some(*inner_type),
+                    typ,
+                    "some",
+                )?)),
+                None => Ok(DynValue::Option(None)),
+            }
+        }
+        WitTypeNode::ResultType((ok_type, err_type)) => {
+            match extractor
+                .result()
+                .ok_or_else(|| unexpected(ValueKind::Result))?
+            {
+                Ok(value) => Ok(DynValue::Result(Ok(case_value(
+                    value, *ok_type, typ, "ok",
+                )?))),
+                Err(value) => Ok(DynValue::Result(Err(case_value(
+                    value, *err_type, typ, "err",
+                )?))),
+            }
+        }
+        WitTypeNode::HandleType(_) => {
+            let (uri, resource_id) = extractor
+                .handle()
+                .ok_or_else(|| unexpected(ValueKind::Handle))?;
+            Ok(DynValue::Handle {
+                uri: uri.value,
+                resource_id,
+            })
+        }
+    }
+}
+
+/// The value of a variant, option or result case, which has to be present exactly if the type
+/// of the case has a value
+fn case_value(
+    value: Option<WitNodePointer>,
+    case_type: Option<NodeIndex>,
+    typ: &WitType,
+    case: &str,
+) -> Result<Option<Box<DynValue>>, ValueError> {
+    match (value, case_type) {
+        (Some(value), Some(case_type)) => from_node(&value, typ, case_type)
+            .map(|value| Some(Box::new(value)))
+            .map_err(|err| err.at_case(case)),
+        (None, None) => Ok(None),
+        (None, Some(_)) => Err(ValueError::missing_case_value(case)),
+        (Some(_), None) => {
+            Err(ValueError::custom("unexpected value of a case without one").at_case(case))
+        }
+    }
+}
+
+fn parse_path(path: &str) -> Option<Vec<PathSegment>> {
+    let mut segments = Vec::new();
+    let mut rest = path;
+    if !rest.is_empty() && !rest.starts_with(['[', ':']) {
+        let (name, tail) = split_name(rest)?;
+        segments.push(PathSegment::Field(name));
+        rest = tail;
+    }
+    while !rest.is_empty() {
+        if let Some(tail) = rest.strip_prefix('.') {
+            let (name, tail) = split_name(tail)?;
+            segments.push(PathSegment::Field(name));
+            rest = tail;
+        } else if let Some(tail) = rest.strip_prefix("::") {
+            let (name, tail) = split_name(tail)?;
+            segments.push(PathSegment::Case(name));
+            rest = tail;
+        } else if let Some(tail) = rest.strip_prefix('[') {
+            let (idx, tail) = tail.split_once(']')?;
+            segments.push(PathSegment::Index(idx.parse().ok()?));
+            rest = tail;
+        } else {
+            return None;
+        }
+    }
+    Some(segments)
+}
+
+fn split_name(path: &str) -> Option<(String, &str)> {
+    let end = path.find(['.', '[', ']', ':']).unwrap_or(path.len());
+    if end == 0 {
+        None
+    } else {
+        Some((path[..end].to_string(), &path[end..]))
+    }
+}
+
+#[cfg(all(test, feature = "macro"))]
+mod tests {
+    use crate::value_and_type::{DynValue, IntoValue, IntoValueAndType, PathSegment, ValueError};
+    use crate::IntoValue;
+    use golem_wasm_rpc::golem_rpc_0_2_x::types::ValueAndType;
+    use golem_wasm_rpc::Value;
+
+    #[derive(IntoValue)]
+    struct Item {
+        sku: String,
+        quantity: u32,
+    }
+
+    #[derive(IntoValue)]
+    enum Payment {
+        Card { number: String },
+        Cash,
+    }
+
+    #[derive(IntoValue)]
+    struct Order {
+        items: Vec<Item>,
+        payment: Payment,
+        note: Option<String>,
+    }
+
+    fn order() -> DynValue {
+        let order = Order {
+            items: vec![
+                Item {
+                    sku: "apple".to_string(),
+                    quantity: 2,
+                },
+                Item {
+                    sku: "pear".to_string(),
+                    quantity: 1,
+                },
+            ],
+            payment: Payment::Card {
+                number: "1234".to_string(),
+            },
+            note: Some("fragile".to_string()),
+        };
+        DynValue::from_value_and_type(
+            &(Some(order), Payment::Cash, None::<u8>).into_value_and_type(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn path_accessors() {
+        let value = order();
+        assert_eq!(
+            value.get("[0].items[1].sku"),
+            Some(&DynValue::String("pear".to_string()))
+        );
+        assert_eq!(
+            value.get("[0]::some.payment::card.number"),
+            Some(&DynValue::String("1234".to_string()))
+        );
+        assert_eq!(
+            value.get("[0].note::some"),
+            Some(&DynValue::String("fragile".to_string()))
+        );
+        assert_eq!(
+            value.get("[1]"),
+            Some(&DynValue::Variant {
+                case: "cash".to_string(),
+                value: None
+            })
+        );
+        assert_eq!(value.get("[0].payment::cash"), None);
+        assert_eq!(value.get("[2]"), Some(&DynValue::Option(None)));
+        assert_eq!(value.get("[0].items[2]"), None);
+        assert_eq!(value.get("[0]..items"), None);
+        assert_eq!(value.get(""), Some(&value));
+    }
+
+    #[test]
+    fn display() {
+        let value = order();
+        assert_eq!(
+            value.to_string(),
+            r#"(some({items: [{sku: "apple", quantity: 2}, {sku: "pear", quantity: 1}], payment: card({number: "1234"}), note: some("fragile")}), cash, none)"#
+        );
+        assert_eq!(
+            format!("{:#}", value.get("[0].items[0]").unwrap()),
+            "{\n  sku: \"apple\",\n  quantity: 2,\n}"
+        );
+    }
+
+    #[test]
+    fn mismatching_value() {
+        let value_and_type = ValueAndType {
+            value: Value::Tuple(vec![Value::U8(1)]).into(),
+            typ: <(u8, String)>::get_type(),
+        };
+        let err = DynValue::from_value_and_type(&value_and_type).unwrap_err();
+        assert_eq!(err.path(), &[PathSegment::Index(1)]);
+        assert_eq!(err, ValueError::missing_element(&value_and_type.value, 1));
+    }
+}
@@ -0,0 +1,381 @@
+// Copyright 2024-2025 Golem Cloud
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt::{Display, Formatter};
+
+use golem_wasm_rpc::{WitNode, WitType, WitTypeNode, WitValueExtractor};
+
+/// Error of `FromValueAndType`, pointing to the part of the value which could not be converted.
+///
+/// Can be converted to and from `String` for compatibility with conversions written before it
+/// existed; the string form is the `Display` output.
+#[derive(Debug, Clone)]
+pub struct ValueError {
+    path: Vec<PathSegment>,
+    kind: ValueErrorKind,
+    expected_type: Option<WitType>,
+}
+
+impl ValueError {
+    pub fn new(kind: ValueErrorKind) -> Self {
+        Self {
+            path: Vec::new(),
+            kind,
+            expected_type: None,
+        }
+    }
+
+    pub fn custom(message: impl Into<String>) -> Self {
+        Self::new(ValueErrorKind::Custom(message.into()))
+    }
+
+    /// The value is not of the `expected` kind
+    pub fn unexpected<'a, 'b>(
+        expected: ValueKind,
+        extractor: &'a impl WitValueExtractor<'a, 'b>,
+    ) -> Self {
+        Self::new(ValueErrorKind::UnexpectedKind {
+            expected,
+            actual: ValueKind::of(extractor),
+        })
+    }
+
+    pub fn invalid_case(case_idx: u32) -> Self {
+        Self::new(ValueErrorKind::InvalidCase(case_idx))
+    }
+
+    /// The record field with the given name is missing, or the value is not a record at all
+    pub fn missing_field<'a, 'b>(
+        extractor: &'a impl WitValueExtractor<'a, 'b>,
+        name: &str,
+    ) -> Self {
+        match ValueKind::of(extractor) {
+            Some(ValueKind::Record) | None => Self::new(ValueErrorKind::Missing).at_field(name),
+            Some(_) => Self::unexpected(ValueKind::Record, extractor),
+        }
+    }
+
+    /// The tuple element with the given index is missing, or the value is not a tuple at all
+    pub fn missing_element<'a, 'b>(
+        extractor: &'a impl WitValueExtractor<'a, 'b>,
+        idx: usize,
+    ) -> Self {
+        match ValueKind::of(extractor) {
+            Some(ValueKind::Tuple) | None => Self::new(ValueErrorKind::Missing).at_index(idx),
+            Some(_) => Self::unexpected(ValueKind::Tuple, extractor),
+        }
+    }
+
+    /// A variant or result case has no value, while the target type requires one
+    pub fn missing_case_value(case: &str) -> Self {
+        Self::new(ValueErrorKind::Missing).at_case(case)
+    }
+
+    /// Prepends a record field to the path
+    pub fn at_field(self, name: impl Into<String>) -> Self {
+        self.at(PathSegment::Field(name.into()))
+    }
+
+    /// Prepends a list or tuple index to the path
+    pub fn at_index(self, idx: usize) -> Self {
+        self.at(PathSegment::Index(idx))
+    }
+
+    /// Prepends a variant, option or result case to the path
+    pub fn at_case(self, name: impl Into<String>) -> Self {
+        self.at(PathSegment::Case(name.into()))
+    }
+
+    pub fn with_expected_type(mut self, expected_type: WitType) -> Self {
+        self.expected_type = Some(expected_type);
+        self
+    }
+
+    /// Path from the root of the value to the part which failed to convert
+    pub fn path(&self) -> &[PathSegment] {
+        &self.path
+    }
+
+    pub fn kind(&self) -> &ValueErrorKind {
+        &self.kind
+    }
+
+    pub fn expected_type(&self) -> Option<&WitType> {
+        self.expected_type.as_ref()
+    }
+
+    fn at(mut self, segment: PathSegment) -> Self {
+        self.path.insert(0, segment);
+        self
+    }
+}
+
+/// The expected type is informational and not compared
+impl PartialEq for ValueError {
+    fn eq(&self, other: &Self) -> bool {
+        self.path == other.path && self.kind == other.kind
+    }
+}
+
+impl Display for ValueError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write_at_path(f, &self.path)?;
+        write!(f, "{}", self.kind)
+    }
+}
+
+/// Writes the `at a[0]::some.b: ` prefix of messages about a part of a value, if the path is not
+/// empty
+pub(crate) fn write_at_path(f: &mut Formatter<'_>, path: &[PathSegment]) -> std::fmt::Result {
+    if path.is_empty() {
+        return Ok(());
+    }
+    write!(f, "at ")?;
+    for (idx, segment) in path.iter().enumerate() {
+        match segment {
+            PathSegment::Field(name) if idx == 0 => write!(f, "{name}")?,
+            PathSegment::Field(name) => write!(f, ".{name}")?,
+            PathSegment::Index(idx) => write!(f, "[{idx}]")?,
+            PathSegment::Case(name) => write!(f, "::{name}")?,
+        }
+    }
+    write!(f, ": ")
+}
+
+impl std::error::Error for ValueError {}
+
+impl From<String> for ValueError {
+    fn from(message: String) -> Self {
+        Self::custom(message)
+    }
+}
+
+impl From<&str> for ValueError {
+    fn from(message: &str) -> Self {
+        Self::custom(message)
+    }
+}
+
+impl From<ValueError> for String {
+    fn from(error: ValueError) -> Self {
+        error.to_string()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathSegment {
+    Field(String),
+    Index(usize),
+    Case(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValueErrorKind {
+    /// The value is of a different kind than expected. The actual kind is `None` for empty
+    /// records and tuples, which cannot be told apart.
+    UnexpectedKind {
+        expected: ValueKind,
+        actual: Option<ValueKind>,
+    },
+    /// The part of the value pointed to by the path does not exist
+    Missing,
+    /// The variant case or enum value does not exist in the target type
+    InvalidCase(u32),
+    Custom(String),
+}
+
+impl Display for ValueErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValueErrorKind::UnexpectedKind {
+                expected,
+                actual: Some(actual),
+            } => write!(f, "expected {expected}, found {actual}"),
+            ValueErrorKind::UnexpectedKind {
+                expected,
+                actual: None,
+            } => write!(f, "expected {expected}, found an empty record or tuple"),
+            ValueErrorKind::Missing => write!(f, "missing value"),
+            ValueErrorKind::InvalidCase(case_idx) => write!(f, "invalid case index {case_idx}"),
+            ValueErrorKind::Custom(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+/// Kind of a value node, named after the corresponding WIT type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueKind {
+    Bool,
+    U8,
+    U16,
+    U32,
+    U64,
+    S8,
+    S16,
+    S32,
+    S64,
+    F32,
+    F64,
+    Char,
+    String,
+    Record,
+    Variant,
+    Enum,
+    Flags,
+    Tuple,
+    List,
+    Option,
+    Result,
+    Handle,
+}
+
+impl ValueKind {
+    /// Kind of the node the extractor points to, `None` for empty records and tuples
+    pub fn of<'a, 'b>(extractor: &'a impl WitValueExtractor<'a, 'b>) -> Option<Self> {
+        let kind = if extractor.bool().is_some() {
+            ValueKind::Bool
+        } else if extractor.u8().is_some() {
+            ValueKind::U8
+        } else if extractor.u16().is_some() {
+            ValueKind::U16
+        } else if extractor.u32().is_some() {
+            ValueKind::U32
+        } else if extractor.u64().is_some() {
+            ValueKind::U64
+        } else if extractor.s8().is_some() {
+            ValueKind::S8
+        } else if extractor.s16().is_some() {
+            ValueKind::S16
+        } else if extractor.s32().is_some() {
+            ValueKind::S32
+        } else if extractor.s64().is_some() {
+            ValueKind::S64
+        } else if extractor.f32().is_some() {
+            ValueKind::F32
+        } else if extractor.f64().is_some() {
+            ValueKind::F64
+        } else if extractor.char().is_some() {
+            ValueKind::Char
+        } else if extractor.string().is_some() {
+            ValueKind::String
+        } else if extractor.field(0).is_some() {
+            ValueKind::Record
+        } else if extractor.variant().is_some() {
+            ValueKind::Variant
+        } else if extractor.enum_value().is_some() {
+            ValueKind::Enum
+        } else if extractor.flags().is_some() {
+            ValueKind::Flags
+        } else if extractor.tuple_element(0).is_some() {
+            ValueKind::Tuple
+        } else if extractor.list_elements(|_| ()).is_some() {
+            ValueKind::List
+        } else if extractor.option().is_some() {
+            ValueKind::Option
+        } else if extractor.result().is_some() {
+            ValueKind::Result
+        } else if extractor.handle().is_some() {
+            ValueKind::Handle
+        } else {
+            return None;
+        };
+        Some(kind)
+    }
+
+    /// Kind of the value node
+    pub fn of_node(node: &WitNode) -> Self {
+        match node {
+            WitNode::RecordValue(_) => ValueKind::Record,
+            WitNode::VariantValue(_) => ValueKind::Variant,
+            WitNode::EnumValue(_) => ValueKind::Enum,
+            WitNode::FlagsValue(_) => ValueKind::Flags,
+            WitNode::TupleValue(_) => ValueKind::Tuple,
+            WitNode::ListValue(_) => ValueKind::List,
+            WitNode::OptionValue(_) => ValueKind::Option,
+            WitNode::ResultValue(_) => ValueKind::Result,
+            WitNode::PrimU8(_) => ValueKind::U8,
+            WitNode::PrimU16(_) => ValueKind::U16,
+            WitNode::PrimU32(_) => ValueKind::U32,
+            WitNode::PrimU64(_) => ValueKind::U64,
+            WitNode::PrimS8(_) => ValueKind::S8,
+            WitNode::PrimS16(_) => ValueKind::S16,
+            WitNode::PrimS32(_) => ValueKind::S32,
+            WitNode::PrimS64(_) => ValueKind::S64,
+            WitNode::PrimFloat32(_) => ValueKind::F32,
+            WitNode::PrimFloat64(_) => ValueKind::F64,
+            WitNode::PrimChar(_) => ValueKind::Char,
+            WitNode::PrimBool(_) => ValueKind::Bool,
+            WitNode::PrimString(_) => ValueKind::String,
+            WitNode::Handle(_) => ValueKind::Handle,
+        }
+    }
+
+    /// Kind of the values of the type node
+    pub fn of_type(node: &WitTypeNode) -> Self {
+        match node {
+            WitTypeNode::PrimBoolType => ValueKind::Bool,
+            WitTypeNode::PrimU8Type => ValueKind::U8,
+            WitTypeNode::PrimU16Type => ValueKind::U16,
+            WitTypeNode::PrimU32Type => ValueKind::U32,
+            WitTypeNode::PrimU64Type => ValueKind::U64,
+            WitTypeNode::PrimS8Type => ValueKind::S8,
+            WitTypeNode::PrimS16Type => ValueKind::S16,
+            WitTypeNode::PrimS32Type => ValueKind::S32,
+            WitTypeNode::PrimS64Type => ValueKind::S64,
+            WitTypeNode::PrimF32Type => ValueKind::F32,
+            WitTypeNode::PrimF64Type => ValueKind::F64,
+            WitTypeNode::PrimCharType => ValueKind::Char,
+            WitTypeNode::PrimStringType => ValueKind::String,
+            WitTypeNode::RecordType(_) => ValueKind::Record,
+            WitTypeNode::VariantType(_) => ValueKind::Variant,
+            WitTypeNode::EnumType(_) => ValueKind::Enum,
+            WitTypeNode::FlagsType(_) => ValueKind::Flags,
+            WitTypeNode::TupleType(_) => ValueKind::Tuple,
+            WitTypeNode::ListType(_) => ValueKind::List,
+            WitTypeNode::OptionType(_) => ValueKind::Option,
+            WitTypeNode::ResultType(_) => ValueKind::Result,
+            WitTypeNode::HandleType(_) => ValueKind::Handle,
+        }
+    }
+}
+
+impl Display for ValueKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ValueKind::Bool => "bool",
+            ValueKind::U8 => "u8",
+            ValueKind::U16 => "u16",
+            ValueKind::U32 => "u32",
+            ValueKind::U64 => "u64",
+            ValueKind::S8 => "s8",
+            ValueKind::S16 => "s16",
+            ValueKind::S32 => "s32",
+            ValueKind::S64 => "s64",
+            ValueKind::F32 => "f32",
+            ValueKind::F64 => "f64",
+            ValueKind::Char => "char",
+            ValueKind::String => "string",
+            ValueKind::Record => "record",
+            ValueKind::Variant => "variant",
+            ValueKind::Enum => "enum",
+            ValueKind::Flags => "flags",
+            ValueKind::Tuple => "tuple",
+            ValueKind::List => "list",
+            ValueKind::Option => "option",
+            ValueKind::Result => "result",
+            ValueKind::Handle => "handle",
+        };
+        write!(f, "{name}")
+    }
+}
@@ -0,0 +1,182 @@
+// Copyright 2024-2025 Golem Cloud
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt::{Debug, Formatter};
+use std::marker::PhantomData;
+
+use golem_wasm_rpc::golem_rpc_0_2_x::types::ResourceId;
+use golem_wasm_rpc::{ResourceMode, Uri, WitValueExtractor};
+
+use crate::value_and_type::{
+    FromValueAndType, IntoValue, NodeBuilder, TypeNodeBuilder, ValueError, ValueKind,
+};
+
+/// Describes a resource type, used as the type parameter of `Handle` and `BorrowedHandle`
+pub trait ResourceType {
+    /// Identifies the resource type in the handle nodes of `WitType`
+    const RESOURCE_ID: ResourceId;
+    /// Name of the resource type
+    const NAME: Option<&'static str> = None;
+    /// Owner of the resource type, for example `"my:component/api"`
+    const OWNER: Option<&'static str> = None;
+}
+
+/// Owned handle of a resource of type `T`, identified by the URI of the worker owning the
+/// resource and the resource's handle value in that worker
+pub struct Handle<T> {
+    uri: String,
+    value: u64,
+    _resource: PhantomData<T>,
+}
+
+impl<T> Handle<T> {
+    pub fn new(uri: impl Into<String>, value: u64) -> Self {
+        Self {
+            uri: uri.into(),
+            value,
+            _resource: PhantomData,
+        }
+    }
+
+    pub fn uri(&self) -> &str {
+        &self.uri
+    }
+
+    pub fn value(&self) -> u64 {
+        self.value
+    }
+
+    /// A borrowed handle of the same resource
+    pub fn borrow(&self) -> BorrowedHandle<T> {
+        BorrowedHandle::new(self.uri.clone(), self.value)
+    }
+}
+
+/// Borrowed handle of a resource of type `T`
+pub struct BorrowedHandle<T> {
+    uri: String,
+    value: u64,
+    _resource: PhantomData<T>,
+}
+
+impl<T> BorrowedHandle<T> {
+    pub fn new(uri: impl Into<String>, value: u64) -> Self {
+        Self {
+            uri: uri.into(),
+            value,
+            _resource: PhantomData,
+        }
+    }
+
+    pub fn uri(&self) -> &str {
+        &self.uri
+    }
+
+    pub fn value(&self) -> u64 {
+        self.value
+    }
+}
+
+macro_rules! handle_impls {
+    ($handle:ident, $mode:expr) => {
+        // Implemented by hand to not require the marker type to implement the traits
+        impl<T> Clone for $handle<T> {
+            fn clone(&self) -> Self {
+                Self::new(self.uri.clone(), self.value)
+            }
+        }
+
+        impl<T> PartialEq for $handle<T> {
+            fn eq(&self, other: &Self) -> bool {
+                self.uri == other.uri && self.value == other.value
+            }
+        }
+
+        impl<T> Eq for $handle<T> {}
+
+        impl<T> Debug for $handle<T> {
+            fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+                f.debug_struct(stringify!($handle))
+                    .field("uri", &self.uri)
+                    .field("value", &self.value)
+                    .finish()
+            }
+        }
+
+        impl<T: ResourceType> IntoValue for $handle<T> {
+            fn add_to_builder<B: NodeBuilder>(self, builder: B) -> B::Result {
+                builder.handle(Uri { value: self.uri }, self.value)
+            }
+
+            fn add_to_type_builder<B: TypeNodeBuilder>(builder: B) -> B::Result {
+                builder.handle(
+                    T::NAME.map(|name| name.to_string()),
+                    T::OWNER.map(|owner| owner.to_string()),
+                    T::RESOURCE_ID,
+                    $mode,
+                )
+            }
+        }
+
+        impl<T: ResourceType> FromValueAndType for $handle<T> {
+            fn from_extractor<'a, 'b>(
+                extractor: &'a impl WitValueExtractor<'a, 'b>,
+            ) -> Result<Self, ValueError> {
+                let (uri, value) = extractor
+                    .handle()
+                    .ok_or_else(|| ValueError::unexpected(ValueKind::Handle, extractor))?;
+                Ok(Self::new(uri.value, value))
+            }
+        }
+    };
+}
+
+handle_impls!(Handle, ResourceMode::Owned);
+handle_impls!(BorrowedHandle, ResourceMode::Borrowed);
+
+#[cfg(test)]
+mod tests {
+    use crate::value_and_type::test_utils::Counter;
+    use crate::value_and_type::{
+        BorrowedHandle, FromValueAndType, Handle, IntoValue, IntoValueAndType,
+    };
+    use golem_wasm_rpc::{ResourceMode, WitTypeNode};
+
+    #[test]
+    fn handles_roundtrip() {
+        let handle = Handle::<Counter>::new("urn:worker:component/worker", 42);
+        let result = Handle::from_value_and_type(handle.clone().into_value_and_type());
+        assert_eq!(result, Ok(handle.clone()));
+
+        let borrowed = handle.borrow();
+        let result = BorrowedHandle::from_value_and_type(borrowed.clone().into_value_and_type());
+        assert_eq!(result, Ok(borrowed));
+    }
+
+    #[test]
+    fn handle_types() {
+        let typ = Handle::<Counter>::get_type();
+        assert_eq!(typ.nodes[0].name.as_deref(), Some("counter"));
+        assert!(matches!(
+            typ.nodes[0].type_,
+            WitTypeNode::HandleType((7, ResourceMode::Owned))
+        ));
+
+        let typ = Option::<BorrowedHandle<Counter>>::get_type();
+        assert!(matches!(
+            typ.nodes[1].type_,
+            WitTypeNode::HandleType((7, ResourceMode::Borrowed))
+        ));
+    }
+}
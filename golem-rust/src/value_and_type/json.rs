@@ -0,0 +1,557 @@
+// Copyright 2024-2025 Golem Cloud
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Type-directed mapping between values and JSON.
+//!
+//! - primitive types are numbers, booleans and strings, `char` is a string of one character
+//! - records are objects with the field names as keys
+//! - variants and results are objects with the case name (`ok` or `err` for results) as their
+//!   single key, and the case value or `null` as its value
+//! - enums are strings of the case name, flags are arrays of the names of the set flags
+//! - tuples and lists are arrays
+//! - options are `null` or the inner value. If the inner type is also an option, present values
+//!   are objects with the `some` key instead, so `some(none)` is `{"some": null}`.
+//! - handles are objects with the `uri` and `value` keys
+//!
+//! Decoding validates the JSON against the type. It also accepts unit variant cases as plain
+//! strings and fields of option types left out of objects. Floats which are not finite cannot be
+//! encoded.
+
+use golem_wasm_rpc::golem_rpc_0_2_x::types::ValueAndType;
+use golem_wasm_rpc::{NodeIndex, Value, WitType, WitTypeNode};
+use serde_json::{Map, Number};
+
+use crate::value_and_type::{
+    DynValue, FromValueAndType, IntoValue, IntoValueAndType, ValueError, ValueErrorKind,
+};
+
+/// Encodes the value as JSON
+pub fn encode(value_and_type: &ValueAndType) -> Result<serde_json::Value, ValueError> {
+    encode_dyn(&DynValue::from_value_and_type(value_and_type)?)
+}
+
+/// Decodes JSON as a value of the given type
+pub fn decode(json: &serde_json::Value, typ: &WitType) -> Result<ValueAndType, ValueError> {
+    let value = decode_node(json, typ, 0).map_err(|err| err.with_expected_type(typ.clone()))?;
+    Ok(ValueAndType {
+        value: value.into(),
+        typ: typ.clone(),
+    })
+}
+
+/// Encodes a Rust value as JSON
+pub fn to_json<T: IntoValue>(value: T) -> Result<serde_json::Value, ValueError> {
+    encode(&value.into_value_and_type())
+}
+
+/// Decodes JSON as a Rust value
+pub fn from_json<T: IntoValue + FromValueAndType>(
+    json: &serde_json::Value,
+) -> Result<T, ValueError> {
+    T::from_value_and_type(decode(json, &T::get_type())?)
+}
+
+fn encode_dyn(value: &DynValue) -> Result<serde_json::Value, ValueError> {
+    let json = match value {
+        DynValue::Bool(value) => serde_json::Value::Bool(*value),
+        DynValue::U8(value) => (*value).into(),
+        DynValue::U16(value) => (*value).into(),
+        DynValue::U32(value) => (*value).into(),
+        DynValue::U64(value) => (*value).into(),
+        DynValue::S8(value) => (*value).into(),
+        DynValue::S16(value) => (*value).into(),
+        DynValue::S32(value) => (*value).into(),
+        DynValue::S64(value) => (*value).into(),
+        DynValue::F32(value) => encode_float(*value as f64)?,
+        DynValue::F64(value) => encode_float(*value)?,
+        DynValue::Char(value) => serde_json::Value::String(value.to_string()),
+        DynValue::String(value) => serde_json::Value::String(value.clone()),
+        DynValue::Record(fields) => serde_json::Value::Object(
+            fields
+                .iter()
+                .map(|(name, value)| {
+                    Ok((
+                        name.clone(),
+                        encode_dyn(value).map_err(|err| err.at_field(name))?,
+                    ))
+                })
+                .collect::<Result<_, ValueError>>()?,
+        ),
+        DynValue::Variant { case, value } => encode_case(case, value.as_deref())?,
+        DynValue::Enum(case) => serde_json::Value::String(case.clone()),
+        DynValue::Flags(flags) => serde_json::Value::Array(
+            flags
+                .iter()
+                .map(|flag| serde_json::Value::String(flag.clone()))
+                .collect(),
+        ),
+        DynValue::Tuple(items) | DynValue::List(items) => serde_json::Value::Array(
+            items
+                .iter()
+                .enumerate()
+                .map(|(idx, item)| encode_dyn(item).map_err(|err| err.at_index(idx)))
+                .collect::<Result<_, _>>()?,
+        ),
+        DynValue::Option(Some(value)) => {
+            let json = encode_dyn(value).map_err(|err| err.at_case("some"))?;
+            if matches!(**value, DynValue::Option(_)) {
+                serde_json::Value::Object(Map::from_iter([("some".to_string(), json)]))
+            } else {
+                json
+            }
+        }
+        DynValue::Option(None) => serde_json::Value::Null,
+        DynValue::Result(Ok(value)) => encode_case("ok", value.as_deref())?,
+        DynValue::Result(Err(value)) => encode_case("err", value.as_deref())?,
+        DynValue::Handle { uri, resource_id } => serde_json::json!({
+            "uri": uri,
+            "value": resource_id,
+        }),
+    };
+    Ok(json)
+}
+
+fn encode_float(value: f64) -> Result<serde_json::Value, ValueError> {
+    Number::from_f64(value)
+        .map(serde_json::Value::Number)
+        .ok_or_else(|| ValueError::custom(format!("{value} cannot be represented in JSON")))
+}
+
+fn encode_case(case: &str, value: Option<&DynValue>) -> Result<serde_json::Value, ValueError> {
+    let value = match value {
+        Some(value) => encode_dyn(value).map_err(|err| err.at_case(case))?,
+        None => serde_json::Value::Null,
+    };
+    Ok(serde_json::Value::Object(Map::from_iter([(
+        case.to_string(),
+        value,
+    )])))
+}
+
+fn decode_node(
+    json: &serde_json::Value,
+    typ: &WitType,
+    node_idx: NodeIndex,
+) -> Result<Value, ValueError> {
+    let node = typ
+        .nodes
+        .get(node_idx as usize)
+        .ok_or_else(|| ValueError::custom(format!("type node {node_idx} does not exist")))?;
+    match &node.type_ {
+        WitTypeNode::PrimBoolType => json
+            .as_bool()
+            .map(Value::Bool)
+            .ok_or_else(|| unexpected("bool", json)),
+        WitTypeNode::PrimU8Type => {
+            decode_integer(json, "u8", serde_json::Value::as_u64).map(Value::U8)
+        }
+        WitTypeNode::PrimU16Type => {
+            decode_integer(json, "u16", serde_json::Value::as_u64).map(Value::U16)
+        }
+        WitTypeNode::PrimU32Type => {
+            decode_integer(json, "u32", serde_json::Value::as_u64).map(Value::U32)
+        }
+        WitTypeNode::PrimU64Type => {
+            decode_integer(json, "u64", serde_json::Value::as_u64).map(Value::U64)
+        }
+        WitTypeNode::PrimS8Type => {
+            decode_integer(json, "s8", serde_json::Value::as_i64).map(Value::S8)
+        }
+        WitTypeNode::PrimS16Type => {
+            decode_integer(json, "s16", serde_json::Value::as_i64).map(Value::S16)
+        }
+        WitTypeNode::PrimS32Type => {
+            decode_integer(json, "s32", serde_json::Value::as_i64).map(Value::S32)
+        }
+        WitTypeNode::PrimS64Type => {
+            decode_integer(json, "s64", serde_json::Value::as_i64).map(Value::S64)
+        }
+        WitTypeNode::PrimF32Type => json
+            .as_f64()
+            .map(|value| Value::F32(value as f32))
+            .ok_or_else(|| unexpected("f32", json)),
+        WitTypeNode::PrimF64Type => json
+            .as_f64()
+            .map(Value::F64)
+            .ok_or_else(|| unexpected("f64", json)),
+        WitTypeNode::PrimCharType => {
+            let mut chars = json
+                .as_str()
+                .ok_or_else(|| unexpected("char", json))?
+                .chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Ok(Value::Char(c)),
+                _ => Err(unexpected("char", json)),
+            }
+        }
+        WitTypeNode::PrimStringType => json
+            .as_str()
+            .map(|value| Value::String(value.to_string()))
+            .ok_or_else(|| unexpected("string", json)),
+        WitTypeNode::RecordType(fields) => {
+            let object = json.as_object().ok_or_else(|| unexpected("record", json))?;
+            if let Some(unknown) = object
+                .keys()
+                .find(|key| !fields.iter().any(|(name, _)| name == *key))
+            {
+                return Err(ValueError::custom(format!("unknown field {unknown}")));
+            }
+            fields
+                .iter()
+                .map(|(name, field_type)| match object.get(name) {
+                    Some(field) => {
+                        decode_node(field, typ, *field_type).map_err(|err| err.at_field(name))
+                    }
+                    None if is_option(typ, *field_type) => Ok(Value::Option(None)),
+                    None => Err(ValueError::new(ValueErrorKind::Missing).at_field(name)),
+                })
+                .collect::<Result<_, _>>()
+                .map(Value::Record)
+        }
+        WitTypeNode::VariantType(cases) => {
+            let (case, value) = decode_case(json, "variant")?;
+            let case_idx = cases
+                .iter()
+                .position(|(name, _)| name == case)
+                .ok_or_else(|| ValueError::custom(format!("unknown case {case}")))?;
+            Ok(Value::Variant {
+                case_idx: case_idx as u32,
+                case_value: decode_case_value(value, cases[case_idx].1, typ, case)?,
+            })
+        }
+        WitTypeNode::EnumType(cases) => {
+            let case = json.as_str().ok_or_else(|| unexpected("enum", json))?;
+            cases
+                .iter()
+                .position(|name| name == case)
+                .map(|case_idx| Value::Enum(case_idx as u32))
+                .ok_or_else(|| ValueError::custom(format!("unknown case {case}")))
+        }
+        WitTypeNode::FlagsType(names) => {
+            let items = json.as_array().ok_or_else(|| unexpected("flags", json))?;
+            let mut flags = vec![false; names.len()];
+            for (idx, item) in items.iter().enumerate() {
+                let flag = item
+                    .as_str()
+                    .ok_or_else(|| unexpected("flag name", item).at_index(idx))?;
+                let flag_idx = names.iter().position(|name| name == flag).ok_or_else(|| {
+                    ValueError::custom(format!("unknown flag {flag}")).at_index(idx)
+                })?;
+                flags[flag_idx] = true;
+            }
+            Ok(Value::Flags(flags))
+        }
+        WitTypeNode::TupleType(item_types) => {
+            let items = json.as_array().ok_or_else(|| unexpected("tuple", json))?;
+            if items.len() != item_types.len() {
+                return Err(ValueError::custom(format!(
+                    "expected {} tuple elements, found {}",
+                    item_types.len(),
+                    items.len()
+                )));
+            }
+            decode_items(items.iter().zip(item_types.iter().copied()), typ).map(Value::Tuple)
+        }
+        WitTypeNode::ListType(item_type) => {
+            let items = json.as_array().ok_or_else(|| unexpected("list", json))?;
+            decode_items(items.iter().map(|item| (item, *item_type)), typ).map(Value::List)
+        }
+        WitTypeNode::OptionType(inner_type) => match json {
+            serde_json::Value::Null => Ok(Value::Option(None)),
+            json if is_option(typ, *inner_type) => {
+                let (case, value) = decode_case(json, "option")?;
+                if case != "some" {
+                    return Err(ValueError::custom(format!(
+                        "expected the some case of a nested option, found {case}"
+                    )));
+                }
+                decode_case_value(value, Some(*inner_type), typ, case).map(Value::Option)
+            }
+            json => decode_node(json, typ, *inner_type)
+                .map(|value| Value::Option(Some(Box::new(value))))
+                .map_err(|err| err.at_case("some")),
+        },
+        WitTypeNode::ResultType((ok_type, err_type)) => {
+            let (case, value) = decode_case(json, "result")?;
+            match case {
+                "ok" => Ok(Value::Result(Ok(decode_case_value(
+                    value, *ok_type, typ, case,
+                )?))),
+                "err" => Ok(Value::Result(Err(decode_case_value(
+                    value, *err_type, typ, case,
+                )?))),
+                _ => Err(ValueError::custom(format!("unknown case {case}"))),
+            }
+        }
+        WitTypeNode::HandleType(_) => {
+            let uri = json.get("uri").and_then(|uri| uri.as_str());
+            let value = json.get("value").and_then(|value| value.as_u64());
+            match (uri, value) {
+                (Some(uri), Some(value)) => Ok(Value::Handle {
+                    uri: uri.to_string(),
+                    resource_id: value,
+                }),
+                _ => Err(unexpected("handle", json)),
+            }
+        }
+    }
+}
+
+fn decode_integer<N: TryFrom<W>, W>(
+    json: &serde_json::Value,
+    name: &str,
+    as_wide: impl Fn(&serde_json::Value) -> Option<W>,
+) -> Result<N, ValueError> {
+    as_wide(json)
+        .and_then(|value| N::try_from(value).ok())
+        .ok_or_else(|| unexpected(name, json))
+}
+
+fn decode_items<'j>(
+    items: impl Iterator<Item = (&'j serde_json::Value, NodeIndex)>,
+    typ: &WitType,
+) -> Result<Vec<Value>, ValueError> {
+    items
+        .enumerate()
+        .map(|(idx, (item, item_type))| {
+            decode_node(item, typ, item_type).map_err(|err| err.at_index(idx))
+        })
+        .collect()
+}
+
+/// The case name and value of a variant or result, either an object with a single key or the
+/// name of a unit case
+fn decode_case<'j>(
+    json: &'j serde_json::Value,
+    expected: &str,
+) -> Result<(&'j str, Option<&'j serde_json::Value>), ValueError> {
+    match json {
+        serde_json::Value::String(case) => Ok((case, None)),
+        serde_json::Value::Object(object) if object.len() == 1 => {
+            let (case, value) = object.iter().next().expect("object has one key");
+            Ok((case, Some(value).filter(|value| !value.is_null())))
+        }
+        json => Err(unexpected(expected, json)),
+    }
+}
+
+fn decode_case_value(
+    value: Option<&serde_json::Value>,
+    case_type: Option<NodeIndex>,
+    typ: &WitType,
+    case: &str,
+) -> Result<Option<Box<Value>>, ValueError> {
+    match (value, case_type) {
+        (Some(value), Some(case_type)) => decode_node(value, typ, case_type)
+            .map(|value| Some(Box::new(value)))
+            .map_err(|err| err.at_case(case)),
+        // `null` is also a valid value of option case types
+        (None, Some(case_type)) if is_option(typ, case_type) => {
+            Ok(Some(Box::new(Value::Option(None))))
+        }
+        (None, None) => Ok(None),
+        (None, Some(_)) => Err(ValueError::missing_case_value(case)),
+        (Some(_), None) => {
+            Err(ValueError::custom("unexpected value of a case without one").at_case(case))
+        }
+    }
+}
+
+fn is_option(typ: &WitType, node_idx: NodeIndex) -> bool {
+    matches!(
+        typ.nodes.get(node_idx as usize).map(|node| &node.type_),
+        Some(WitTypeNode::OptionType(_))
+    )
+}
+
+fn unexpected(expected: &str, json: &serde_json::Value) -> ValueError {
+    let found = match json {
+        serde_json::Value::Null => "null",
+        serde_json::Value::Bool(_) => "a boolean",
+        serde_json::Value::Number(_) => "a number",
+        serde_json::Value::String(_) => "a string",
+        serde_json::Value::Array(_) => "an array",
+        serde_json::Value::Object(_) => "an object",
+    };
+    ValueError::custom(format!("expected {expected}, found {found} ({json})"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::value_and_type::json::{decode, from_json, to_json};
+    use crate::value_and_type::{IntoValue, PathSegment};
+    use serde_json::json;
+
+    #[test]
+    fn std_types() {
+        let value = (
+            1u8,
+            -2i64,
+            'c',
+            vec![Some(1.5f64), None],
+            Ok::<_, String>(u64::MAX),
+            Err::<(), ()>(()),
+        );
+        let json = to_json(value.clone()).unwrap();
+        assert_eq!(
+            json,
+            json!([1, -2, "c", [1.5, null], {"ok": u64::MAX}, {"err": null}])
+        );
+        assert_eq!(from_json(&json), Ok(value));
+
+        assert!(to_json(f64::NAN).is_err());
+        assert_eq!(from_json::<f32>(&json!(3)), Ok(3.0));
+    }
+
+    #[test]
+    fn nested_options() {
+        for value in [None, Some(None), Some(Some(None)), Some(Some(Some(1u8)))] {
+            let json = to_json(value).unwrap();
+            assert_eq!(from_json(&json), Ok(value));
+        }
+        assert_eq!(
+            to_json(Some(None::<Option<u8>>)).unwrap(),
+            json!({"some": null})
+        );
+        assert_eq!(
+            to_json(Some(Some(Some(1u8)))).unwrap(),
+            json!({"some": {"some": 1}})
+        );
+        assert!(from_json::<Option<Option<u8>>>(&json!(1)).is_err());
+    }
+
+    #[test]
+    fn validation() {
+        let typ = <(u8, Vec<String>)>::get_type();
+        let err = decode(&json!([300, []]), &typ).unwrap_err();
+        assert_eq!(err.path(), &[PathSegment::Index(0)]);
+        assert_eq!(err.to_string(), "at [0]: expected u8, found a number (300)");
+
+        let err = decode(&json!([1, ["a", 2]]), &typ).unwrap_err();
+        assert_eq!(err.path(), &[PathSegment::Index(1), PathSegment::Index(1)]);
+
+        let err = decode(&json!([1]), &typ).unwrap_err();
+        assert_eq!(err.to_string(), "expected 2 tuple elements, found 1");
+    }
+}
+
+#[cfg(all(test, feature = "macro"))]
+mod derive_tests {
+    use crate::value_and_type::json::{from_json, to_json};
+    use crate::value_and_type::PathSegment;
+    use crate::{FromValueAndType, IntoValue};
+    use serde_json::json;
+
+    #[derive(Debug, Clone, PartialEq, IntoValue, FromValueAndType)]
+    #[wit(flags)]
+    struct Access {
+        read: bool,
+        write: bool,
+    }
+
+    #[derive(Debug, Clone, PartialEq, IntoValue, FromValueAndType)]
+    enum Status {
+        Active,
+        Blocked,
+    }
+
+    #[derive(Debug, Clone, PartialEq, IntoValue, FromValueAndType)]
+    enum Contact {
+        Email(String),
+        Phone { country: u16, number: String },
+        Unknown,
+    }
+
+    #[derive(Debug, Clone, PartialEq, IntoValue, FromValueAndType)]
+    struct User {
+        name: String,
+        access: Access,
+        status: Status,
+        contacts: Vec<Contact>,
+        nickname: Option<String>,
+    }
+
+    #[test]
+    fn named_types() {
+        let user = User {
+            name: "ada".to_string(),
+            access: Access {
+                read: true,
+                write: false,
+            },
+            status: Status::Blocked,
+            contacts: vec![
+                Contact::Email("ada@example.com".to_string()),
+                Contact::Phone {
+                    country: 44,
+                    number: "123".to_string(),
+                },
+                Contact::Unknown,
+            ],
+            nickname: None,
+        };
+        let json = to_json(user.clone()).unwrap();
+        assert_eq!(
+            json,
+            json!({
+                "name": "ada",
+                "access": ["read"],
+                "status": "blocked",
+                "contacts": [
+                    {"email": "ada@example.com"},
+                    {"phone": {"country": 44, "number": "123"}},
+                    {"unknown": null},
+                ],
+                "nickname": null,
+            })
+        );
+        assert_eq!(from_json(&json), Ok(user));
+
+        let decoded = from_json::<User>(&json!({
+            "name": "bob",
+            "access": [],
+            "status": "active",
+            "contacts": ["unknown"],
+        }));
+        assert_eq!(
+            decoded.map(|user| user.contacts),
+            Ok(vec![Contact::Unknown])
+        );
+
+        let err = from_json::<User>(&json!({
+            "name": "bob",
+            "access": ["execute"],
+            "status": "active",
+            "contacts": [],
+        }))
+        .unwrap_err();
+        assert_eq!(
+            err.path(),
+            &[
+                PathSegment::Field("access".to_string()),
+                PathSegment::Index(0)
+            ]
+        );
+
+        let err = from_json::<User>(&json!({
+            "name": "bob",
+            "access": [],
+            "status": "active",
+            "contacts": [],
+            "age": 3,
+        }))
+        .unwrap_err();
+        assert_eq!(err.to_string(), "unknown field age");
+    }
+}
@@ -15,20 +15,53 @@
 // Guest binding version of `golem_wasm_rpc` crate's `IntoValueAndType` trait, to be upstreamed
 // eventually.
 
+mod binary;
+#[cfg(feature = "bitflags")]
+pub mod bitflags;
+mod common;
+pub mod compat;
+pub mod diff;
+mod dyn_value;
+mod error;
+mod handle;
+#[cfg(feature = "json")]
+pub mod json;
+#[cfg(feature = "proptest")]
+pub mod proptest;
+#[cfg(feature = "serde")]
+pub mod serde;
+#[cfg(test)]
+mod test_utils;
 pub mod type_builder;
+pub mod validate;
+mod value_ref;
+pub mod wave;
+pub mod wit;
 
 use crate::value_and_type::type_builder::WitTypeBuilderExtensions;
 use golem_wasm_rpc::golem_rpc_0_2_x::types::ValueAndType;
 use golem_wasm_rpc::{WitType, WitValue, WitValueBuilderExtensions};
-use std::collections::Bound;
-use std::collections::HashMap;
-use std::hash::Hash;
-
+use std::collections::{BTreeMap, BTreeSet, Bound, HashMap, HashSet, VecDeque};
+use std::hash::{BuildHasher, Hash};
+use std::rc::Rc;
+use std::sync::Arc;
+
+#[cfg(feature = "durability")]
+pub(crate) use binary::bytes_result_into_value_and_type;
+pub use binary::Bytes;
+pub use dyn_value::DynValue;
+pub use error::{PathSegment, ValueError, ValueErrorKind, ValueKind};
 pub use golem_wasm_rpc::{NodeBuilder, WitValueExtractor};
-pub use type_builder::TypeNodeBuilder;
+pub use handle::{BorrowedHandle, Handle, ResourceType};
+pub use type_builder::{NamedTypeBuilder, TypeNodeBuilder};
+pub use value_ref::{FromValueRef, ListIter, ListRef, ValueRef};
 
 /// Specific trait to convert a type into a pair of `WitValue` and `WitType`.
 pub trait IntoValue: Sized {
+    /// Set for `()`, which is represented by the unit cases when used in a `Result`
+    #[doc(hidden)]
+    const IS_UNIT: bool = false;
+
     fn add_to_builder<T: NodeBuilder>(self, builder: T) -> T::Result;
     fn add_to_type_builder<T: TypeNodeBuilder>(builder: T) -> T::Result;
 
@@ -55,13 +88,22 @@ impl<T: IntoValue + Sized> IntoValueAndType for T {
 }
 
 pub trait FromValueAndType: Sized {
-    fn from_value_and_type(value_and_type: ValueAndType) -> Result<Self, String> {
+    /// Extracts the value, reporting failures with the type of `value_and_type` as the expected
+    /// type
+    fn from_value_and_type(value_and_type: ValueAndType) -> Result<Self, ValueError> {
         Self::from_extractor(&value_and_type.value)
+            .map_err(|err| err.with_expected_type(value_and_type.typ))
     }
 
     fn from_extractor<'a, 'b>(
         extractor: &'a impl WitValueExtractor<'a, 'b>,
-    ) -> Result<Self, String>;
+    ) -> Result<Self, ValueError>;
+
+    /// Constructs the value from a unit case of a `Result`, only supported by `()`
+    #[doc(hidden)]
+    fn from_unit_case() -> Option<Self> {
+        None
+    }
 }
 
 impl IntoValue for u8 {
@@ -77,8 +119,10 @@ impl IntoValue for u8 {
 impl FromValueAndType for u8 {
     fn from_extractor<'a, 'b>(
         extractor: &'a impl WitValueExtractor<'a, 'b>,
-    ) -> Result<Self, String> {
-        extractor.u8().ok_or_else(|| "Expected u8".to_string())
+    ) -> Result<Self, ValueError> {
+        extractor
+            .u8()
+            .ok_or_else(|| ValueError::unexpected(ValueKind::U8, extractor))
     }
 }
 
@@ -95,8 +139,10 @@ impl IntoValue for u16 {
 impl FromValueAndType for u16 {
     fn from_extractor<'a, 'b>(
         extractor: &'a impl WitValueExtractor<'a, 'b>,
-    ) -> Result<Self, String> {
-        extractor.u16().ok_or_else(|| "Expected u16".to_string())
+    ) -> Result<Self, ValueError> {
+        extractor
+            .u16()
+            .ok_or_else(|| ValueError::unexpected(ValueKind::U16, extractor))
     }
 }
 
@@ -113,8 +159,10 @@ impl IntoValue for u32 {
 impl FromValueAndType for u32 {
     fn from_extractor<'a, 'b>(
         extractor: &'a impl WitValueExtractor<'a, 'b>,
-    ) -> Result<Self, String> {
-        extractor.u32().ok_or_else(|| "Expected u32".to_string())
+    ) -> Result<Self, ValueError> {
+        extractor
+            .u32()
+            .ok_or_else(|| ValueError::unexpected(ValueKind::U32, extractor))
     }
 }
 
@@ -131,8 +179,10 @@ impl IntoValue for u64 {
 impl FromValueAndType for u64 {
     fn from_extractor<'a, 'b>(
         extractor: &'a impl WitValueExtractor<'a, 'b>,
-    ) -> Result<Self, String> {
-        extractor.u64().ok_or_else(|| "Expected u64".to_string())
+    ) -> Result<Self, ValueError> {
+        extractor
+            .u64()
+            .ok_or_else(|| ValueError::unexpected(ValueKind::U64, extractor))
     }
 }
 
@@ -149,8 +199,10 @@ impl IntoValue for i8 {
 impl FromValueAndType for i8 {
     fn from_extractor<'a, 'b>(
         extractor: &'a impl WitValueExtractor<'a, 'b>,
-    ) -> Result<Self, String> {
-        extractor.s8().ok_or_else(|| "Expected i8".to_string())
+    ) -> Result<Self, ValueError> {
+        extractor
+            .s8()
+            .ok_or_else(|| ValueError::unexpected(ValueKind::S8, extractor))
     }
 }
 
@@ -167,8 +219,10 @@ impl IntoValue for i16 {
 impl FromValueAndType for i16 {
     fn from_extractor<'a, 'b>(
         extractor: &'a impl WitValueExtractor<'a, 'b>,
-    ) -> Result<Self, String> {
-        extractor.s16().ok_or_else(|| "Expected i16".to_string())
+    ) -> Result<Self, ValueError> {
+        extractor
+            .s16()
+            .ok_or_else(|| ValueError::unexpected(ValueKind::S16, extractor))
     }
 }
 
@@ -185,8 +239,10 @@ impl IntoValue for i32 {
 impl FromValueAndType for i32 {
     fn from_extractor<'a, 'b>(
         extractor: &'a impl WitValueExtractor<'a, 'b>,
-    ) -> Result<Self, String> {
-        extractor.s32().ok_or_else(|| "Expected i32".to_string())
+    ) -> Result<Self, ValueError> {
+        extractor
+            .s32()
+            .ok_or_else(|| ValueError::unexpected(ValueKind::S32, extractor))
     }
 }
 
@@ -203,8 +259,10 @@ impl IntoValue for i64 {
 impl FromValueAndType for i64 {
     fn from_extractor<'a, 'b>(
         extractor: &'a impl WitValueExtractor<'a, 'b>,
-    ) -> Result<Self, String> {
-        extractor.s64().ok_or_else(|| "Expected i64".to_string())
+    ) -> Result<Self, ValueError> {
+        extractor
+            .s64()
+            .ok_or_else(|| ValueError::unexpected(ValueKind::S64, extractor))
     }
 }
 
@@ -221,8 +279,10 @@ impl IntoValue for f32 {
 impl FromValueAndType for f32 {
     fn from_extractor<'a, 'b>(
         extractor: &'a impl WitValueExtractor<'a, 'b>,
-    ) -> Result<Self, String> {
-        extractor.f32().ok_or_else(|| "Expected f32".to_string())
+    ) -> Result<Self, ValueError> {
+        extractor
+            .f32()
+            .ok_or_else(|| ValueError::unexpected(ValueKind::F32, extractor))
     }
 }
 
@@ -239,8 +299,10 @@ impl IntoValue for f64 {
 impl FromValueAndType for f64 {
     fn from_extractor<'a, 'b>(
         extractor: &'a impl WitValueExtractor<'a, 'b>,
-    ) -> Result<Self, String> {
-        extractor.f64().ok_or_else(|| "Expected f64".to_string())
+    ) -> Result<Self, ValueError> {
+        extractor
+            .f64()
+            .ok_or_else(|| ValueError::unexpected(ValueKind::F64, extractor))
     }
 }
 
@@ -257,8 +319,10 @@ impl IntoValue for bool {
 impl FromValueAndType for bool {
     fn from_extractor<'a, 'b>(
         extractor: &'a impl WitValueExtractor<'a, 'b>,
-    ) -> Result<Self, String> {
-        extractor.bool().ok_or_else(|| "Expected bool".to_string())
+    ) -> Result<Self, ValueError> {
+        extractor
+            .bool()
+            .ok_or_else(|| ValueError::unexpected(ValueKind::Bool, extractor))
     }
 }
 
@@ -275,8 +339,10 @@ impl IntoValue for char {
 impl FromValueAndType for char {
     fn from_extractor<'a, 'b>(
         extractor: &'a impl WitValueExtractor<'a, 'b>,
-    ) -> Result<Self, String> {
-        extractor.char().ok_or_else(|| "Expected char".to_string())
+    ) -> Result<Self, ValueError> {
+        extractor
+            .char()
+            .ok_or_else(|| ValueError::unexpected(ValueKind::Char, extractor))
     }
 }
 
@@ -290,29 +356,49 @@ impl IntoValue for String {
     }
 }
 
+impl IntoValue for &str {
+    fn add_to_builder<T: NodeBuilder>(self, builder: T) -> T::Result {
+        builder.string(self)
+    }
+
+    fn add_to_type_builder<T: TypeNodeBuilder>(builder: T) -> T::Result {
+        builder.string()
+    }
+}
+
 impl FromValueAndType for String {
     fn from_extractor<'a, 'b>(
         extractor: &'a impl WitValueExtractor<'a, 'b>,
-    ) -> Result<Self, String> {
+    ) -> Result<Self, ValueError> {
         extractor
             .string()
             .map(|s| s.to_string())
-            .ok_or_else(|| "Expected String".to_string())
+            .ok_or_else(|| ValueError::unexpected(ValueKind::String, extractor))
     }
 }
 
 impl<S: IntoValue, E: IntoValue> IntoValue for Result<S, E> {
     fn add_to_builder<B: NodeBuilder>(self, builder: B) -> B::Result {
         match self {
+            Ok(_) if S::IS_UNIT => builder.result_ok_unit(),
             Ok(ok) => ok.add_to_builder(builder.result_ok()).finish(),
+            Err(_) if E::IS_UNIT => builder.result_err_unit(),
             Err(err) => err.add_to_builder(builder.result_err()).finish(),
         }
     }
 
     fn add_to_type_builder<B: TypeNodeBuilder>(builder: B) -> B::Result {
         let mut builder = builder.result(None, None);
-        builder = S::add_to_type_builder(builder.ok());
-        builder = E::add_to_type_builder(builder.err());
+        builder = if S::IS_UNIT {
+            builder.ok_unit()
+        } else {
+            S::add_to_type_builder(builder.ok())
+        };
+        builder = if E::IS_UNIT {
+            builder.err_unit()
+        } else {
+            E::add_to_type_builder(builder.err())
+        };
         builder.finish()
     }
 }
@@ -320,73 +406,21 @@ impl<S: IntoValue, E: IntoValue> IntoValue for Result<S, E> {
 impl<S: FromValueAndType, E: FromValueAndType> FromValueAndType for Result<S, E> {
     fn from_extractor<'a, 'b>(
         extractor: &'a impl WitValueExtractor<'a, 'b>,
-    ) -> Result<Self, String> {
+    ) -> Result<Self, ValueError> {
         match extractor.result() {
-            Some(Ok(Some(ok))) => S::from_extractor(&ok).map(Ok),
-            Some(Ok(None)) => Err("No value in Ok case".to_string()),
-            Some(Err(Some(err))) => E::from_extractor(&err).map(Err),
-            Some(Err(None)) => Err("No value in Err case".to_string()),
-            None => Err("Expected Result".to_string()),
-        }
-    }
-}
-
-impl<E: IntoValue> IntoValue for Result<(), E> {
-    fn add_to_builder<T: NodeBuilder>(self, builder: T) -> T::Result {
-        match self {
-            Ok(_) => builder.result_ok().finish().finish(),
-            Err(err) => err.add_to_builder(builder.result_err()).finish(),
-        }
-    }
-
-    fn add_to_type_builder<T: TypeNodeBuilder>(builder: T) -> T::Result {
-        let mut builder = builder.result(None, None);
-        builder = builder.ok_unit();
-        builder = E::add_to_type_builder(builder.err());
-        builder.finish()
-    }
-}
-
-impl<E: FromValueAndType> FromValueAndType for Result<(), E> {
-    fn from_extractor<'a, 'b>(
-        extractor: &'a impl WitValueExtractor<'a, 'b>,
-    ) -> Result<Self, String> {
-        match extractor.result() {
-            Some(Ok(Some(_))) => Err("Expected unit Ok case".to_string()),
-            Some(Ok(None)) => Ok(Ok(())),
-            Some(Err(Some(err))) => E::from_extractor(&err).map(Err),
-            Some(Err(None)) => Err("No value in Err case".to_string()),
-            None => Err("Expected Result".to_string()),
-        }
-    }
-}
-
-impl<S: IntoValue> IntoValue for Result<S, ()> {
-    fn add_to_builder<T: NodeBuilder>(self, builder: T) -> T::Result {
-        match self {
-            Ok(ok) => ok.add_to_builder(builder.result_ok()).finish(),
-            Err(_) => builder.result_err().finish().finish(),
-        }
-    }
-
-    fn add_to_type_builder<T: TypeNodeBuilder>(builder: T) -> T::Result {
-        let mut builder = builder.result(None, None);
-        builder = S::add_to_type_builder(builder.ok());
-        builder = builder.err_unit();
-        builder.finish()
-    }
-}
-
-impl<S: FromValueAndType> FromValueAndType for Result<S, ()> {
-    fn from_extractor<'a, 'b>(
-        extractor: &'a impl WitValueExtractor<'a, 'b>,
-    ) -> Result<Self, String> {
-        match extractor.result() {
-            Some(Ok(Some(ok))) => S::from_extractor(&ok).map(Ok),
-            Some(Ok(None)) => Err("No value in Ok case".to_string()),
-            Some(Err(Some(_))) => Ok(Err(())),
-            Some(Err(None)) => Err("Expected unit Err case".to_string()),
-            None => Err("Expected Result".to_string()),
+            Some(Ok(Some(ok))) => S::from_extractor(&ok)
+                .map(Ok)
+                .map_err(|err| err.at_case("ok")),
+            Some(Ok(None)) => S::from_unit_case()
+                .map(Ok)
+                .ok_or_else(|| ValueError::missing_case_value("ok")),
+            Some(Err(Some(err))) => E::from_extractor(&err)
+                .map(Err)
+                .map_err(|err| err.at_case("err")),
+            Some(Err(None)) => E::from_unit_case()
+                .map(Err)
+                .ok_or_else(|| ValueError::missing_case_value("err")),
+            None => Err(ValueError::unexpected(ValueKind::Result, extractor)),
         }
     }
 }
@@ -407,13 +441,15 @@ impl<T: IntoValue> IntoValue for Option<T> {
 impl<T: FromValueAndType> FromValueAndType for Option<T> {
     fn from_extractor<'a, 'b>(
         extractor: &'a impl WitValueExtractor<'a, 'b>,
-    ) -> Result<Self, String> {
+    ) -> Result<Self, ValueError> {
         extractor
             .option()
-            .ok_or_else(|| "Expected option".to_string())
+            .ok_or_else(|| ValueError::unexpected(ValueKind::Option, extractor))
             .and_then(|opt| {
                 if let Some(value) = opt {
-                    T::from_extractor(&value).map(Some)
+                    T::from_extractor(&value)
+                        .map(Some)
+                        .map_err(|err| err.at_case("some"))
                 } else {
                     Ok(None)
                 }
@@ -448,35 +484,31 @@ impl<T: IntoValue> IntoValue for Bound<T> {
 impl<T: FromValueAndType> FromValueAndType for Bound<T> {
     fn from_extractor<'a, 'b>(
         extractor: &'a impl WitValueExtractor<'a, 'b>,
-    ) -> Result<Self, String> {
+    ) -> Result<Self, ValueError> {
         if let Some((case_idx, inner)) = extractor.variant() {
             match case_idx {
                 0 => T::from_extractor(
-                    &inner
-                        .ok_or_else(|| "Missing variant value for inclusive bound".to_string())?,
+                    &inner.ok_or_else(|| ValueError::missing_case_value("included"))?,
                 )
-                .map(Bound::Included),
+                .map(Bound::Included)
+                .map_err(|err| err.at_case("included")),
                 1 => T::from_extractor(
-                    &inner
-                        .ok_or_else(|| "Missing variant value for exclusive bound".to_string())?,
+                    &inner.ok_or_else(|| ValueError::missing_case_value("excluded"))?,
                 )
-                .map(Bound::Excluded),
+                .map(Bound::Excluded)
+                .map_err(|err| err.at_case("excluded")),
                 2 => Ok(Bound::Unbounded),
-                _ => Err(format!("Invalid variant case ({case_idx}) for Bound")),
+                _ => Err(ValueError::invalid_case(case_idx)),
             }
         } else {
-            Err("Expected variant for Bound".to_string())
+            Err(ValueError::unexpected(ValueKind::Variant, extractor))
         }
     }
 }
 
 impl<T: IntoValue> IntoValue for Vec<T> {
     fn add_to_builder<B: NodeBuilder>(self, builder: B) -> B::Result {
-        let mut list_builder = builder.list();
-        for item in self {
-            list_builder = item.add_to_builder(list_builder.item());
-        }
-        list_builder.finish()
+        add_list_to_builder(self, builder)
     }
 
     fn add_to_type_builder<B: TypeNodeBuilder>(builder: B) -> B::Result {
@@ -487,112 +519,977 @@ impl<T: IntoValue> IntoValue for Vec<T> {
 impl<T: FromValueAndType> FromValueAndType for Vec<T> {
     fn from_extractor<'a, 'b>(
         extractor: &'a impl WitValueExtractor<'a, 'b>,
-    ) -> Result<Self, String> {
+    ) -> Result<Self, ValueError> {
         extractor
             .list_elements(|elem| T::from_extractor(&elem))
-            .ok_or_else(|| "Expected list".to_string())
-            .and_then(|list| list.into_iter().collect())
+            .ok_or_else(|| ValueError::unexpected(ValueKind::List, extractor))?
+            .into_iter()
+            .enumerate()
+            .map(|(idx, item)| item.map_err(|err| err.at_index(idx)))
+            .collect()
     }
 }
 
-impl<A: IntoValue, B: IntoValue> IntoValue for (A, B) {
+impl<K: IntoValue, V: IntoValue, S> IntoValue for HashMap<K, V, S> {
     fn add_to_builder<T: NodeBuilder>(self, builder: T) -> T::Result {
-        let mut builder = builder.tuple();
-        builder = self.0.add_to_builder(builder.item());
-        builder = self.1.add_to_builder(builder.item());
-        builder.finish()
+        add_map_to_builder(self, builder)
     }
 
     fn add_to_type_builder<T: TypeNodeBuilder>(builder: T) -> T::Result {
-        let mut builder = builder.tuple(None, None);
-        builder = A::add_to_type_builder(builder.item());
-        builder = B::add_to_type_builder(builder.item());
-        builder.finish()
+        add_map_to_type_builder::<K, V, T>(builder)
     }
 }
 
-impl<A: FromValueAndType, B: FromValueAndType> FromValueAndType for (A, B) {
+impl<K: FromValueAndType + Eq + Hash, V: FromValueAndType, S: BuildHasher + Default>
+    FromValueAndType for HashMap<K, V, S>
+{
     fn from_extractor<'a, 'b>(
         extractor: &'a impl WitValueExtractor<'a, 'b>,
-    ) -> Result<Self, String> {
-        let a = A::from_extractor(
-            &extractor
-                .tuple_element(0)
-                .ok_or_else(|| "Expected 2-tuple".to_string())?,
-        )?;
-        let b = B::from_extractor(
-            &extractor
-                .tuple_element(1)
-                .ok_or_else(|| "Expected 2-tuple".to_string())?,
-        )?;
-        Ok((a, b))
-    }
-}
-
-impl<A: IntoValue, B: IntoValue, C: IntoValue> IntoValue for (A, B, C) {
+    ) -> Result<Self, ValueError> {
+        let items: Vec<(K, V)> = FromValueAndType::from_extractor(extractor)?;
+        Ok(HashMap::from_iter(items))
+    }
+}
+
+impl<K: IntoValue, V: IntoValue> IntoValue for BTreeMap<K, V> {
     fn add_to_builder<T: NodeBuilder>(self, builder: T) -> T::Result {
-        let mut builder = builder.tuple();
-        builder = self.0.add_to_builder(builder.item());
-        builder = self.1.add_to_builder(builder.item());
-        builder = self.2.add_to_builder(builder.item());
-        builder.finish()
+        add_map_to_builder(self, builder)
     }
 
     fn add_to_type_builder<T: TypeNodeBuilder>(builder: T) -> T::Result {
-        let mut builder = builder.tuple(None, None);
-        builder = A::add_to_type_builder(builder.item());
-        builder = B::add_to_type_builder(builder.item());
-        builder = C::add_to_type_builder(builder.item());
-        builder.finish()
+        add_map_to_type_builder::<K, V, T>(builder)
+    }
+}
+
+impl<K: FromValueAndType + Ord, V: FromValueAndType> FromValueAndType for BTreeMap<K, V> {
+    fn from_extractor<'a, 'b>(
+        extractor: &'a impl WitValueExtractor<'a, 'b>,
+    ) -> Result<Self, ValueError> {
+        let items: Vec<(K, V)> = FromValueAndType::from_extractor(extractor)?;
+        Ok(BTreeMap::from_iter(items))
+    }
+}
+
+/// Maps are represented as a list of key-value tuples
+fn add_map_to_builder<K: IntoValue, V: IntoValue, T: NodeBuilder>(
+    map: impl IntoIterator<Item = (K, V)>,
+    builder: T,
+) -> T::Result {
+    let mut builder = builder.list();
+    for (k, v) in map {
+        let mut tuple_builder = builder.item().tuple();
+        tuple_builder = k.add_to_builder(tuple_builder.item());
+        tuple_builder = v.add_to_builder(tuple_builder.item());
+        builder = tuple_builder.finish();
+    }
+    builder.finish()
+}
+
+fn add_map_to_type_builder<K: IntoValue, V: IntoValue, T: TypeNodeBuilder>(
+    builder: T,
+) -> T::Result {
+    let mut builder = builder.list(None, None).tuple(None, None);
+    builder = K::add_to_type_builder(builder.item());
+    builder = V::add_to_type_builder(builder.item());
+    builder.finish().finish()
+}
+
+impl<T: IntoValue> IntoValue for VecDeque<T> {
+    fn add_to_builder<B: NodeBuilder>(self, builder: B) -> B::Result {
+        add_list_to_builder(self, builder)
+    }
+
+    fn add_to_type_builder<B: TypeNodeBuilder>(builder: B) -> B::Result {
+        Vec::<T>::add_to_type_builder(builder)
     }
 }
 
-impl<A: FromValueAndType, B: FromValueAndType, C: FromValueAndType> FromValueAndType for (A, B, C) {
+impl<T: FromValueAndType> FromValueAndType for VecDeque<T> {
     fn from_extractor<'a, 'b>(
         extractor: &'a impl WitValueExtractor<'a, 'b>,
-    ) -> Result<Self, String> {
-        let a = A::from_extractor(
-            &extractor
-                .tuple_element(0)
-                .ok_or_else(|| "Expected 3-tuple".to_string())?,
-        )?;
-        let b = B::from_extractor(
-            &extractor
-                .tuple_element(1)
-                .ok_or_else(|| "Expected 3-tuple".to_string())?,
-        )?;
-        let c = C::from_extractor(
-            &extractor
-                .tuple_element(2)
-                .ok_or_else(|| "Expected 3-tuple".to_string())?,
-        )?;
-        Ok((a, b, c))
-    }
-}
-
-impl<K: IntoValue, V: IntoValue> IntoValue for HashMap<K, V> {
+    ) -> Result<Self, ValueError> {
+        Vec::<T>::from_extractor(extractor).map(VecDeque::from)
+    }
+}
+
+impl<T: IntoValue> IntoValue for BTreeSet<T> {
+    fn add_to_builder<B: NodeBuilder>(self, builder: B) -> B::Result {
+        add_list_to_builder(self, builder)
+    }
+
+    fn add_to_type_builder<B: TypeNodeBuilder>(builder: B) -> B::Result {
+        Vec::<T>::add_to_type_builder(builder)
+    }
+}
+
+impl<T: FromValueAndType + Ord> FromValueAndType for BTreeSet<T> {
+    fn from_extractor<'a, 'b>(
+        extractor: &'a impl WitValueExtractor<'a, 'b>,
+    ) -> Result<Self, ValueError> {
+        Vec::<T>::from_extractor(extractor).map(BTreeSet::from_iter)
+    }
+}
+
+impl<T: IntoValue, S> IntoValue for HashSet<T, S> {
+    fn add_to_builder<B: NodeBuilder>(self, builder: B) -> B::Result {
+        add_list_to_builder(self, builder)
+    }
+
+    fn add_to_type_builder<B: TypeNodeBuilder>(builder: B) -> B::Result {
+        Vec::<T>::add_to_type_builder(builder)
+    }
+}
+
+impl<T: FromValueAndType + Eq + Hash, S: BuildHasher + Default> FromValueAndType for HashSet<T, S> {
+    fn from_extractor<'a, 'b>(
+        extractor: &'a impl WitValueExtractor<'a, 'b>,
+    ) -> Result<Self, ValueError> {
+        Vec::<T>::from_extractor(extractor).map(HashSet::from_iter)
+    }
+}
+
+impl<T: IntoValue, const N: usize> IntoValue for [T; N] {
+    fn add_to_builder<B: NodeBuilder>(self, builder: B) -> B::Result {
+        add_list_to_builder(self, builder)
+    }
+
+    fn add_to_type_builder<B: TypeNodeBuilder>(builder: B) -> B::Result {
+        Vec::<T>::add_to_type_builder(builder)
+    }
+}
+
+impl<T: FromValueAndType, const N: usize> FromValueAndType for [T; N] {
+    fn from_extractor<'a, 'b>(
+        extractor: &'a impl WitValueExtractor<'a, 'b>,
+    ) -> Result<Self, ValueError> {
+        Vec::<T>::from_extractor(extractor)?
+            .try_into()
+            .map_err(|items: Vec<T>| {
+                ValueError::custom(format!(
+                    "Expected list of {N} elements, got {}",
+                    items.len()
+                ))
+            })
+    }
+}
+
+fn add_list_to_builder<T: IntoValue, B: NodeBuilder>(
+    items: impl IntoIterator<Item = T>,
+    builder: B,
+) -> B::Result {
+    let mut list_builder = builder.list();
+    for item in items {
+        list_builder = item.add_to_builder(list_builder.item());
+    }
+    list_builder.finish()
+}
+
+impl<T: IntoValue> IntoValue for Box<T> {
+    fn add_to_builder<B: NodeBuilder>(self, builder: B) -> B::Result {
+        (*self).add_to_builder(builder)
+    }
+
+    fn add_to_type_builder<B: TypeNodeBuilder>(builder: B) -> B::Result {
+        T::add_to_type_builder(builder)
+    }
+}
+
+impl<T: FromValueAndType> FromValueAndType for Box<T> {
+    fn from_extractor<'a, 'b>(
+        extractor: &'a impl WitValueExtractor<'a, 'b>,
+    ) -> Result<Self, ValueError> {
+        T::from_extractor(extractor).map(Box::new)
+    }
+}
+
+impl<T: IntoValue + Clone> IntoValue for Rc<T> {
+    fn add_to_builder<B: NodeBuilder>(self, builder: B) -> B::Result {
+        Rc::unwrap_or_clone(self).add_to_builder(builder)
+    }
+
+    fn add_to_type_builder<B: TypeNodeBuilder>(builder: B) -> B::Result {
+        T::add_to_type_builder(builder)
+    }
+}
+
+impl<T: FromValueAndType> FromValueAndType for Rc<T> {
+    fn from_extractor<'a, 'b>(
+        extractor: &'a impl WitValueExtractor<'a, 'b>,
+    ) -> Result<Self, ValueError> {
+        T::from_extractor(extractor).map(Rc::new)
+    }
+}
+
+impl<T: IntoValue + Clone> IntoValue for Arc<T> {
+    fn add_to_builder<B: NodeBuilder>(self, builder: B) -> B::Result {
+        Arc::unwrap_or_clone(self).add_to_builder(builder)
+    }
+
+    fn add_to_type_builder<B: TypeNodeBuilder>(builder: B) -> B::Result {
+        T::add_to_type_builder(builder)
+    }
+}
+
+impl<T: FromValueAndType> FromValueAndType for Arc<T> {
+    fn from_extractor<'a, 'b>(
+        extractor: &'a impl WitValueExtractor<'a, 'b>,
+    ) -> Result<Self, ValueError> {
+        T::from_extractor(extractor).map(Arc::new)
+    }
+}
+
+/// `()` is represented by an empty tuple, except in `Result` where it becomes a unit case
+impl IntoValue for () {
+    const IS_UNIT: bool = true;
+
     fn add_to_builder<T: NodeBuilder>(self, builder: T) -> T::Result {
-        let mut builder = builder.list();
-        for (k, v) in self {
-            builder = k.add_to_builder(builder.item().tuple().item()).finish();
-            builder = v.add_to_builder(builder.item().tuple().item()).finish();
+        builder.tuple().finish()
+    }
+
+    fn add_to_type_builder<T: TypeNodeBuilder>(builder: T) -> T::Result {
+        builder.tuple(None, None).finish()
+    }
+}
+
+impl FromValueAndType for () {
+    fn from_extractor<'a, 'b>(
+        extractor: &'a impl WitValueExtractor<'a, 'b>,
+    ) -> Result<Self, ValueError> {
+        match ValueKind::of(extractor) {
+            // An extractor cannot tell empty tuples apart from empty records
+            None => Ok(()),
+            Some(ValueKind::Tuple) => Err(non_empty_unit_tuple()),
+            Some(_) => Err(ValueError::unexpected(ValueKind::Tuple, extractor)),
         }
-        builder.finish()
+    }
+
+    fn from_unit_case() -> Option<Self> {
+        Some(())
+    }
+}
+
+pub(crate) fn non_empty_unit_tuple() -> ValueError {
+    ValueError::custom("Expected an empty tuple, found a tuple with elements")
+}
+
+/// `u128` is represented by a tuple of its high and low 64 bits
+impl IntoValue for u128 {
+    fn add_to_builder<T: NodeBuilder>(self, builder: T) -> T::Result {
+        ((self >> 64) as u64, self as u64).add_to_builder(builder)
     }
 
     fn add_to_type_builder<T: TypeNodeBuilder>(builder: T) -> T::Result {
-        let mut builder = builder.list(None, None).tuple(None, None);
-        builder = K::add_to_type_builder(builder.item());
-        builder = V::add_to_type_builder(builder.item());
-        builder.finish().finish()
+        <(u64, u64)>::add_to_type_builder(builder)
     }
 }
 
-impl<K: FromValueAndType + Eq + Hash, V: FromValueAndType> FromValueAndType for HashMap<K, V> {
+impl FromValueAndType for u128 {
     fn from_extractor<'a, 'b>(
         extractor: &'a impl WitValueExtractor<'a, 'b>,
-    ) -> Result<Self, String> {
-        let items: Vec<(K, V)> = FromValueAndType::from_extractor(extractor)?;
-        Ok(HashMap::from_iter(items))
+    ) -> Result<Self, ValueError> {
+        let (high, low) = <(u64, u64)>::from_extractor(extractor)?;
+        Ok(((high as u128) << 64) | low as u128)
+    }
+}
+
+/// `i128` is represented by a tuple of its signed high and unsigned low 64 bits
+impl IntoValue for i128 {
+    fn add_to_builder<T: NodeBuilder>(self, builder: T) -> T::Result {
+        ((self >> 64) as i64, self as u64).add_to_builder(builder)
+    }
+
+    fn add_to_type_builder<T: TypeNodeBuilder>(builder: T) -> T::Result {
+        <(i64, u64)>::add_to_type_builder(builder)
+    }
+}
+
+impl FromValueAndType for i128 {
+    fn from_extractor<'a, 'b>(
+        extractor: &'a impl WitValueExtractor<'a, 'b>,
+    ) -> Result<Self, ValueError> {
+        let (high, low) = <(i64, u64)>::from_extractor(extractor)?;
+        Ok(((high as i128) << 64) | low as i128)
+    }
+}
+
+macro_rules! tuple_value {
+    ($($ty:ident),*) => {
+        impl<$($ty: IntoValue),*> IntoValue for ($($ty,)*) {
+            fn add_to_builder<Builder: NodeBuilder>(self, builder: Builder) -> Builder::Result {
+                #[allow(non_snake_case)]
+                let ($($ty,)*) = self;
+                let mut builder = builder.tuple();
+                $(builder = $ty.add_to_builder(builder.item());)*
+                builder.finish()
+            }
+
+            fn add_to_type_builder<Builder: TypeNodeBuilder>(builder: Builder) -> Builder::Result {
+                let mut builder = builder.tuple(None, None);
+                $(builder = $ty::add_to_type_builder(builder.item());)*
+                builder.finish()
+            }
+        }
+
+        impl<$($ty: FromValueAndType),*> FromValueAndType for ($($ty,)*) {
+            #[allow(unused_assignments)]
+            fn from_extractor<'a, 'b>(
+                extractor: &'a impl WitValueExtractor<'a, 'b>,
+            ) -> Result<Self, ValueError> {
+                let mut idx = 0;
+                Ok(($(
+                    {
+                        let item = $ty::from_extractor(
+                            &extractor
+                                .tuple_element(idx)
+                                .ok_or_else(|| ValueError::missing_element(extractor, idx))?,
+                        )
+                        .map_err(|err| err.at_index(idx))?;
+                        idx += 1;
+                        item
+                    },
+                )*))
+            }
+        }
+    };
+}
+
+macro_rules! generate_for_tuples {
+    ($name:ident) => {
+        $name!(T1);
+        $name!(T1, T2);
+        $name!(T1, T2, T3);
+        $name!(T1, T2, T3, T4);
+        $name!(T1, T2, T3, T4, T5);
+        $name!(T1, T2, T3, T4, T5, T6);
+        $name!(T1, T2, T3, T4, T5, T6, T7);
+        $name!(T1, T2, T3, T4, T5, T6, T7, T8);
+        $name!(T1, T2, T3, T4, T5, T6, T7, T8, T9);
+        $name!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10);
+        $name!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11);
+        $name!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12);
+        $name!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13);
+        $name!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14);
+        $name!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14, T15);
+        $name!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14, T15, T16);
+    };
+}
+
+generate_for_tuples!(tuple_value);
+
+pub(crate) use generate_for_tuples;
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+    use std::rc::Rc;
+    use std::sync::Arc;
+
+    use crate::value_and_type::test_utils::roundtrip;
+    use crate::value_and_type::{
+        FromValueAndType, IntoValue, IntoValueAndType, PathSegment, ValueError, ValueErrorKind,
+        ValueKind,
+    };
+
+    #[test]
+    fn collections_roundtrip() {
+        roundtrip(VecDeque::from(vec![1u32, 2, 3]));
+        roundtrip(BTreeSet::from(["a".to_string(), "b".to_string()]));
+        roundtrip(HashSet::from([1i8, -1]));
+        roundtrip(BTreeMap::from([
+            (1u8, "x".to_string()),
+            (2u8, "y".to_string()),
+        ]));
+        roundtrip(HashMap::from([("k".to_string(), vec![true, false])]));
+        roundtrip([1u16, 2, 3, 4]);
+    }
+
+    #[test]
+    fn smart_pointers_roundtrip() {
+        roundtrip(Box::new(1.5f64));
+        roundtrip(Rc::new("rc".to_string()));
+        roundtrip(Arc::new(Some('x')));
+    }
+
+    #[test]
+    fn unit_and_wide_integers_roundtrip() {
+        roundtrip(());
+        roundtrip(Ok::<(), ()>(()));
+        roundtrip(Err::<(), ()>(()));
+        roundtrip(Ok::<(), String>(()));
+        roundtrip(Err::<u8, ()>(()));
+        roundtrip(u128::MAX - 12345);
+        roundtrip(i128::MIN + 12345);
+        roundtrip(-1i128);
+    }
+
+    #[test]
+    fn unit_requires_an_empty_tuple() {
+        assert_eq!(<()>::from_extractor(&().into_value()), Ok(()));
+        assert!(<()>::from_extractor(&(1u8,).into_value()).is_err());
+        assert_eq!(
+            <()>::from_extractor(&1u8.into_value()).unwrap_err().kind(),
+            &ValueErrorKind::UnexpectedKind {
+                expected: ValueKind::Tuple,
+                actual: Some(ValueKind::U8)
+            }
+        );
+        assert!(Result::<(), String>::from_extractor(&Ok::<u8, String>(1).into_value()).is_err());
+    }
+
+    #[test]
+    fn tuples_roundtrip() {
+        roundtrip((1u8,));
+        roundtrip((1u8, "two".to_string(), 3.0f32));
+        roundtrip((
+            1u8,
+            2u16,
+            3u32,
+            4u64,
+            5i8,
+            6i16,
+            7i32,
+            8i64,
+            'a',
+            true,
+            (),
+            12u8,
+        ));
+
+        type Tuple16 = (
+            u8,
+            u8,
+            u8,
+            u8,
+            u8,
+            u8,
+            u8,
+            u8,
+            u8,
+            u8,
+            u8,
+            u8,
+            u8,
+            u8,
+            u8,
+            String,
+        );
+        let value: Tuple16 = (
+            1,
+            2,
+            3,
+            4,
+            5,
+            6,
+            7,
+            8,
+            9,
+            10,
+            11,
+            12,
+            13,
+            14,
+            15,
+            "16".to_string(),
+        );
+        let result = Tuple16::from_value_and_type(value.into_value_and_type()).unwrap();
+        assert_eq!((result.0, result.14, result.15), (1, 15, "16".to_string()));
+    }
+
+    #[test]
+    fn array_with_wrong_length_fails() {
+        let value = vec![1u8, 2, 3].into_value_and_type();
+        assert_eq!(
+            <[u8; 2]>::from_value_and_type(value).map_err(String::from),
+            Err("Expected list of 2 elements, got 3".to_string())
+        );
+    }
+
+    #[test]
+    fn errors_point_to_the_failing_part() {
+        let value = vec![(1u8, Some("a".to_string())), (2u8, None)].into_value_and_type();
+        let err = Vec::<(u8, Option<u32>)>::from_value_and_type(value).unwrap_err();
+        assert_eq!(
+            err.path(),
+            &[
+                PathSegment::Index(0),
+                PathSegment::Index(1),
+                PathSegment::Case("some".to_string())
+            ]
+        );
+        assert_eq!(
+            err.kind(),
+            &ValueErrorKind::UnexpectedKind {
+                expected: ValueKind::U32,
+                actual: Some(ValueKind::String)
+            }
+        );
+        assert!(err.expected_type().is_some());
+        assert_eq!(
+            String::from(err),
+            "at [0][1]::some: expected u32, found string"
+        );
+
+        let value = Err::<u8, ()>(()).into_value_and_type();
+        let err = Result::<u8, u8>::from_value_and_type(value).unwrap_err();
+        assert_eq!(err, ValueError::missing_case_value("err"));
+        assert_eq!(err.to_string(), "at ::err: missing value");
+    }
+}
+
+#[cfg(all(test, feature = "macro"))]
+mod derive_tests {
+    use crate::value_and_type::test_utils::Counter;
+    use crate::value_and_type::{
+        BorrowedHandle, FromValueAndType, Handle, IntoValue, IntoValueAndType, ValueError,
+    };
+    use crate::{FromValueAndType, IntoValue};
+    use golem_wasm_rpc::{ResourceMode, Value, WitTypeNode, WitValue};
+    use std::marker::PhantomData;
+
+    fn default_priority() -> u8 {
+        5
+    }
+
+    #[derive(Debug, Clone, PartialEq, IntoValue, FromValueAndType)]
+    #[wit(rename = "task", owner = "tasks:api/types", rename_all = "snake_case")]
+    struct Task {
+        task_id: u64,
+        #[wit(rename = "title")]
+        name: String,
+        #[wit(skip)]
+        cached: Option<String>,
+        #[wit(default = "default_priority")]
+        priority: u8,
+    }
+
+    #[derive(Debug, Clone, PartialEq, IntoValue, FromValueAndType)]
+    #[wit(rename_all = "SCREAMING-KEBAB-CASE")]
+    enum Event {
+        Created,
+        #[wit(rename = "moved", rename_all = "camelCase")]
+        Relocated {
+            from_column: String,
+            to_column: String,
+        },
+        Tagged(String),
+        #[wit(type_name = "score")]
+        Scored(u8, u16),
+    }
+
+    #[derive(Debug, Clone, PartialEq, IntoValue, FromValueAndType)]
+    #[wit(transparent)]
+    struct Label {
+        text: String,
+        #[wit(skip)]
+        width: usize,
+    }
+
+    #[derive(Debug, Clone, PartialEq, IntoValue, FromValueAndType)]
+    struct Page<T> {
+        items: Vec<T>,
+        next: Option<String>,
+    }
+
+    #[derive(Debug, Clone, PartialEq, IntoValue, FromValueAndType)]
+    enum Either<A, B> {
+        Left(A),
+        Right(B),
+    }
+
+    #[derive(IntoValue)]
+    struct Borrowed<'a, T: Copy> {
+        name: &'a str,
+        value: T,
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Untyped;
+
+    #[derive(Debug, Clone, PartialEq, IntoValue, FromValueAndType)]
+    #[wit(bound = "")]
+    struct Id<M> {
+        id: u64,
+        #[wit(skip)]
+        marker: PhantomData<M>,
+    }
+
+    #[derive(Debug, Clone, PartialEq, IntoValue, FromValueAndType)]
+    #[wit(flags, rename = "permissions")]
+    struct Permissions {
+        read: bool,
+        write: bool,
+        manage_users: bool,
+    }
+
+    #[cfg(feature = "bitflags")]
+    ::bitflags::bitflags! {
+        #[derive(Debug, Clone, Copy, PartialEq, IntoValue, FromValueAndType)]
+        #[wit(bitflags, rename = "permissions")]
+        struct PermissionBits: u8 {
+            const READ = 1;
+            const WRITE = 2;
+            const MANAGE_USERS = 4;
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, IntoValue, FromValueAndType)]
+    struct Session {
+        counter: Handle<Counter>,
+        viewer: Option<BorrowedHandle<Counter>>,
+    }
+
+    #[derive(Debug, Clone, PartialEq, IntoValue, FromValueAndType)]
+    struct Board {
+        open: Vec<Task>,
+        done: Vec<Task>,
+        current: Option<Task>,
+    }
+
+    #[derive(Debug, Clone, PartialEq, IntoValue, FromValueAndType)]
+    struct Tree {
+        label: String,
+        children: Vec<Tree>,
+    }
+
+    #[derive(Debug, Clone, PartialEq, IntoValue, FromValueAndType)]
+    struct GenericTree<T> {
+        value: T,
+        children: Vec<GenericTree<T>>,
+    }
+
+    #[test]
+    fn renamed_and_skipped_fields() {
+        let task = Task {
+            task_id: 1,
+            name: "write tests".to_string(),
+            cached: Some("cached".to_string()),
+            priority: 2,
+        };
+        let typ = Task::get_type();
+        assert_eq!(typ.nodes[0].name.as_deref(), Some("task"));
+        assert_eq!(typ.nodes[0].owner.as_deref(), Some("tasks:api/types"));
+        assert_eq!(
+            format!("{:?}", typ.nodes[0].type_),
+            r#"WitTypeNode::RecordType([("task_id", 1), ("title", 2), ("priority", 3)])"#
+        );
+
+        let result = Task::from_value_and_type(task.clone().into_value_and_type());
+        assert_eq!(
+            result,
+            Ok(Task {
+                cached: None,
+                ..task
+            })
+        );
+    }
+
+    #[test]
+    fn missing_field_uses_default() {
+        let value: WitValue =
+            Value::Record(vec![Value::U64(1), Value::String("old".to_string())]).into();
+        assert_eq!(
+            Task::from_extractor(&value),
+            Ok(Task {
+                task_id: 1,
+                name: "old".to_string(),
+                cached: None,
+                priority: 5,
+            })
+        );
+
+        let value: WitValue = Value::Record(vec![Value::U64(1)]).into();
+        assert_eq!(
+            Task::from_extractor(&value),
+            Err(ValueError::missing_field(&value, "title"))
+        );
+    }
+
+    #[test]
+    fn renamed_cases() {
+        let typ = Event::get_type();
+        assert_eq!(
+            format!("{:?}", typ.nodes[0].type_),
+            r#"WitTypeNode::VariantType([("CREATED", None), ("moved", Some(1)), ("TAGGED", Some(4)), ("SCORED", Some(5))])"#
+        );
+        assert_eq!(
+            format!("{:?}", typ.nodes[1].type_),
+            r#"WitTypeNode::RecordType([("fromColumn", 2), ("toColumn", 3)])"#
+        );
+        assert_eq!(typ.nodes[1].name.as_deref(), Some("Event.Relocated"));
+        assert_eq!(typ.nodes[5].name.as_deref(), Some("score"));
+
+        for event in [
+            Event::Created,
+            Event::Relocated {
+                from_column: "todo".to_string(),
+                to_column: "done".to_string(),
+            },
+            Event::Tagged("urgent".to_string()),
+            Event::Scored(1, 2),
+        ] {
+            let result = Event::from_value_and_type(event.clone().into_value_and_type());
+            assert_eq!(result, Ok(event));
+        }
+    }
+
+    #[test]
+    fn transparent_struct() {
+        let label = Label {
+            text: "label".to_string(),
+            width: 10,
+        };
+        assert_eq!(label.clone().into_value(), "label".into_value());
+        assert_eq!(
+            Label::from_value_and_type(label.into_value_and_type()),
+            Ok(Label {
+                text: "label".to_string(),
+                width: 0
+            })
+        );
+    }
+
+    #[test]
+    fn generic_types() {
+        let page = Page {
+            items: vec![Either::Left(1u8), Either::Right("two".to_string())],
+            next: Some("cursor".to_string()),
+        };
+        let result = Page::from_value_and_type(page.clone().into_value_and_type());
+        assert_eq!(result, Ok(page));
+
+        let borrowed = Borrowed {
+            name: "borrowed",
+            value: 1u32,
+        };
+        assert_eq!(
+            borrowed.into_value(),
+            WitValue::from(Value::Record(vec![
+                Value::String("borrowed".to_string()),
+                Value::U32(1)
+            ]))
+        );
+
+        let id = Id::<Untyped> {
+            id: 1,
+            marker: PhantomData,
+        };
+        let result = Id::<Untyped>::from_value_and_type(id.clone().into_value_and_type());
+        assert_eq!(result, Ok(id));
+    }
+
+    #[test]
+    fn flags() {
+        let permissions = Permissions {
+            read: true,
+            write: false,
+            manage_users: true,
+        };
+        assert_eq!(
+            format!("{:?}", Permissions::get_type().nodes[0].type_),
+            r#"WitTypeNode::FlagsType(["read", "write", "manage-users"])"#
+        );
+        assert_eq!(
+            permissions.clone().into_value(),
+            WitValue::from(Value::Flags(vec![true, false, true]))
+        );
+        let result = Permissions::from_value_and_type(permissions.clone().into_value_and_type());
+        assert_eq!(result, Ok(permissions));
+
+        for flags in [vec![true, false], vec![true, false, true, true]] {
+            let result = Permissions::from_extractor(&WitValue::from(Value::Flags(flags)));
+            assert!(result.is_err());
+        }
+    }
+
+    #[cfg(feature = "bitflags")]
+    #[test]
+    fn bitflags() {
+        let permissions = PermissionBits::READ | PermissionBits::MANAGE_USERS;
+        assert_eq!(
+            format!("{:?}", PermissionBits::get_type().nodes[0].type_),
+            r#"WitTypeNode::FlagsType(["read", "write", "manage-users"])"#
+        );
+        assert_eq!(
+            permissions.into_value(),
+            WitValue::from(Value::Flags(vec![true, false, true]))
+        );
+        let result = PermissionBits::from_value_and_type(permissions.into_value_and_type());
+        assert_eq!(result, Ok(permissions));
+
+        let result = PermissionBits::from_extractor(&WitValue::from(Value::Flags(vec![true])));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn handle_fields() {
+        let counter = Handle::<Counter>::new("urn:worker:counters/counter-1", 3);
+        let session = Session {
+            viewer: Some(counter.borrow()),
+            counter,
+        };
+        let typ = Session::get_type();
+        assert_eq!(typ.nodes[1].owner.as_deref(), Some("counters:api/types"));
+        assert!(matches!(
+            typ.nodes[1].type_,
+            WitTypeNode::HandleType((7, ResourceMode::Owned))
+        ));
+        assert!(matches!(
+            typ.nodes[3].type_,
+            WitTypeNode::HandleType((7, ResourceMode::Borrowed))
+        ));
+        let result = Session::from_value_and_type(session.clone().into_value_and_type());
+        assert_eq!(result, Ok(session));
+    }
+
+    mod a {
+        use crate::{FromValueAndType, IntoValue};
+
+        #[derive(Debug, Clone, PartialEq, IntoValue, FromValueAndType)]
+        pub struct Status {
+            pub code: u32,
+        }
+    }
+
+    mod b {
+        use crate::{FromValueAndType, IntoValue};
+
+        #[derive(Debug, Clone, PartialEq, IntoValue, FromValueAndType)]
+        pub struct Status {
+            pub text: String,
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, IntoValue, FromValueAndType)]
+    struct Both {
+        x: a::Status,
+        y: b::Status,
+        z: a::Status,
+    }
+
+    #[test]
+    fn same_named_types_are_not_merged() {
+        let typ = Both::get_type();
+        assert_eq!(
+            format!("{:?}", typ.nodes[0].type_),
+            r#"WitTypeNode::RecordType([("x", 1), ("y", 3), ("z", 1)])"#
+        );
+        assert_eq!(typ.nodes[1].name.as_deref(), Some("Status"));
+        assert_eq!(typ.nodes[3].name.as_deref(), Some("Status"));
+        assert!(matches!(typ.nodes[4].type_, WitTypeNode::PrimStringType));
+
+        let both = Both {
+            x: a::Status { code: 1 },
+            y: b::Status {
+                text: "ok".to_string(),
+            },
+            z: a::Status { code: 2 },
+        };
+        let value = both.clone().into_value_and_type();
+        assert!(crate::value_and_type::validate::validate(&value).is_ok());
+        assert_eq!(Both::from_value_and_type(value), Ok(both));
+    }
+
+    #[derive(Debug, Clone, PartialEq, IntoValue, FromValueAndType)]
+    enum Request {
+        #[wit(type_name = "payload")]
+        Get { key: String },
+    }
+
+    #[derive(Debug, Clone, PartialEq, IntoValue, FromValueAndType)]
+    enum Response {
+        #[wit(type_name = "payload")]
+        Found { value: u64 },
+    }
+
+    #[test]
+    fn same_named_case_types_are_not_merged() {
+        let typ = <(Request, Response, Request)>::get_type();
+        let value = (
+            Request::Get {
+                key: "k".to_string(),
+            },
+            Response::Found { value: 1 },
+            Request::Get {
+                key: "l".to_string(),
+            },
+        )
+            .into_value_and_type();
+        assert!(crate::value_and_type::validate::validate(&value).is_ok());
+        let payloads = typ
+            .nodes
+            .iter()
+            .filter(|node| node.name.as_deref() == Some("payload"))
+            .count();
+        assert_eq!(payloads, 2);
+    }
+
+    #[test]
+    fn repeated_types_are_interned() {
+        let typ = Board::get_type();
+        assert_eq!(typ.nodes.len(), 8);
+        assert_eq!(
+            format!("{:?}", typ.nodes[0].type_),
+            r#"WitTypeNode::RecordType([("open", 1), ("done", 6), ("current", 7)])"#
+        );
+        assert!(matches!(typ.nodes[6].type_, WitTypeNode::ListType(2)));
+        assert!(matches!(typ.nodes[7].type_, WitTypeNode::OptionType(2)));
+
+        let task = Task {
+            task_id: 1,
+            name: "intern".to_string(),
+            cached: None,
+            priority: 1,
+        };
+        let board = Board {
+            open: vec![task.clone()],
+            done: vec![],
+            current: Some(task),
+        };
+        let result = Board::from_value_and_type(board.clone().into_value_and_type());
+        assert_eq!(result, Ok(board));
+    }
+
+    #[test]
+    fn recursive_types() {
+        let typ = Tree::get_type();
+        assert_eq!(
+            format!("{:?}", typ.nodes[0].type_),
+            r#"WitTypeNode::RecordType([("label", 1), ("children", 2)])"#
+        );
+        assert!(matches!(typ.nodes[2].type_, WitTypeNode::ListType(0)));
+
+        let value: WitValue = Value::Record(vec![
+            Value::String("root".to_string()),
+            Value::List(vec![Value::Record(vec![
+                Value::String("leaf".to_string()),
+                Value::List(vec![]),
+            ])]),
+        ])
+        .into();
+        assert_eq!(
+            Tree::from_extractor(&value),
+            Ok(Tree {
+                label: "root".to_string(),
+                children: vec![Tree {
+                    label: "leaf".to_string(),
+                    children: vec![],
+                }],
+            })
+        );
+    }
+
+    #[test]
+    fn recursive_generic_types() {
+        let typ = GenericTree::<u8>::get_type();
+        assert_eq!(
+            format!("{:?}", typ.nodes[0].type_),
+            r#"WitTypeNode::RecordType([("value", 1), ("children", 2)])"#
+        );
+        assert!(matches!(typ.nodes[2].type_, WitTypeNode::ListType(0)));
+
+        let typ = <(GenericTree<u8>, GenericTree<String>)>::get_type();
+        assert_eq!(typ.nodes.len(), 7);
+        assert!(matches!(typ.nodes[5].type_, WitTypeNode::PrimStringType));
+        assert!(matches!(typ.nodes[6].type_, WitTypeNode::ListType(4)));
     }
 }
@@ -0,0 +1,339 @@
+// Copyright 2024-2025 Golem Cloud
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Proptest strategies generating values of a `WitType`, and of Rust types implementing
+//! `IntoValue` and `FromValueAndType`.
+//!
+//! Floats are generated without NaNs, so generated values can be compared by `PartialEq`. Lists
+//! have at most `MAX_LIST_LENGTH` items, and below `MAX_DEPTH` nested nodes lists are empty,
+//! options are `none` and variants and results take the cases which end the nesting, so values
+//! of recursive types stay finite.
+
+use std::collections::HashSet;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use ::proptest::collection::vec;
+use ::proptest::num::{f32, f64};
+use ::proptest::prelude::*;
+use ::proptest::strategy::Union;
+use ::proptest::test_runner::{TestCaseError, TestRunner};
+use golem_wasm_rpc::golem_rpc_0_2_x::types::ValueAndType;
+use golem_wasm_rpc::{NodeIndex, Value, WitType, WitTypeNode};
+
+use crate::value_and_type::{FromValueAndType, IntoValue, IntoValueAndType};
+
+pub const MAX_LIST_LENGTH: usize = 8;
+pub const MAX_DEPTH: usize = 6;
+
+/// Generates values of the type
+pub fn values(typ: &WitType) -> BoxedStrategy<ValueAndType> {
+    let typ = Arc::new(typ.clone());
+    node(&typ, 0, MAX_DEPTH)
+        .prop_map(move |value| ValueAndType {
+            value: value.into(),
+            typ: (*typ).clone(),
+        })
+        .boxed()
+}
+
+/// Generates values of `T` by extracting them from the generated values of its `WitType`,
+/// skipping the ones `T` cannot be extracted from
+pub fn arbitrary<T: IntoValue + FromValueAndType + Debug + 'static>() -> BoxedStrategy<T> {
+    values(&T::get_type())
+        .prop_filter_map("value cannot be extracted", |value| {
+            T::from_value_and_type(value).ok()
+        })
+        .boxed()
+}
+
+/// Checks that the generated values of `T` are extracted unchanged from their `ValueAndType`
+/// representation, panicking with the smallest failing value otherwise
+pub fn assert_roundtrip<T: IntoValue + FromValueAndType + Debug + Clone + PartialEq + 'static>() {
+    let result = TestRunner::default().run(&arbitrary::<T>(), |value| {
+        let roundtrip = T::from_value_and_type(value.clone().into_value_and_type())
+            .map_err(|err| TestCaseError::fail(err.to_string()))?;
+        prop_assert_eq!(roundtrip, value);
+        Ok(())
+    });
+    if let Err(err) = result {
+        panic!("{} does not roundtrip: {err}", std::any::type_name::<T>());
+    }
+}
+
+fn node(typ: &Arc<WitType>, idx: NodeIndex, depth: usize) -> BoxedStrategy<Value> {
+    let child = |child_idx: NodeIndex| node(typ, child_idx, depth.saturating_sub(1));
+    match &typ.nodes[idx as usize].type_ {
+        WitTypeNode::PrimBoolType => any::<bool>().prop_map(Value::Bool).boxed(),
+        WitTypeNode::PrimU8Type => any::<u8>().prop_map(Value::U8).boxed(),
+        WitTypeNode::PrimU16Type => any::<u16>().prop_map(Value::U16).boxed(),
+        WitTypeNode::PrimU32Type => any::<u32>().prop_map(Value::U32).boxed(),
+        WitTypeNode::PrimU64Type => any::<u64>().prop_map(Value::U64).boxed(),
+        WitTypeNode::PrimS8Type => any::<i8>().prop_map(Value::S8).boxed(),
+        WitTypeNode::PrimS16Type => any::<i16>().prop_map(Value::S16).boxed(),
+        WitTypeNode::PrimS32Type => any::<i32>().prop_map(Value::S32).boxed(),
+        WitTypeNode::PrimS64Type => any::<i64>().prop_map(Value::S64).boxed(),
+        WitTypeNode::PrimF32Type => (f32::POSITIVE
+            | f32::NEGATIVE
+            | f32::NORMAL
+            | f32::SUBNORMAL
+            | f32::ZERO
+            | f32::INFINITE)
+            .prop_map(Value::F32)
+            .boxed(),
+        WitTypeNode::PrimF64Type => (f64::POSITIVE
+            | f64::NEGATIVE
+            | f64::NORMAL
+            | f64::SUBNORMAL
+            | f64::ZERO
+            | f64::INFINITE)
+            .prop_map(Value::F64)
+            .boxed(),
+        WitTypeNode::PrimCharType => any::<char>().prop_map(Value::Char).boxed(),
+        WitTypeNode::PrimStringType => any::<String>().prop_map(Value::String).boxed(),
+        WitTypeNode::RecordType(fields) => fields
+            .iter()
+            .map(|(_, field)| child(*field))
+            .collect::<Vec<_>>()
+            .prop_map(Value::Record)
+            .boxed(),
+        WitTypeNode::TupleType(items) => items
+            .iter()
+            .map(|item| child(*item))
+            .collect::<Vec<_>>()
+            .prop_map(Value::Tuple)
+            .boxed(),
+        WitTypeNode::VariantType(cases) => {
+            let cases = cases
+                .iter()
+                .enumerate()
+                .filter(|(_, (_, case))| depth > 0 || case_ends_nesting(typ, *case))
+                .map(|(case_idx, (_, case))| {
+                    let case_idx = case_idx as u32;
+                    match case {
+                        Some(case) => child(*case)
+                            .prop_map(move |value| Value::Variant {
+                                case_idx,
+                                case_value: Some(Box::new(value)),
+                            })
+                            .boxed(),
+                        None => Just(Value::Variant {
+                            case_idx,
+                            case_value: None,
+                        })
+                        .boxed(),
+                    }
+                })
+                .collect::<Vec<_>>();
+            assert!(
+                !cases.is_empty(),
+                "variant type {idx} has no cases to generate"
+            );
+            Union::new(cases).boxed()
+        }
+        WitTypeNode::EnumType(cases) => {
+            assert!(
+                !cases.is_empty(),
+                "enum type {idx} has no cases to generate"
+            );
+            (0..cases.len() as u32).prop_map(Value::Enum).boxed()
+        }
+        WitTypeNode::FlagsType(flags) => vec(any::<bool>(), flags.len())
+            .prop_map(Value::Flags)
+            .boxed(),
+        WitTypeNode::ListType(item) if depth > 0 => vec(child(*item), 0..=MAX_LIST_LENGTH)
+            .prop_map(Value::List)
+            .boxed(),
+        WitTypeNode::ListType(_) => Just(Value::List(Vec::new())).boxed(),
+        WitTypeNode::OptionType(inner) if depth > 0 => ::proptest::option::of(child(*inner))
+            .prop_map(|value| Value::Option(value.map(Box::new)))
+            .boxed(),
+        WitTypeNode::OptionType(_) => Just(Value::Option(None)).boxed(),
+        WitTypeNode::ResultType((ok, err)) => {
+            let case = |case: Option<NodeIndex>| match case {
+                Some(case) => child(case).prop_map(|value| Some(Box::new(value))).boxed(),
+                None => Just(None).boxed(),
+            };
+            let mut cases = Vec::new();
+            if depth > 0 || case_ends_nesting(typ, *ok) {
+                cases.push(case(*ok).prop_map(|value| Value::Result(Ok(value))).boxed());
+            }
+            if depth > 0 || case_ends_nesting(typ, *err) {
+                cases.push(
+                    case(*err)
+                        .prop_map(|value| Value::Result(Err(value)))
+                        .boxed(),
+                );
+            }
+            assert!(
+                !cases.is_empty(),
+                "result type {idx} has no cases to generate"
+            );
+            Union::new(cases).boxed()
+        }
+        WitTypeNode::HandleType(_) => any::<u64>()
+            .prop_map(|resource_id| Value::Handle {
+                uri: "urn:worker:proptest".to_string(),
+                resource_id,
+            })
+            .boxed(),
+    }
+}
+
+/// Whether a finite value of the case can be generated when no more nesting is allowed
+fn case_ends_nesting(typ: &WitType, case: Option<NodeIndex>) -> bool {
+    case.is_none_or(|case| ends_nesting(typ, case, &mut HashSet::new()))
+}
+
+fn ends_nesting(typ: &WitType, idx: NodeIndex, visiting: &mut HashSet<NodeIndex>) -> bool {
+    if !visiting.insert(idx) {
+        return false;
+    }
+    let result = match &typ.nodes[idx as usize].type_ {
+        WitTypeNode::RecordType(fields) => fields
+            .iter()
+            .all(|(_, field)| ends_nesting(typ, *field, visiting)),
+        WitTypeNode::TupleType(items) => {
+            items.iter().all(|item| ends_nesting(typ, *item, visiting))
+        }
+        WitTypeNode::VariantType(cases) => cases
+            .iter()
+            .any(|(_, case)| case.is_none_or(|case| ends_nesting(typ, case, visiting))),
+        WitTypeNode::ResultType((ok, err)) => [ok, err]
+            .iter()
+            .any(|case| case.is_none_or(|case| ends_nesting(typ, case, visiting))),
+        _ => true,
+    };
+    visiting.remove(&idx);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, HashMap};
+
+    use proptest::strategy::{Strategy, ValueTree};
+    use proptest::test_runner::TestRunner;
+
+    use crate::value_and_type::proptest::{assert_roundtrip, values};
+    use crate::value_and_type::{Bytes, DynValue, IntoValue};
+
+    #[test]
+    fn values_conform_to_the_type() {
+        let typ = <(Vec<Option<String>>, Result<u8, ()>, [f64; 2])>::get_type();
+        let mut runner = TestRunner::default();
+        for _ in 0..64 {
+            let value = values(&typ).new_tree(&mut runner).unwrap().current();
+            assert!(DynValue::from_value_and_type(&value).is_ok());
+        }
+    }
+
+    #[test]
+    fn std_types_roundtrip() {
+        assert_roundtrip::<(bool, char, String, u128, i128)>();
+        assert_roundtrip::<(Vec<Option<u32>>, Result<i64, String>, Result<(), u8>)>();
+        assert_roundtrip::<(f32, f64)>();
+        assert_roundtrip::<(HashMap<String, u16>, BTreeMap<u8, Vec<i8>>)>();
+        assert_roundtrip::<Bytes>();
+    }
+}
+
+#[cfg(all(test, feature = "macro"))]
+mod derive_tests {
+    use proptest::strategy::{Strategy, ValueTree};
+    use proptest::test_runner::TestRunner;
+
+    use crate::value_and_type::proptest::{assert_roundtrip, values};
+    use crate::value_and_type::{FromValueAndType as _, IntoValue as _};
+    use crate::{FromValueAndType, IntoValue};
+
+    #[derive(Debug, Clone, PartialEq, IntoValue, FromValueAndType)]
+    #[wit(flags)]
+    struct Permissions {
+        read: bool,
+        write: bool,
+        execute: bool,
+    }
+
+    #[derive(Debug, Clone, PartialEq, IntoValue, FromValueAndType)]
+    #[wit(rename_all = "snake_case")]
+    enum Level {
+        Low,
+        MediumHigh,
+    }
+
+    #[derive(Debug, Clone, PartialEq, IntoValue, FromValueAndType)]
+    enum Event {
+        Started,
+        Progress(u8),
+        Moved {
+            x: i32,
+            y: i32,
+        },
+        Renamed(String, String),
+        #[wit(rename = "custom-finished")]
+        Finished(Option<u32>),
+    }
+
+    #[derive(Debug, Clone, PartialEq, IntoValue, FromValueAndType)]
+    struct Wrapper<T> {
+        inner: T,
+    }
+
+    #[derive(Debug, Clone, PartialEq, IntoValue, FromValueAndType)]
+    struct Pair(u16, String);
+
+    #[derive(Debug, Clone, PartialEq, IntoValue, FromValueAndType)]
+    #[wit(transparent)]
+    struct Id(u64);
+
+    #[derive(Debug, Clone, PartialEq, IntoValue, FromValueAndType)]
+    struct Job {
+        id: Id,
+        #[wit(rename = "job-name")]
+        name: String,
+        permissions: Permissions,
+        level: Level,
+        events: Vec<Event>,
+        pair: Option<Pair>,
+        wrapped: Wrapper<Vec<u8>>,
+        #[wit(skip)]
+        cached: Option<u32>,
+    }
+
+    #[derive(Debug, Clone, PartialEq, IntoValue, FromValueAndType)]
+    struct Tree {
+        label: String,
+        children: Vec<Tree>,
+    }
+
+    #[test]
+    fn derived_types_roundtrip() {
+        assert_roundtrip::<Permissions>();
+        assert_roundtrip::<Level>();
+        assert_roundtrip::<Event>();
+        assert_roundtrip::<Wrapper<(u8, Level)>>();
+        assert_roundtrip::<Job>();
+    }
+
+    #[test]
+    fn recursive_types_are_finite() {
+        let typ = Tree::get_type();
+        let mut runner = TestRunner::default();
+        for _ in 0..64 {
+            let value = values(&typ).new_tree(&mut runner).unwrap().current();
+            assert!(Tree::from_value_and_type(value).is_ok());
+        }
+    }
+}
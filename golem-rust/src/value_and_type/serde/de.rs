@@ -0,0 +1,431 @@
+// Copyright 2024-2025 Golem Cloud
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::marker::PhantomData;
+
+use ::serde::de::value::{U32Deserializer, UnitDeserializer};
+use ::serde::de::{
+    DeserializeSeed, Deserializer, EnumAccess, IntoDeserializer, MapAccess, SeqAccess,
+    VariantAccess, Visitor,
+};
+use golem_wasm_rpc::{WitNodePointer, WitValueExtractor};
+
+use crate::value_and_type::serde::SerdeError;
+
+/// Serde `Deserializer` reading a value through a `WitValueExtractor`
+pub struct WitValueDeserializer<'a, 'de, E: WitValueExtractor<'a, 'de>> {
+    extractor: &'a E,
+    _lifetime: PhantomData<&'de ()>,
+}
+
+impl<'a, 'de, E: WitValueExtractor<'a, 'de>> WitValueDeserializer<'a, 'de, E> {
+    pub fn new(extractor: &'a E) -> Self {
+        Self {
+            extractor,
+            _lifetime: PhantomData,
+        }
+    }
+
+    /// The elements of a list, tuple or record; empty for any other value
+    fn elements(&self) -> Vec<WitNodePointer<'de>> {
+        if let Some(elements) = self.extractor.list_elements(|element| element) {
+            return elements;
+        }
+        let tuple_elements: Vec<_> = (0..)
+            .map_while(|idx| self.extractor.tuple_element(idx))
+            .collect();
+        if !tuple_elements.is_empty() {
+            return tuple_elements;
+        }
+        (0..).map_while(|idx| self.extractor.field(idx)).collect()
+    }
+
+    fn wide_integer_parts(&self, name: &str) -> Result<(WitNodePointer<'de>, u64), SerdeError> {
+        let high = self.extractor.tuple_element(0);
+        let low = self.extractor.tuple_element(1).and_then(|low| low.u64());
+        match (high, low) {
+            (Some(high), Some(low)) => Ok((high, low)),
+            _ => Err(SerdeError(format!("Expected {name} tuple"))),
+        }
+    }
+}
+
+fn child<'p, 'de>(
+    pointer: &'p WitNodePointer<'de>,
+) -> WitValueDeserializer<'p, 'de, WitNodePointer<'de>> {
+    WitValueDeserializer::new(pointer)
+}
+
+macro_rules! deserialize_primitive {
+    ($method:ident, $extract:ident, $visit:ident) => {
+        fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+            match self.extractor.$extract() {
+                Some(value) => visitor.$visit(value),
+                None => self.deserialize_any(visitor),
+            }
+        }
+    };
+}
+
+impl<'a, 'de, E: WitValueExtractor<'a, 'de>> Deserializer<'de>
+    for WitValueDeserializer<'a, 'de, E>
+{
+    type Error = SerdeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        let extractor = self.extractor;
+        if let Some(value) = extractor.bool() {
+            visitor.visit_bool(value)
+        } else if let Some(value) = extractor.u8() {
+            visitor.visit_u8(value)
+        } else if let Some(value) = extractor.u16() {
+            visitor.visit_u16(value)
+        } else if let Some(value) = extractor.u32() {
+            visitor.visit_u32(value)
+        } else if let Some(value) = extractor.u64() {
+            visitor.visit_u64(value)
+        } else if let Some(value) = extractor.s8() {
+            visitor.visit_i8(value)
+        } else if let Some(value) = extractor.s16() {
+            visitor.visit_i16(value)
+        } else if let Some(value) = extractor.s32() {
+            visitor.visit_i32(value)
+        } else if let Some(value) = extractor.s64() {
+            visitor.visit_i64(value)
+        } else if let Some(value) = extractor.f32() {
+            visitor.visit_f32(value)
+        } else if let Some(value) = extractor.f64() {
+            visitor.visit_f64(value)
+        } else if let Some(value) = extractor.char() {
+            visitor.visit_char(value)
+        } else if let Some(value) = extractor.string() {
+            visitor.visit_borrowed_str(value)
+        } else if let Some(value) = extractor.option() {
+            match value {
+                Some(inner) => visitor.visit_some(child(&inner)),
+                None => visitor.visit_none(),
+            }
+        } else if let Some((case_idx, payload)) = extractor.variant() {
+            visitor.visit_enum(Variant { case_idx, payload })
+        } else if let Some(case_idx) = extractor.enum_value() {
+            visitor.visit_enum(Variant {
+                case_idx,
+                payload: None,
+            })
+        } else if let Some(flags) = extractor.flags() {
+            visitor.visit_seq(
+                flags
+                    .iter()
+                    .copied()
+                    .map(IntoDeserializer::into_deserializer)
+                    .collect::<Vec<_>>()
+                    .into_deserializer(),
+            )
+        } else if let Some(result) = extractor.result() {
+            let (case_idx, payload) = match result {
+                Ok(payload) => (0, payload),
+                Err(payload) => (1, payload),
+            };
+            visitor.visit_enum(Variant { case_idx, payload })
+        } else if extractor.handle().is_some() {
+            Err(SerdeError(
+                "Resource handles cannot be deserialized with serde".to_string(),
+            ))
+        } else {
+            let elements = self.elements();
+            if elements.is_empty() && extractor.list_elements(|_| ()).is_none() {
+                visitor.visit_unit()
+            } else {
+                visitor.visit_seq(Elements::new(elements))
+            }
+        }
+    }
+
+    deserialize_primitive!(deserialize_bool, bool, visit_bool);
+    deserialize_primitive!(deserialize_i8, s8, visit_i8);
+    deserialize_primitive!(deserialize_i16, s16, visit_i16);
+    deserialize_primitive!(deserialize_i32, s32, visit_i32);
+    deserialize_primitive!(deserialize_i64, s64, visit_i64);
+    deserialize_primitive!(deserialize_u8, u8, visit_u8);
+    deserialize_primitive!(deserialize_u16, u16, visit_u16);
+    deserialize_primitive!(deserialize_u32, u32, visit_u32);
+    deserialize_primitive!(deserialize_u64, u64, visit_u64);
+    deserialize_primitive!(deserialize_f32, f32, visit_f32);
+    deserialize_primitive!(deserialize_f64, f64, visit_f64);
+    deserialize_primitive!(deserialize_char, char, visit_char);
+    deserialize_primitive!(deserialize_str, string, visit_borrowed_str);
+    deserialize_primitive!(deserialize_string, string, visit_borrowed_str);
+
+    fn deserialize_i128<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        let (high, low) = self.wide_integer_parts("i128")?;
+        let high = high
+            .s64()
+            .ok_or_else(|| SerdeError("Expected i128 tuple".to_string()))?;
+        visitor.visit_i128(((high as i128) << 64) | low as i128)
+    }
+
+    fn deserialize_u128<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        let (high, low) = self.wide_integer_parts("u128")?;
+        let high = high
+            .u64()
+            .ok_or_else(|| SerdeError("Expected u128 tuple".to_string()))?;
+        visitor.visit_u128(((high as u128) << 64) | low as u128)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        match self.extractor.list_elements(|element| element.u8()) {
+            Some(bytes) => match bytes.into_iter().collect::<Option<Vec<u8>>>() {
+                Some(bytes) => visitor.visit_byte_buf(bytes),
+                None => Err(SerdeError("Expected list of u8".to_string())),
+            },
+            None => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        match self.extractor.option() {
+            Some(Some(inner)) => visitor.visit_some(child(&inner)),
+            Some(None) => visitor.visit_none(),
+            None => Err(SerdeError("Expected option".to_string())),
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        visitor.visit_seq(Elements::new(self.elements()))
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        match self.extractor.list_elements(|element| element) {
+            Some(entries) => visitor.visit_map(Entries {
+                entries: entries.into_iter(),
+                value: None,
+            }),
+            None => Err(SerdeError("Expected list of key-value tuples".to_string())),
+        }
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        if let Some((case_idx, payload)) = self.extractor.variant() {
+            visitor.visit_enum(Variant { case_idx, payload })
+        } else if let Some(case_idx) = self.extractor.enum_value() {
+            visitor.visit_enum(Variant {
+                case_idx,
+                payload: None,
+            })
+        } else if let Some(result) = self.extractor.result() {
+            let (case_idx, payload) = match result {
+                Ok(payload) => (0, payload),
+                Err(payload) => (1, payload),
+            };
+            visitor.visit_enum(Variant { case_idx, payload })
+        } else {
+            Err(SerdeError(format!("Expected variant for {name}")))
+        }
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        visitor.visit_unit()
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+struct Elements<'de> {
+    elements: std::vec::IntoIter<WitNodePointer<'de>>,
+}
+
+impl<'de> Elements<'de> {
+    fn new(elements: Vec<WitNodePointer<'de>>) -> Self {
+        Self {
+            elements: elements.into_iter(),
+        }
+    }
+}
+
+impl<'de> SeqAccess<'de> for Elements<'de> {
+    type Error = SerdeError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, SerdeError> {
+        match self.elements.next() {
+            Some(element) => seed.deserialize(child(&element)).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.elements.len())
+    }
+}
+
+struct Entries<'de> {
+    entries: std::vec::IntoIter<WitNodePointer<'de>>,
+    value: Option<WitNodePointer<'de>>,
+}
+
+impl<'de> MapAccess<'de> for Entries<'de> {
+    type Error = SerdeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, SerdeError> {
+        match self.entries.next() {
+            Some(entry) => {
+                let (key, value) = match (entry.tuple_element(0), entry.tuple_element(1)) {
+                    (Some(key), Some(value)) => (key, value),
+                    _ => return Err(SerdeError("Expected key-value tuple".to_string())),
+                };
+                self.value = Some(value);
+                seed.deserialize(child(&key)).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, SerdeError> {
+        match self.value.take() {
+            Some(value) => seed.deserialize(child(&value)),
+            None => Err(SerdeError("Map value requested before its key".to_string())),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.entries.len())
+    }
+}
+
+struct Variant<'de> {
+    case_idx: u32,
+    payload: Option<WitNodePointer<'de>>,
+}
+
+impl<'de> EnumAccess<'de> for Variant<'de> {
+    type Error = SerdeError;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self), SerdeError> {
+        let variant = seed.deserialize(U32Deserializer::<SerdeError>::new(self.case_idx))?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de> VariantAccess<'de> for Variant<'de> {
+    type Error = SerdeError;
+
+    fn unit_variant(self) -> Result<(), SerdeError> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> Result<T::Value, SerdeError> {
+        match self.payload {
+            Some(payload) => seed.deserialize(child(&payload)),
+            None => seed.deserialize(UnitDeserializer::<SerdeError>::new()),
+        }
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        match self.payload {
+            Some(payload) => child(&payload).deserialize_tuple(len, visitor),
+            None => Err(SerdeError("Missing tuple variant payload".to_string())),
+        }
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        match self.payload {
+            Some(payload) => child(&payload).deserialize_struct("", fields, visitor),
+            None => Err(SerdeError("Missing struct variant payload".to_string())),
+        }
+    }
+}
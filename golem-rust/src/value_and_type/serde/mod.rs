@@ -0,0 +1,529 @@
+// Copyright 2024-2025 Golem Cloud
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Bridge between serde and the `WitValue`/`WitType` representation.
+//!
+//! Any type implementing `Serialize` and `Deserialize` can be converted to and from a
+//! `ValueAndType` without deriving `IntoValue` and `FromValueAndType`, either by the functions of
+//! this module or by wrapping it in `SerdeValue` (which cannot be nested in `IntoValue` types and
+//! is converted by the fallible `TryIntoValueAndType`).
+//! The serde data model is mapped as follows:
+//!
+//! - primitive types and strings are mapped to the corresponding WIT primitives
+//! - `i128`/`u128` are tuples of their high and low 64 bits, like their `IntoValue` implementation
+//! - byte arrays and sequences are lists, maps are lists of key-value tuples
+//! - `()`, unit structs and empty tuples are empty tuples
+//! - newtype structs are represented by their inner value
+//! - structs are records, tuples and tuple structs are tuples
+//! - enums are variants, where tuple and struct variants have a tuple or record payload
+
+mod de;
+mod ser;
+mod trace;
+
+use std::fmt::{Display, Formatter};
+
+use ::serde::de::DeserializeOwned;
+use ::serde::Serialize;
+use golem_wasm_rpc::golem_rpc_0_2_x::types::ValueAndType;
+use golem_wasm_rpc::{NodeIndex, Value, WitType, WitValue, WitValueExtractor};
+
+use crate::value_and_type::type_builder::{
+    InnerTypeNodeBuilder, WitTypeBuilder, WitTypeBuilderExtensions, WitTypeResultBuilder,
+};
+use crate::value_and_type::{FromValueAndType, IntoValue, TypeNodeBuilder, ValueError};
+
+pub use de::WitValueDeserializer;
+pub use ser::WitValueSerializer;
+
+/// Error of converting between serde types and `WitValue`s
+#[derive(Debug, Clone, PartialEq)]
+pub struct SerdeError(String);
+
+impl Display for SerdeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for SerdeError {}
+
+impl ::serde::ser::Error for SerdeError {
+    fn custom<T: Display>(msg: T) -> Self {
+        SerdeError(msg.to_string())
+    }
+}
+
+impl ::serde::de::Error for SerdeError {
+    fn custom<T: Display>(msg: T) -> Self {
+        SerdeError(msg.to_string())
+    }
+}
+
+impl From<SerdeError> for ValueError {
+    fn from(value: SerdeError) -> Self {
+        ValueError::custom(value.0)
+    }
+}
+
+impl From<SerdeError> for String {
+    fn from(value: SerdeError) -> Self {
+        value.0
+    }
+}
+
+/// Serializes a value, inferring its type from the value itself.
+///
+/// As the type is inferred from a single value, it is incomplete where the value does not
+/// contain enough information: the element type of empty sequences and of `None` is an empty
+/// tuple, and enum variants only know the case of the serialized value (other cases get
+/// placeholder names). Use `wit_type_of` to get the complete type of a deserializable type.
+pub fn to_value_and_type<T: Serialize + ?Sized>(value: &T) -> Result<ValueAndType, SerdeError> {
+    let (value, typ) = value.serialize(WitValueSerializer)?;
+    Ok(ValueAndType {
+        value: value.into(),
+        typ: typ.to_wit_type(),
+    })
+}
+
+/// Serializes a value to a `WitValue`
+pub fn to_wit_value<T: Serialize + ?Sized>(value: &T) -> Result<WitValue, SerdeError> {
+    let (value, _) = value.serialize(WitValueSerializer)?;
+    Ok(value.into())
+}
+
+/// Deserializes a value from a `ValueAndType`. The type information is not used.
+pub fn from_value_and_type<T: DeserializeOwned>(
+    value_and_type: ValueAndType,
+) -> Result<T, SerdeError> {
+    from_extractor(&value_and_type.value)
+}
+
+/// Deserializes a value through a `WitValueExtractor`
+pub fn from_extractor<'a, 'b, T: ::serde::Deserialize<'b>>(
+    extractor: &'a impl WitValueExtractor<'a, 'b>,
+) -> Result<T, SerdeError> {
+    T::deserialize(WitValueDeserializer::new(extractor))
+}
+
+/// Determines the type of a deserializable type by tracing the shapes it requests during
+/// deserialization, with all cases of its enums.
+///
+/// Tracing fails for recursive types and for types which deserialize themselves without a
+/// type hint (`deserialize_any`), such as untagged enums. Enums are identified by their name, so
+/// different instances of a generic enum within the same type are not distinguished.
+pub fn wit_type_of<T: DeserializeOwned>() -> Result<WitType, SerdeError> {
+    Ok(trace::trace::<T>()?.to_wit_type())
+}
+
+/// Wrapper making any serde type usable as a durable function result in the `Durability` API.
+///
+/// It implements `FromValueAndType`, so it can be extracted at any position, but not
+/// `IntoValue`: the shape of the value is only known at runtime, while `NodeBuilder` nests its
+/// builders statically. It is therefore only converted by `TryIntoValueAndType`, on its own or as
+/// a case of a `Result` (which is how `Durability::try_persist_serializable` persists results),
+/// and cannot be a field or element of other `IntoValue` types. Use `to_value_and_type` for other
+/// compositions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SerdeValue<T>(pub T);
+
+impl<T> SerdeValue<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T: Serialize> SerdeValue<T> {
+    fn into_dyn_value(self) -> Result<Value, SerdeError> {
+        let (value, _) = self.0.serialize(WitValueSerializer)?;
+        Ok(value)
+    }
+}
+
+/// Conversion to a `ValueAndType` which fails if the value cannot be serialized or its type
+/// cannot be determined by `wit_type_of`
+pub trait TryIntoValueAndType {
+    fn try_into_value_and_type(self) -> Result<ValueAndType, SerdeError>;
+}
+
+impl<T: Serialize + DeserializeOwned> TryIntoValueAndType for SerdeValue<T> {
+    fn try_into_value_and_type(self) -> Result<ValueAndType, SerdeError> {
+        let typ = wit_type_of::<T>()?;
+        Ok(ValueAndType {
+            value: self.into_dyn_value()?.into(),
+            typ,
+        })
+    }
+}
+
+impl<T: DeserializeOwned> FromValueAndType for SerdeValue<T> {
+    fn from_extractor<'a, 'b>(
+        extractor: &'a impl WitValueExtractor<'a, 'b>,
+    ) -> Result<Self, ValueError> {
+        Ok(SerdeValue(from_extractor(extractor)?))
+    }
+}
+
+impl<T: Serialize + DeserializeOwned, E: IntoValue> TryIntoValueAndType
+    for Result<SerdeValue<T>, E>
+{
+    fn try_into_value_and_type(self) -> Result<ValueAndType, SerdeError> {
+        result_into_value_and_type(self)
+    }
+}
+
+impl<S: IntoValue, T: Serialize + DeserializeOwned> TryIntoValueAndType
+    for Result<S, SerdeValue<T>>
+{
+    fn try_into_value_and_type(self) -> Result<ValueAndType, SerdeError> {
+        result_into_value_and_type(self)
+    }
+}
+
+impl<T: Serialize + DeserializeOwned, E: Serialize + DeserializeOwned> TryIntoValueAndType
+    for Result<SerdeValue<T>, SerdeValue<E>>
+{
+    fn try_into_value_and_type(self) -> Result<ValueAndType, SerdeError> {
+        result_into_value_and_type(self)
+    }
+}
+
+/// One of the cases of a `Result` converted by `result_into_value_and_type`
+trait ResultCase {
+    /// The value of the case, `None` for a unit case
+    fn into_case_value(self) -> Result<Option<Value>, SerdeError>;
+
+    fn add_case_type(
+        builder: WitTypeResultBuilder<WitTypeBuilder>,
+        ok: bool,
+    ) -> Result<WitTypeResultBuilder<WitTypeBuilder>, SerdeError>;
+}
+
+impl<T: IntoValue> ResultCase for T {
+    fn into_case_value(self) -> Result<Option<Value>, SerdeError> {
+        Ok((!T::IS_UNIT).then(|| self.into_value().into()))
+    }
+
+    fn add_case_type(
+        builder: WitTypeResultBuilder<WitTypeBuilder>,
+        ok: bool,
+    ) -> Result<WitTypeResultBuilder<WitTypeBuilder>, SerdeError> {
+        Ok(match (T::IS_UNIT, ok) {
+            (true, true) => builder.ok_unit(),
+            (true, false) => builder.err_unit(),
+            (false, true) => T::add_to_type_builder(builder.ok()),
+            (false, false) => T::add_to_type_builder(builder.err()),
+        })
+    }
+}
+
+impl<T: Serialize + DeserializeOwned> ResultCase for SerdeValue<T> {
+    fn into_case_value(self) -> Result<Option<Value>, SerdeError> {
+        self.into_dyn_value().map(Some)
+    }
+
+    fn add_case_type(
+        builder: WitTypeResultBuilder<WitTypeBuilder>,
+        ok: bool,
+    ) -> Result<WitTypeResultBuilder<WitTypeBuilder>, SerdeError> {
+        let typ = trace::trace::<T>()?;
+        Ok(if ok {
+            typ.add_to_inner_builder(builder.ok())
+        } else {
+            typ.add_to_inner_builder(builder.err())
+        })
+    }
+}
+
+fn result_into_value_and_type<S: ResultCase, E: ResultCase>(
+    result: Result<S, E>,
+) -> Result<ValueAndType, SerdeError> {
+    let mut builder = WitType::builder().result(None, None);
+    builder = S::add_case_type(builder, true)?;
+    builder = E::add_case_type(builder, false)?;
+    let value = match result {
+        Ok(ok) => Value::Result(Ok(ok.into_case_value()?.map(Box::new))),
+        Err(err) => Value::Result(Err(err.into_case_value()?.map(Box::new))),
+    };
+    Ok(ValueAndType {
+        value: value.into(),
+        typ: builder.finish(),
+    })
+}
+
+/// The shape of a serde type, inferred by `WitValueSerializer` or traced by `wit_type_of`
+#[derive(Debug, Clone, PartialEq)]
+pub enum SerdeType {
+    Bool,
+    U8,
+    U16,
+    U32,
+    U64,
+    S8,
+    S16,
+    S32,
+    S64,
+    F32,
+    F64,
+    Char,
+    String,
+    /// Not known from the serialized value; represented as an empty tuple
+    Unknown,
+    Option(Box<SerdeType>),
+    List(Box<SerdeType>),
+    Tuple(Vec<SerdeType>),
+    Record {
+        name: Option<&'static str>,
+        fields: Vec<(&'static str, SerdeType)>,
+    },
+    Variant {
+        name: &'static str,
+        cases: Vec<(String, Option<SerdeType>)>,
+    },
+}
+
+impl SerdeType {
+    pub fn unit() -> Self {
+        SerdeType::Tuple(Vec::new())
+    }
+
+    pub fn to_wit_type(&self) -> WitType {
+        let mut builder = WitType::builder();
+        self.add_nodes(&mut builder);
+        builder.build()
+    }
+
+    /// Adds the type as the node of a child builder. The nodes are added directly, as the shape
+    /// is only known at runtime while the nesting of the typed builders is static.
+    fn add_to_inner_builder<B: InnerTypeNodeBuilder>(&self, mut builder: B) -> B::Result {
+        let idx = self.add_nodes(InnerTypeNodeBuilder::parent_builder(&mut builder));
+        InnerTypeNodeBuilder::finish(builder, idx)
+    }
+
+    fn add_nodes(&self, builder: &mut WitTypeBuilder) -> NodeIndex {
+        match self {
+            SerdeType::Bool => builder.add_bool(),
+            SerdeType::U8 => builder.add_u8(),
+            SerdeType::U16 => builder.add_u16(),
+            SerdeType::U32 => builder.add_u32(),
+            SerdeType::U64 => builder.add_u64(),
+            SerdeType::S8 => builder.add_s8(),
+            SerdeType::S16 => builder.add_s16(),
+            SerdeType::S32 => builder.add_s32(),
+            SerdeType::S64 => builder.add_s64(),
+            SerdeType::F32 => builder.add_f32(),
+            SerdeType::F64 => builder.add_f64(),
+            SerdeType::Char => builder.add_char(),
+            SerdeType::String => builder.add_string(),
+            SerdeType::Unknown => builder.add_tuple(None, None),
+            SerdeType::Option(inner) => {
+                let idx = builder.add_option(None, None);
+                let inner_idx = inner.add_nodes(builder);
+                builder.finish_container(idx, inner_idx);
+                idx
+            }
+            SerdeType::List(inner) => {
+                let idx = builder.add_list(None, None);
+                let inner_idx = inner.add_nodes(builder);
+                builder.finish_container(idx, inner_idx);
+                idx
+            }
+            SerdeType::Tuple(items) => {
+                let idx = builder.add_tuple(None, None);
+                let items = items.iter().map(|item| item.add_nodes(builder)).collect();
+                builder.finish_tuple(idx, items);
+                idx
+            }
+            SerdeType::Record { name, fields } => {
+                let idx = builder.add_record(name.map(|name| name.to_string()), None);
+                let fields = fields
+                    .iter()
+                    .map(|(field_name, field_type)| {
+                        (field_name.to_string(), field_type.add_nodes(builder))
+                    })
+                    .collect();
+                builder.finish_record(idx, fields);
+                idx
+            }
+            SerdeType::Variant { name, cases } => {
+                let idx = builder.add_variant(Some(name.to_string()), None);
+                let cases = cases
+                    .iter()
+                    .map(|(case_name, case_type)| {
+                        let case_idx = case_type.as_ref().map(|typ| typ.add_nodes(builder));
+                        (case_name.clone(), case_idx)
+                    })
+                    .collect();
+                builder.finish_variant(idx, cases);
+                idx
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use ::serde::{Deserialize, Serialize};
+    use golem_wasm_rpc::{NodeBuilder, WitType, WitValue, WitValueBuilderExtensions};
+
+    use crate::value_and_type::serde::{
+        from_value_and_type, to_value_and_type, wit_type_of, SerdeValue, TryIntoValueAndType,
+    };
+    use crate::value_and_type::type_builder::WitTypeBuilderExtensions;
+    use crate::value_and_type::FromValueAndType;
+    use crate::value_and_type::TypeNodeBuilder;
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Order {
+        id: u64,
+        customer: Option<String>,
+        items: Vec<Item>,
+        tags: BTreeMap<String, i32>,
+        status: Status,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Item(String, u32);
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    enum Status {
+        Pending,
+        Shipped(u128),
+        Cancelled { reason: String, refunded: bool },
+    }
+
+    fn order(status: Status) -> Order {
+        Order {
+            id: 42,
+            customer: Some("golem".to_string()),
+            items: vec![Item("sku-1".to_string(), 2), Item("sku-2".to_string(), 1)],
+            tags: BTreeMap::from([("priority".to_string(), -1)]),
+            status,
+        }
+    }
+
+    #[test]
+    fn serde_roundtrip() {
+        for status in [
+            Status::Pending,
+            Status::Shipped(u128::MAX - 1),
+            Status::Cancelled {
+                reason: "out of stock".to_string(),
+                refunded: true,
+            },
+        ] {
+            let order = order(status);
+            let value_and_type = to_value_and_type(&order).unwrap();
+            assert_eq!(from_value_and_type::<Order>(value_and_type), Ok(order));
+        }
+    }
+
+    #[test]
+    fn tuple_struct_is_a_tuple() {
+        let value_and_type = to_value_and_type(&Item("sku".to_string(), 3)).unwrap();
+        let expected = WitValue::builder()
+            .tuple()
+            .item()
+            .string("sku")
+            .item()
+            .u32(3)
+            .finish();
+        assert_eq!(value_and_type.value, expected);
+    }
+
+    #[test]
+    fn traced_type_contains_all_cases() {
+        let expected = {
+            let builder = WitType::builder().variant(Some("Status".to_string()), None);
+            let builder = builder.unit_case("Pending");
+            let builder = builder
+                .case("Shipped")
+                .tuple(None, None)
+                .item()
+                .u64()
+                .item()
+                .u64()
+                .finish();
+            let builder = builder
+                .case("Cancelled")
+                .record(None, None)
+                .field("reason")
+                .string()
+                .field("refunded")
+                .bool()
+                .finish();
+            builder.finish()
+        };
+        assert_eq!(
+            format!("{:?}", wit_type_of::<Status>().unwrap()),
+            format!("{expected:?}")
+        );
+
+        let order_type = wit_type_of::<Order>().unwrap();
+        assert_eq!(order_type.nodes[0].name.as_deref(), Some("Order"));
+    }
+
+    #[test]
+    fn untraceable_types_are_reported() {
+        #[derive(Serialize, Deserialize)]
+        struct Tree {
+            children: Vec<Tree>,
+        }
+
+        assert!(wit_type_of::<Tree>().is_err());
+        let tree = Tree { children: vec![] };
+        assert!(SerdeValue(tree).try_into_value_and_type().is_err());
+        let result: Result<(), SerdeValue<Tree>> = Err(SerdeValue(Tree { children: vec![] }));
+        assert!(result.try_into_value_and_type().is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn self_describing_types_are_reported() {
+        assert!(wit_type_of::<::serde_json::Value>().is_err());
+    }
+
+    #[test]
+    fn serde_value_roundtrip() {
+        let order = order(Status::Shipped(7));
+        let value_and_type = SerdeValue(order.clone()).try_into_value_and_type().unwrap();
+        assert_eq!(
+            format!("{:?}", value_and_type.typ),
+            format!("{:?}", wit_type_of::<Order>().unwrap())
+        );
+        assert_eq!(
+            SerdeValue::<Order>::from_value_and_type(value_and_type).map(SerdeValue::into_inner),
+            Ok(order)
+        );
+    }
+
+    #[test]
+    fn serde_value_in_result() {
+        let result: Result<SerdeValue<Item>, String> = Ok(SerdeValue(Item("sku".to_string(), 1)));
+        let value_and_type = result.clone().try_into_value_and_type().unwrap();
+        assert_eq!(
+            Result::<SerdeValue<Item>, String>::from_value_and_type(value_and_type),
+            Ok(result)
+        );
+
+        let result: Result<(), SerdeValue<Status>> = Err(SerdeValue(Status::Pending));
+        let value_and_type = result.clone().try_into_value_and_type().unwrap();
+        assert_eq!(
+            Result::<(), SerdeValue<Status>>::from_value_and_type(value_and_type),
+            Ok(result)
+        );
+    }
+}
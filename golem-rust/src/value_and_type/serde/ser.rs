@@ -0,0 +1,479 @@
+// Copyright 2024-2025 Golem Cloud
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use ::serde::ser::{
+    Serialize, SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant, SerializeTuple,
+    SerializeTupleStruct, SerializeTupleVariant, Serializer,
+};
+use golem_wasm_rpc::Value;
+
+use crate::value_and_type::serde::{SerdeError, SerdeType};
+
+/// Serde `Serializer` producing a dynamic `Value` together with the type inferred from it
+pub struct WitValueSerializer;
+
+type Serialized = (Value, SerdeType);
+
+impl Serializer for WitValueSerializer {
+    type Ok = Serialized;
+    type Error = SerdeError;
+    type SerializeSeq = SeqSerializer;
+    type SerializeTuple = TupleSerializer;
+    type SerializeTupleStruct = TupleSerializer;
+    type SerializeTupleVariant = VariantSerializer<TupleSerializer>;
+    type SerializeMap = MapSerializer;
+    type SerializeStruct = StructSerializer;
+    type SerializeStructVariant = VariantSerializer<StructSerializer>;
+
+    fn serialize_bool(self, v: bool) -> Result<Serialized, SerdeError> {
+        Ok((Value::Bool(v), SerdeType::Bool))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Serialized, SerdeError> {
+        Ok((Value::S8(v), SerdeType::S8))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Serialized, SerdeError> {
+        Ok((Value::S16(v), SerdeType::S16))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Serialized, SerdeError> {
+        Ok((Value::S32(v), SerdeType::S32))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Serialized, SerdeError> {
+        Ok((Value::S64(v), SerdeType::S64))
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Serialized, SerdeError> {
+        Ok((
+            Value::Tuple(vec![Value::S64((v >> 64) as i64), Value::U64(v as u64)]),
+            SerdeType::Tuple(vec![SerdeType::S64, SerdeType::U64]),
+        ))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Serialized, SerdeError> {
+        Ok((Value::U8(v), SerdeType::U8))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Serialized, SerdeError> {
+        Ok((Value::U16(v), SerdeType::U16))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Serialized, SerdeError> {
+        Ok((Value::U32(v), SerdeType::U32))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Serialized, SerdeError> {
+        Ok((Value::U64(v), SerdeType::U64))
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Serialized, SerdeError> {
+        Ok((
+            Value::Tuple(vec![Value::U64((v >> 64) as u64), Value::U64(v as u64)]),
+            SerdeType::Tuple(vec![SerdeType::U64, SerdeType::U64]),
+        ))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Serialized, SerdeError> {
+        Ok((Value::F32(v), SerdeType::F32))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Serialized, SerdeError> {
+        Ok((Value::F64(v), SerdeType::F64))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Serialized, SerdeError> {
+        Ok((Value::Char(v), SerdeType::Char))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Serialized, SerdeError> {
+        Ok((Value::String(v.to_string()), SerdeType::String))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Serialized, SerdeError> {
+        Ok((
+            Value::List(v.iter().copied().map(Value::U8).collect()),
+            SerdeType::List(Box::new(SerdeType::U8)),
+        ))
+    }
+
+    fn serialize_none(self) -> Result<Serialized, SerdeError> {
+        Ok((
+            Value::Option(None),
+            SerdeType::Option(Box::new(SerdeType::Unknown)),
+        ))
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Serialized, SerdeError> {
+        let (value, typ) = value.serialize(WitValueSerializer)?;
+        Ok((
+            Value::Option(Some(Box::new(value))),
+            SerdeType::Option(Box::new(typ)),
+        ))
+    }
+
+    fn serialize_unit(self) -> Result<Serialized, SerdeError> {
+        Ok((Value::Tuple(Vec::new()), SerdeType::unit()))
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Serialized, SerdeError> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Serialized, SerdeError> {
+        Ok((
+            Value::Variant {
+                case_idx: variant_index,
+                case_value: None,
+            },
+            variant_type(name, variant_index, variant, None),
+        ))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Serialized, SerdeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Serialized, SerdeError> {
+        let (value, typ) = value.serialize(WitValueSerializer)?;
+        Ok((
+            Value::Variant {
+                case_idx: variant_index,
+                case_value: Some(Box::new(value)),
+            },
+            variant_type(name, variant_index, variant, Some(typ)),
+        ))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SeqSerializer, SerdeError> {
+        Ok(SeqSerializer {
+            items: Vec::with_capacity(len.unwrap_or_default()),
+            item_type: None,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<TupleSerializer, SerdeError> {
+        Ok(TupleSerializer {
+            items: Vec::with_capacity(len),
+            types: Vec::with_capacity(len),
+        })
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<TupleSerializer, SerdeError> {
+        self.serialize_tuple(len)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<VariantSerializer<TupleSerializer>, SerdeError> {
+        Ok(VariantSerializer {
+            name,
+            variant_index,
+            variant,
+            payload: self.serialize_tuple(len)?,
+        })
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<MapSerializer, SerdeError> {
+        Ok(MapSerializer {
+            entries: Vec::with_capacity(len.unwrap_or_default()),
+            key: None,
+            key_type: None,
+            value_type: None,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<StructSerializer, SerdeError> {
+        Ok(StructSerializer {
+            name: Some(name),
+            values: Vec::with_capacity(len),
+            fields: Vec::with_capacity(len),
+        })
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<VariantSerializer<StructSerializer>, SerdeError> {
+        Ok(VariantSerializer {
+            name,
+            variant_index,
+            variant,
+            payload: StructSerializer {
+                name: None,
+                values: Vec::with_capacity(len),
+                fields: Vec::with_capacity(len),
+            },
+        })
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+/// The type of a variant of which only the case with the given index is known
+fn variant_type(
+    name: &'static str,
+    variant_index: u32,
+    variant: &'static str,
+    payload: Option<SerdeType>,
+) -> SerdeType {
+    let mut cases: Vec<(String, Option<SerdeType>)> = (0..variant_index)
+        .map(|idx| (format!("case-{idx}"), None))
+        .collect();
+    cases.push((variant.to_string(), payload));
+    SerdeType::Variant { name, cases }
+}
+
+pub struct SeqSerializer {
+    items: Vec<Value>,
+    item_type: Option<SerdeType>,
+}
+
+impl SerializeSeq for SeqSerializer {
+    type Ok = Serialized;
+    type Error = SerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        let (value, typ) = value.serialize(WitValueSerializer)?;
+        self.items.push(value);
+        self.item_type.get_or_insert(typ);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Serialized, SerdeError> {
+        Ok((
+            Value::List(self.items),
+            SerdeType::List(Box::new(self.item_type.unwrap_or(SerdeType::Unknown))),
+        ))
+    }
+}
+
+pub struct TupleSerializer {
+    items: Vec<Value>,
+    types: Vec<SerdeType>,
+}
+
+impl TupleSerializer {
+    fn add<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        let (value, typ) = value.serialize(WitValueSerializer)?;
+        self.items.push(value);
+        self.types.push(typ);
+        Ok(())
+    }
+
+    fn finish(self) -> Serialized {
+        (Value::Tuple(self.items), SerdeType::Tuple(self.types))
+    }
+}
+
+impl SerializeTuple for TupleSerializer {
+    type Ok = Serialized;
+    type Error = SerdeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.add(value)
+    }
+
+    fn end(self) -> Result<Serialized, SerdeError> {
+        Ok(self.finish())
+    }
+}
+
+impl SerializeTupleStruct for TupleSerializer {
+    type Ok = Serialized;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.add(value)
+    }
+
+    fn end(self) -> Result<Serialized, SerdeError> {
+        Ok(self.finish())
+    }
+}
+
+pub struct StructSerializer {
+    name: Option<&'static str>,
+    values: Vec<Value>,
+    fields: Vec<(&'static str, SerdeType)>,
+}
+
+impl StructSerializer {
+    fn finish(self) -> Serialized {
+        (
+            Value::Record(self.values),
+            SerdeType::Record {
+                name: self.name,
+                fields: self.fields,
+            },
+        )
+    }
+}
+
+impl SerializeStruct for StructSerializer {
+    type Ok = Serialized;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), SerdeError> {
+        let (value, typ) = value.serialize(WitValueSerializer)?;
+        self.values.push(value);
+        self.fields.push((key, typ));
+        Ok(())
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> Result<(), SerdeError> {
+        // Records are positional, so skipping a field would shift all the following ones
+        Err(SerdeError(format!("Skipping field {key} is not supported")))
+    }
+
+    fn end(self) -> Result<Serialized, SerdeError> {
+        Ok(self.finish())
+    }
+}
+
+pub struct VariantSerializer<Payload> {
+    name: &'static str,
+    variant_index: u32,
+    variant: &'static str,
+    payload: Payload,
+}
+
+impl<Payload> VariantSerializer<Payload> {
+    fn finish(self, payload: impl FnOnce(Payload) -> Serialized) -> Serialized {
+        let (payload, payload_type) = payload(self.payload);
+        (
+            Value::Variant {
+                case_idx: self.variant_index,
+                case_value: Some(Box::new(payload)),
+            },
+            variant_type(
+                self.name,
+                self.variant_index,
+                self.variant,
+                Some(payload_type),
+            ),
+        )
+    }
+}
+
+impl SerializeTupleVariant for VariantSerializer<TupleSerializer> {
+    type Ok = Serialized;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        self.payload.add(value)
+    }
+
+    fn end(self) -> Result<Serialized, SerdeError> {
+        Ok(self.finish(|payload| payload.finish()))
+    }
+}
+
+impl SerializeStructVariant for VariantSerializer<StructSerializer> {
+    type Ok = Serialized;
+    type Error = SerdeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), SerdeError> {
+        SerializeStruct::serialize_field(&mut self.payload, key, value)
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> Result<(), SerdeError> {
+        SerializeStruct::skip_field(&mut self.payload, key)
+    }
+
+    fn end(self) -> Result<Serialized, SerdeError> {
+        Ok(self.finish(|payload| payload.finish()))
+    }
+}
+
+pub struct MapSerializer {
+    entries: Vec<Value>,
+    key: Option<Value>,
+    key_type: Option<SerdeType>,
+    value_type: Option<SerdeType>,
+}
+
+impl SerializeMap for MapSerializer {
+    type Ok = Serialized;
+    type Error = SerdeError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), SerdeError> {
+        let (key, typ) = key.serialize(WitValueSerializer)?;
+        self.key = Some(key);
+        self.key_type.get_or_insert(typ);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeError> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| SerdeError("Map value serialized before its key".to_string()))?;
+        let (value, typ) = value.serialize(WitValueSerializer)?;
+        self.entries.push(Value::Tuple(vec![key, value]));
+        self.value_type.get_or_insert(typ);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Serialized, SerdeError> {
+        Ok((
+            Value::List(self.entries),
+            SerdeType::List(Box::new(SerdeType::Tuple(vec![
+                self.key_type.unwrap_or(SerdeType::Unknown),
+                self.value_type.unwrap_or(SerdeType::Unknown),
+            ]))),
+        ))
+    }
+}
@@ -0,0 +1,563 @@
+// Copyright 2024-2025 Golem Cloud
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Determines the shape of a deserializable type by deserializing sample values from a
+// deserializer which records the requested shapes. Enums are deserialized once per case, so the
+// type is deserialized repeatedly until every case of every reachable enum has been traced.
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+
+use ::serde::de::value::U32Deserializer;
+use ::serde::de::{
+    DeserializeOwned, DeserializeSeed, Deserializer, EnumAccess, MapAccess, SeqAccess,
+    VariantAccess, Visitor,
+};
+
+use crate::value_and_type::serde::{SerdeError, SerdeType};
+
+const MAX_DEPTH: usize = 64;
+
+pub(crate) fn trace<T: DeserializeOwned>() -> Result<SerdeType, SerdeError> {
+    let tracer = Tracer::default();
+    let mut runs_without_progress = 0;
+    loop {
+        let untraced_before = tracer.untraced_cases();
+        let mut typ = SerdeType::Unknown;
+        T::deserialize(TracingDeserializer {
+            tracer: &tracer,
+            out: &mut typ,
+        })?;
+        tracer.run.set(tracer.run.get() + 1);
+
+        let untraced_after = tracer.untraced_cases();
+        if untraced_after == 0 {
+            return tracer.resolve(typ, &mut Vec::new());
+        } else if untraced_after < untraced_before {
+            runs_without_progress = 0;
+        } else {
+            runs_without_progress += 1;
+            if runs_without_progress > tracer.total_cases() {
+                return Err(SerdeError(
+                    "Could not reach all enum cases while tracing".to_string(),
+                ));
+            }
+        }
+    }
+}
+
+struct TracedEnum {
+    variants: &'static [&'static str],
+    /// `None` until traced, then the payload type of the case
+    cases: Vec<Option<Option<SerdeType>>>,
+    in_progress: Vec<bool>,
+}
+
+#[derive(Default)]
+struct Tracer {
+    enums: RefCell<HashMap<&'static str, TracedEnum>>,
+    depth: Cell<usize>,
+    run: Cell<usize>,
+}
+
+impl Tracer {
+    fn untraced_cases(&self) -> usize {
+        self.enums
+            .borrow()
+            .values()
+            .map(|traced| traced.cases.iter().filter(|case| case.is_none()).count())
+            .sum()
+    }
+
+    fn total_cases(&self) -> usize {
+        self.enums
+            .borrow()
+            .values()
+            .map(|traced| traced.cases.len())
+            .sum()
+    }
+
+    /// Chooses the case to deserialize: an untraced one if possible, otherwise a traced one,
+    /// rotating between runs to reach enums nested in other cases.
+    fn choose_case(
+        &self,
+        name: &'static str,
+        variants: &'static [&'static str],
+    ) -> Result<u32, SerdeError> {
+        let mut enums = self.enums.borrow_mut();
+        let traced = enums.entry(name).or_insert_with(|| TracedEnum {
+            variants,
+            cases: vec![None; variants.len()],
+            in_progress: vec![false; variants.len()],
+        });
+        if variants.is_empty() {
+            return Err(SerdeError(format!("Enum {name} has no cases")));
+        }
+
+        let untraced = (0..variants.len())
+            .find(|idx| traced.cases[*idx].is_none() && !traced.in_progress[*idx]);
+        let chosen = match untraced {
+            Some(idx) => idx,
+            None => {
+                let candidates: Vec<usize> = (0..variants.len())
+                    .filter(|idx| traced.cases[*idx].is_some())
+                    .collect();
+                if candidates.is_empty() {
+                    return Err(SerdeError(format!(
+                        "Recursive enum {name} cannot be traced"
+                    )));
+                }
+                candidates[self.run.get() % candidates.len()]
+            }
+        };
+        traced.in_progress[chosen] = true;
+        Ok(chosen as u32)
+    }
+
+    fn case_traced(&self, name: &'static str, case_idx: u32, payload: Option<SerdeType>) {
+        let mut enums = self.enums.borrow_mut();
+        if let Some(traced) = enums.get_mut(name) {
+            let case_idx = case_idx as usize;
+            traced.in_progress[case_idx] = false;
+            if traced.cases[case_idx].is_none() {
+                traced.cases[case_idx] = Some(payload);
+            }
+        }
+    }
+
+    fn enter(&self) -> Result<(), SerdeError> {
+        let depth = self.depth.get() + 1;
+        if depth > MAX_DEPTH {
+            Err(SerdeError(
+                "Type is too deep or recursive to be traced".to_string(),
+            ))
+        } else {
+            self.depth.set(depth);
+            Ok(())
+        }
+    }
+
+    fn leave(&self) {
+        self.depth.set(self.depth.get() - 1);
+    }
+
+    fn trace_seed<'de, S: DeserializeSeed<'de>>(
+        &self,
+        seed: S,
+    ) -> Result<(S::Value, SerdeType), SerdeError> {
+        self.enter()?;
+        let mut typ = SerdeType::Unknown;
+        let value = seed.deserialize(TracingDeserializer {
+            tracer: self,
+            out: &mut typ,
+        })?;
+        self.leave();
+        Ok((value, typ))
+    }
+
+    /// Replaces the references to enums with their traced cases
+    fn resolve(
+        &self,
+        typ: SerdeType,
+        resolving: &mut Vec<&'static str>,
+    ) -> Result<SerdeType, SerdeError> {
+        Ok(match typ {
+            SerdeType::Option(inner) => {
+                SerdeType::Option(Box::new(self.resolve(*inner, resolving)?))
+            }
+            SerdeType::List(inner) => SerdeType::List(Box::new(self.resolve(*inner, resolving)?)),
+            SerdeType::Tuple(items) => SerdeType::Tuple(
+                items
+                    .into_iter()
+                    .map(|item| self.resolve(item, resolving))
+                    .collect::<Result<_, _>>()?,
+            ),
+            SerdeType::Record { name, fields } => SerdeType::Record {
+                name,
+                fields: fields
+                    .into_iter()
+                    .map(|(field_name, field_type)| {
+                        Ok((field_name, self.resolve(field_type, resolving)?))
+                    })
+                    .collect::<Result<_, SerdeError>>()?,
+            },
+            SerdeType::Variant { name, .. } => {
+                if resolving.contains(&name) {
+                    return Err(SerdeError(format!(
+                        "Recursive enum {name} cannot be represented"
+                    )));
+                }
+                let (variants, cases) = {
+                    let enums = self.enums.borrow();
+                    let traced = &enums[name];
+                    (traced.variants, traced.cases.clone())
+                };
+                resolving.push(name);
+                let cases = variants
+                    .iter()
+                    .zip(cases)
+                    .map(|(case_name, payload)| {
+                        let payload = payload
+                            .flatten()
+                            .map(|payload| self.resolve(payload, resolving))
+                            .transpose()?;
+                        Ok((case_name.to_string(), payload))
+                    })
+                    .collect::<Result<_, SerdeError>>()?;
+                resolving.pop();
+                SerdeType::Variant { name, cases }
+            }
+            other => other,
+        })
+    }
+}
+
+struct TracingDeserializer<'t> {
+    tracer: &'t Tracer,
+    out: &'t mut SerdeType,
+}
+
+macro_rules! trace_primitive {
+    ($method:ident, $typ:expr, $visit:ident, $sample:expr) => {
+        fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+            *self.out = $typ;
+            visitor.$visit($sample)
+        }
+    };
+}
+
+impl<'de> Deserializer<'de> for TracingDeserializer<'_> {
+    type Error = SerdeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, SerdeError> {
+        Err(SerdeError(
+            "Types deserialized without a type hint cannot be traced".to_string(),
+        ))
+    }
+
+    trace_primitive!(deserialize_bool, SerdeType::Bool, visit_bool, false);
+    trace_primitive!(deserialize_i8, SerdeType::S8, visit_i8, 0);
+    trace_primitive!(deserialize_i16, SerdeType::S16, visit_i16, 0);
+    trace_primitive!(deserialize_i32, SerdeType::S32, visit_i32, 0);
+    trace_primitive!(deserialize_i64, SerdeType::S64, visit_i64, 0);
+    trace_primitive!(deserialize_u8, SerdeType::U8, visit_u8, 0);
+    trace_primitive!(deserialize_u16, SerdeType::U16, visit_u16, 0);
+    trace_primitive!(deserialize_u32, SerdeType::U32, visit_u32, 0);
+    trace_primitive!(deserialize_u64, SerdeType::U64, visit_u64, 0);
+    trace_primitive!(deserialize_f32, SerdeType::F32, visit_f32, 0.0);
+    trace_primitive!(deserialize_f64, SerdeType::F64, visit_f64, 0.0);
+    trace_primitive!(deserialize_char, SerdeType::Char, visit_char, 'a');
+    trace_primitive!(deserialize_str, SerdeType::String, visit_str, "");
+    trace_primitive!(
+        deserialize_string,
+        SerdeType::String,
+        visit_string,
+        String::new()
+    );
+    trace_primitive!(
+        deserialize_i128,
+        SerdeType::Tuple(vec![SerdeType::S64, SerdeType::U64]),
+        visit_i128,
+        0
+    );
+    trace_primitive!(
+        deserialize_u128,
+        SerdeType::Tuple(vec![SerdeType::U64, SerdeType::U64]),
+        visit_u128,
+        0
+    );
+    trace_primitive!(
+        deserialize_bytes,
+        SerdeType::List(Box::new(SerdeType::U8)),
+        visit_bytes,
+        &[]
+    );
+    trace_primitive!(
+        deserialize_byte_buf,
+        SerdeType::List(Box::new(SerdeType::U8)),
+        visit_byte_buf,
+        Vec::new()
+    );
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        *self.out = SerdeType::unit();
+        visitor.visit_unit()
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        self.tracer.enter()?;
+        let mut inner = SerdeType::Unknown;
+        let value = visitor.visit_some(TracingDeserializer {
+            tracer: self.tracer,
+            out: &mut inner,
+        })?;
+        self.tracer.leave();
+        *self.out = SerdeType::Option(Box::new(inner));
+        Ok(value)
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        let mut types = Vec::new();
+        let value = visitor.visit_seq(Elements {
+            tracer: self.tracer,
+            remaining: 1,
+            types: &mut types,
+        })?;
+        let element = types.pop().unwrap_or(SerdeType::Unknown);
+        *self.out = SerdeType::List(Box::new(element));
+        Ok(value)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        let mut types = Vec::new();
+        let value = visitor.visit_seq(Elements {
+            tracer: self.tracer,
+            remaining: len,
+            types: &mut types,
+        })?;
+        *self.out = SerdeType::Tuple(types);
+        Ok(value)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        let mut key = SerdeType::Unknown;
+        let mut value_type = SerdeType::Unknown;
+        let value = visitor.visit_map(Entry {
+            tracer: self.tracer,
+            remaining: true,
+            key: &mut key,
+            value: &mut value_type,
+        })?;
+        *self.out = SerdeType::List(Box::new(SerdeType::Tuple(vec![key, value_type])));
+        Ok(value)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        let mut types = Vec::new();
+        let value = visitor.visit_seq(Elements {
+            tracer: self.tracer,
+            remaining: fields.len(),
+            types: &mut types,
+        })?;
+        *self.out = SerdeType::Record {
+            name: Some(name),
+            fields: fields.iter().copied().zip(types).collect(),
+        };
+        Ok(value)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        let case_idx = self.tracer.choose_case(name, variants)?;
+        let value = visitor.visit_enum(Case {
+            tracer: self.tracer,
+            name,
+            case_idx,
+        })?;
+        *self.out = SerdeType::Variant {
+            name,
+            cases: Vec::new(),
+        };
+        Ok(value)
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeError> {
+        self.deserialize_any(visitor)
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+struct Elements<'t> {
+    tracer: &'t Tracer,
+    remaining: usize,
+    types: &'t mut Vec<SerdeType>,
+}
+
+impl<'de> SeqAccess<'de> for Elements<'_> {
+    type Error = SerdeError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, SerdeError> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        self.remaining -= 1;
+        let (value, typ) = self.tracer.trace_seed(seed)?;
+        self.types.push(typ);
+        Ok(Some(value))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.remaining)
+    }
+}
+
+struct Entry<'t> {
+    tracer: &'t Tracer,
+    remaining: bool,
+    key: &'t mut SerdeType,
+    value: &'t mut SerdeType,
+}
+
+impl<'de> MapAccess<'de> for Entry<'_> {
+    type Error = SerdeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, SerdeError> {
+        if !self.remaining {
+            return Ok(None);
+        }
+        self.remaining = false;
+        let (key, typ) = self.tracer.trace_seed(seed)?;
+        *self.key = typ;
+        Ok(Some(key))
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, SerdeError> {
+        let (value, typ) = self.tracer.trace_seed(seed)?;
+        *self.value = typ;
+        Ok(value)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.remaining as usize)
+    }
+}
+
+struct Case<'t> {
+    tracer: &'t Tracer,
+    name: &'static str,
+    case_idx: u32,
+}
+
+impl<'de, 't> EnumAccess<'de> for Case<'t> {
+    type Error = SerdeError;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self), SerdeError> {
+        let variant = seed.deserialize(U32Deserializer::<SerdeError>::new(self.case_idx))?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de> VariantAccess<'de> for Case<'_> {
+    type Error = SerdeError;
+
+    fn unit_variant(self) -> Result<(), SerdeError> {
+        self.tracer.case_traced(self.name, self.case_idx, None);
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> Result<T::Value, SerdeError> {
+        let (value, typ) = self.tracer.trace_seed(seed)?;
+        self.tracer.case_traced(self.name, self.case_idx, Some(typ));
+        Ok(value)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        let mut typ = SerdeType::Unknown;
+        self.tracer.enter()?;
+        let value = TracingDeserializer {
+            tracer: self.tracer,
+            out: &mut typ,
+        }
+        .deserialize_tuple(len, visitor)?;
+        self.tracer.leave();
+        self.tracer.case_traced(self.name, self.case_idx, Some(typ));
+        Ok(value)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, SerdeError> {
+        let mut types = Vec::new();
+        self.tracer.enter()?;
+        let value = visitor.visit_seq(Elements {
+            tracer: self.tracer,
+            remaining: fields.len(),
+            types: &mut types,
+        })?;
+        self.tracer.leave();
+        let typ = SerdeType::Record {
+            name: None,
+            fields: fields.iter().copied().zip(types).collect(),
+        };
+        self.tracer.case_traced(self.name, self.case_idx, Some(typ));
+        Ok(value)
+    }
+}
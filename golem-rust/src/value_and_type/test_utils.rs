@@ -0,0 +1,35 @@
+// Copyright 2024-2025 Golem Cloud
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Fixtures shared by the tests of the value_and_type modules
+
+use std::fmt::Debug;
+
+use crate::value_and_type::{FromValueAndType, IntoValueAndType, ResourceType};
+
+pub(crate) struct Counter;
+
+impl ResourceType for Counter {
+    const RESOURCE_ID: u64 = 7;
+    const NAME: Option<&'static str> = Some("counter");
+    const OWNER: Option<&'static str> = Some("counters:api/types");
+}
+
+/// Converts the value to a `ValueAndType` and back, expecting the original value
+pub(crate) fn roundtrip<T: IntoValueAndType + FromValueAndType + Clone + PartialEq + Debug>(
+    value: T,
+) {
+    let result = T::from_value_and_type(value.clone().into_value_and_type());
+    assert_eq!(result, Ok(value));
+}
@@ -14,6 +14,8 @@
 
 // Builder for WitType, to be eventually upstreamed to `golem_wasm_rpc`
 
+use std::collections::HashMap;
+
 use golem_wasm_rpc::golem_rpc_0_2_x::types::{NamedWitTypeNode, ResourceId};
 use golem_wasm_rpc::{NodeIndex, ResourceMode, WitType, WitTypeNode};
 
@@ -61,16 +63,57 @@ pub trait TypeNodeBuilder: Sized {
         resource_mode: ResourceMode,
     ) -> Self::Result;
 
+    /// Refers to a node which was already added to the type
+    fn reference(self, idx: NodeIndex) -> Self::Result;
+
     fn finish(self) -> Self::Result;
+
+    /// Adds the named type built by `build` only the first time a type with the same key is
+    /// visited, and refers to its node afterwards.
+    ///
+    /// The key identifies the type, and has to differ for types with different nodes even if
+    /// they have the same name and owner, so derived types use the `std::any::type_name` of the
+    /// Rust type. The name and owner are only set on the node by `build`.
+    ///
+    /// The type is registered before `build` runs, so recursive types refer back to their own
+    /// node instead of being expanded forever. `build` always gets a `NamedTypeBuilder`, which
+    /// keeps the builder types of recursive types finite.
+    ///
+    /// `NodeBuilder` has no counterpart of this yet, so values of recursive types cannot be
+    /// built, as its builder types grow with the depth of the value.
+    fn interned(
+        mut self,
+        key: &str,
+        build: impl FnOnce(NamedTypeBuilder<'_>) -> NodeIndex,
+    ) -> Self::Result {
+        let parent = self.parent_builder();
+        let idx = match parent.named_nodes.get(key) {
+            Some(idx) => *idx,
+            None => {
+                parent
+                    .named_nodes
+                    .insert(key.to_string(), parent.nodes.len() as NodeIndex);
+                let idx = build(NamedTypeBuilder::new(parent));
+                parent.named_nodes.insert(key.to_string(), idx);
+                idx
+            }
+        };
+        self.reference(idx)
+    }
 }
 
 pub struct WitTypeBuilder {
     nodes: Vec<NamedWitTypeNode>,
+    /// Nodes of the interned named types by their key
+    named_nodes: HashMap<String, NodeIndex>,
 }
 
 impl WitTypeBuilder {
     pub(crate) fn new() -> Self {
-        WitTypeBuilder { nodes: Vec::new() }
+        WitTypeBuilder {
+            nodes: Vec::new(),
+            named_nodes: HashMap::new(),
+        }
     }
 
     fn add(&mut self, node: NamedWitTypeNode) -> NodeIndex {
@@ -476,7 +519,7 @@ impl TypeNodeBuilder for WitTypeBuilder {
         owner: Option<String>,
         values: &[&str],
     ) -> Self::Result {
-        let _ = self.add_enum(name, owner, values.iter().map(|s| s.to_string()).collect());
+        let _ = self.add_flags(name, owner, values.iter().map(|s| s.to_string()).collect());
         self.build()
     }
 
@@ -532,11 +575,177 @@ impl TypeNodeBuilder for WitTypeBuilder {
         self.build()
     }
 
+    fn reference(self, idx: NodeIndex) -> Self::Result {
+        assert_eq!(idx, 0, "the root of a type cannot refer to another node");
+        self.build()
+    }
+
     fn finish(self) -> Self::Result {
         self.build()
     }
 }
 
+/// Builder of an interned named type, see `TypeNodeBuilder::interned`. Its result is the index
+/// of the built node.
+pub struct NamedTypeBuilder<'a> {
+    builder: &'a mut WitTypeBuilder,
+    /// Index of the first added node, which is the node of the type
+    target_idx: NodeIndex,
+}
+
+impl<'a> NamedTypeBuilder<'a> {
+    fn new(builder: &'a mut WitTypeBuilder) -> Self {
+        let target_idx = builder.nodes.len() as NodeIndex;
+        Self {
+            builder,
+            target_idx,
+        }
+    }
+}
+
+impl TypeNodeBuilder for NamedTypeBuilder<'_> {
+    type Result = NodeIndex;
+
+    fn parent_builder(&mut self) -> &mut WitTypeBuilder {
+        self.builder
+    }
+
+    fn u8(self) -> Self::Result {
+        self.builder.add_u8()
+    }
+
+    fn u16(self) -> Self::Result {
+        self.builder.add_u16()
+    }
+
+    fn u32(self) -> Self::Result {
+        self.builder.add_u32()
+    }
+
+    fn u64(self) -> Self::Result {
+        self.builder.add_u64()
+    }
+
+    fn s8(self) -> Self::Result {
+        self.builder.add_s8()
+    }
+
+    fn s16(self) -> Self::Result {
+        self.builder.add_s16()
+    }
+
+    fn s32(self) -> Self::Result {
+        self.builder.add_s32()
+    }
+
+    fn s64(self) -> Self::Result {
+        self.builder.add_s64()
+    }
+
+    fn f32(self) -> Self::Result {
+        self.builder.add_f32()
+    }
+
+    fn f64(self) -> Self::Result {
+        self.builder.add_f64()
+    }
+
+    fn string(self) -> Self::Result {
+        self.builder.add_string()
+    }
+
+    fn bool(self) -> Self::Result {
+        self.builder.add_bool()
+    }
+
+    fn char(self) -> Self::Result {
+        self.builder.add_char()
+    }
+
+    fn option(self, name: Option<String>, owner: Option<String>) -> WitTypeContainerBuilder<Self> {
+        let option_idx = self.builder.add_option(name, owner);
+        WitTypeContainerBuilder {
+            builder: self,
+            target_idx: option_idx,
+        }
+    }
+
+    fn list(self, name: Option<String>, owner: Option<String>) -> WitTypeContainerBuilder<Self> {
+        let list_idx = self.builder.add_list(name, owner);
+        WitTypeContainerBuilder {
+            builder: self,
+            target_idx: list_idx,
+        }
+    }
+
+    fn r#enum(self, name: Option<String>, owner: Option<String>, values: &[&str]) -> Self::Result {
+        self.builder
+            .add_enum(name, owner, values.iter().map(|s| s.to_string()).collect())
+    }
+
+    fn flags(self, name: Option<String>, owner: Option<String>, values: &[&str]) -> Self::Result {
+        self.builder
+            .add_flags(name, owner, values.iter().map(|s| s.to_string()).collect())
+    }
+
+    fn record(self, name: Option<String>, owner: Option<String>) -> WitTypeRecordBuilder<Self> {
+        let record_idx = self.builder.add_record(name, owner);
+        WitTypeRecordBuilder {
+            builder: self,
+            target_idx: record_idx,
+            fields: Vec::new(),
+        }
+    }
+
+    fn tuple(self, name: Option<String>, owner: Option<String>) -> WitTypeTupleBuilder<Self> {
+        let tuple_idx = self.builder.add_tuple(name, owner);
+        WitTypeTupleBuilder {
+            builder: self,
+            target_idx: tuple_idx,
+            fields: Vec::new(),
+        }
+    }
+
+    fn variant(self, name: Option<String>, owner: Option<String>) -> WitTypeVariantBuilder<Self> {
+        let variant_idx = self.builder.add_variant(name, owner);
+        WitTypeVariantBuilder {
+            builder: self,
+            target_idx: variant_idx,
+            cases: Vec::new(),
+        }
+    }
+
+    fn result(self, name: Option<String>, owner: Option<String>) -> WitTypeResultBuilder<Self> {
+        let result_idx = self.builder.add_result(name, owner);
+        WitTypeResultBuilder {
+            builder: self,
+            target_idx: result_idx,
+            ok: None,
+            err: None,
+        }
+    }
+
+    fn handle(
+        self,
+        name: Option<String>,
+        owner: Option<String>,
+        resource_id: ResourceId,
+        resource_mode: ResourceMode,
+    ) -> Self::Result {
+        self.builder
+            .add_handle(name, owner, resource_id, resource_mode)
+    }
+
+    fn reference(self, idx: NodeIndex) -> Self::Result {
+        idx
+    }
+
+    /// Called by the compound type builders once the type is complete
+    fn finish(self) -> Self::Result {
+        self.target_idx
+    }
+}
+
 pub struct WitTypeContainerBuilder<ParentBuilder: TypeNodeBuilder> {
     builder: ParentBuilder,
     target_idx: NodeIndex,
@@ -786,6 +995,13 @@ impl<ParentBuilder: TypeNodeBuilder> TypeNodeBuilder for WitTypeContainerBuilder
         self.builder
     }
 
+    fn reference(mut self, idx: NodeIndex) -> Self::Result {
+        self.builder
+            .parent_builder()
+            .finish_container(self.target_idx, idx);
+        self.builder
+    }
+
     fn finish(self) -> Self::Result {
         self.builder
     }
@@ -1262,6 +1478,10 @@ impl<B: InnerTypeNodeBuilder> TypeNodeBuilder for B {
         self.finish(child_index)
     }
 
+    fn reference(self, idx: NodeIndex) -> Self::Result {
+        self.finish(idx)
+    }
+
     fn finish(self) -> Self::Result {
         self.into_parent()
     }
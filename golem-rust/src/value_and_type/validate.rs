@@ -0,0 +1,377 @@
+// Copyright 2024-2025 Golem Cloud
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Validation of `WitValue`s against their `WitType`s.
+//!
+//! A value conforms to its type if every node is of the kind of the corresponding type node,
+//! records and tuples have exactly the fields and elements of their types, variant cases and
+//! enum values exist, flags have one entry per flag, and case values are present exactly for the
+//! cases with a type. Every value node has to be referenced once at most, so malformed values
+//! with shared or cyclic nodes are reported instead of being walked forever.
+
+use std::collections::HashSet;
+
+use golem_wasm_rpc::golem_rpc_0_2_x::types::ValueAndType;
+use golem_wasm_rpc::{NodeIndex, WitNode, WitType, WitTypeNode, WitValue};
+
+use crate::value_and_type::{PathSegment, ValueError, ValueErrorKind, ValueKind};
+
+/// All the parts of the value not conforming to the type, empty if the value is valid
+pub fn check(value: &WitValue, typ: &WitType) -> Vec<ValueError> {
+    let mut validator = Validator {
+        value,
+        typ,
+        visited: HashSet::new(),
+        path: Vec::new(),
+        errors: Vec::new(),
+    };
+    validator.validate(0, 0);
+    validator.errors
+}
+
+/// Whether the value conforms to the type
+pub fn is_valid(value: &WitValue, typ: &WitType) -> bool {
+    check(value, typ).is_empty()
+}
+
+/// Fails with the first part of the value not conforming to its type
+pub fn validate(value_and_type: &ValueAndType) -> Result<(), ValueError> {
+    match check(&value_and_type.value, &value_and_type.typ)
+        .into_iter()
+        .next()
+    {
+        Some(err) => Err(err.with_expected_type(value_and_type.typ.clone())),
+        None => Ok(()),
+    }
+}
+
+struct Validator<'v> {
+    value: &'v WitValue,
+    typ: &'v WitType,
+    /// Value nodes already validated
+    visited: HashSet<NodeIndex>,
+    path: Vec<PathSegment>,
+    errors: Vec<ValueError>,
+}
+
+impl Validator<'_> {
+    fn validate(&mut self, value_idx: NodeIndex, type_idx: NodeIndex) {
+        let Some(type_node) = self.typ.nodes.get(type_idx as usize) else {
+            return self.report(ValueErrorKind::Custom(format!(
+                "type node {type_idx} does not exist"
+            )));
+        };
+        let Some(node) = self.value.nodes.get(value_idx as usize) else {
+            return self.report(ValueErrorKind::Custom(format!(
+                "value node {value_idx} does not exist"
+            )));
+        };
+        if !self.visited.insert(value_idx) {
+            return self.report(ValueErrorKind::Custom(format!(
+                "value node {value_idx} is referenced more than once"
+            )));
+        }
+
+        let expected = ValueKind::of_type(&type_node.type_);
+        let actual = ValueKind::of_node(node);
+        if expected != actual {
+            return self.report(ValueErrorKind::UnexpectedKind {
+                expected,
+                actual: Some(actual),
+            });
+        }
+
+        match (node, &type_node.type_) {
+            (WitNode::RecordValue(fields), WitTypeNode::RecordType(field_types)) => {
+                for (idx, (name, field_type)) in field_types.iter().enumerate() {
+                    let segment = PathSegment::Field(name.clone());
+                    match fields.get(idx) {
+                        Some(field) => self.validate_at(segment, *field, *field_type),
+                        None => self.report_at(segment, ValueErrorKind::Missing),
+                    }
+                }
+                if fields.len() > field_types.len() {
+                    self.report(ValueErrorKind::Custom(format!(
+                        "expected {} fields, found {}",
+                        field_types.len(),
+                        fields.len()
+                    )));
+                }
+            }
+            (WitNode::TupleValue(items), WitTypeNode::TupleType(item_types)) => {
+                for (idx, item_type) in item_types.iter().enumerate() {
+                    let segment = PathSegment::Index(idx);
+                    match items.get(idx) {
+                        Some(item) => self.validate_at(segment, *item, *item_type),
+                        None => self.report_at(segment, ValueErrorKind::Missing),
+                    }
+                }
+                if items.len() > item_types.len() {
+                    self.report(ValueErrorKind::Custom(format!(
+                        "expected {} elements, found {}",
+                        item_types.len(),
+                        items.len()
+                    )));
+                }
+            }
+            (WitNode::VariantValue((case_idx, value)), WitTypeNode::VariantType(cases)) => {
+                match cases.get(*case_idx as usize) {
+                    Some((case, case_type)) => {
+                        self.validate_case(case, *value, *case_type);
+                    }
+                    None => self.report(ValueErrorKind::InvalidCase(*case_idx)),
+                }
+            }
+            (WitNode::EnumValue(case_idx), WitTypeNode::EnumType(cases))
+                if *case_idx as usize >= cases.len() =>
+            {
+                self.report(ValueErrorKind::InvalidCase(*case_idx));
+            }
+            (WitNode::FlagsValue(flags), WitTypeNode::FlagsType(names))
+                if flags.len() != names.len() =>
+            {
+                self.report(ValueErrorKind::Custom(format!(
+                    "expected {} flags, found {}",
+                    names.len(),
+                    flags.len()
+                )));
+            }
+            (WitNode::ListValue(items), WitTypeNode::ListType(item_type)) => {
+                for (idx, item) in items.iter().enumerate() {
+                    self.validate_at(PathSegment::Index(idx), *item, *item_type);
+                }
+            }
+            (WitNode::OptionValue(Some(value)), WitTypeNode::OptionType(inner_type)) => {
+                self.validate_at(PathSegment::Case("some".to_string()), *value, *inner_type);
+            }
+            (WitNode::ResultValue(Ok(value)), WitTypeNode::ResultType((ok_type, _))) => {
+                self.validate_case("ok", *value, *ok_type);
+            }
+            (WitNode::ResultValue(Err(value)), WitTypeNode::ResultType((_, err_type))) => {
+                self.validate_case("err", *value, *err_type);
+            }
+            _ => {}
+        }
+    }
+
+    fn validate_at(&mut self, segment: PathSegment, value_idx: NodeIndex, type_idx: NodeIndex) {
+        self.path.push(segment);
+        self.validate(value_idx, type_idx);
+        self.path.pop();
+    }
+
+    /// Validates the value of a variant or result case, which has to be present exactly if the
+    /// case has a type
+    fn validate_case(
+        &mut self,
+        case: &str,
+        value: Option<NodeIndex>,
+        case_type: Option<NodeIndex>,
+    ) {
+        let segment = PathSegment::Case(case.to_string());
+        match (value, case_type) {
+            (Some(value), Some(case_type)) => self.validate_at(segment, value, case_type),
+            (None, None) => {}
+            (None, Some(_)) => self.report_at(segment, ValueErrorKind::Missing),
+            (Some(_), None) => self.report_at(
+                segment,
+                ValueErrorKind::Custom("unexpected value of a case without one".to_string()),
+            ),
+        }
+    }
+
+    fn report_at(&mut self, segment: PathSegment, kind: ValueErrorKind) {
+        self.path.push(segment);
+        self.report(kind);
+        self.path.pop();
+    }
+
+    fn report(&mut self, kind: ValueErrorKind) {
+        let err =
+            self.path
+                .iter()
+                .rev()
+                .fold(ValueError::new(kind), |err, segment| match segment {
+                    PathSegment::Field(name) => err.at_field(name.clone()),
+                    PathSegment::Index(idx) => err.at_index(*idx),
+                    PathSegment::Case(name) => err.at_case(name.clone()),
+                });
+        self.errors.push(err);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use golem_wasm_rpc::{Value, WitNode, WitValue};
+
+    use crate::value_and_type::validate::{check, is_valid, validate};
+    use crate::value_and_type::{IntoValue, IntoValueAndType, ValueErrorKind, ValueKind};
+
+    #[test]
+    fn valid_values() {
+        let value = (
+            vec![Some(1u32), None],
+            Ok::<_, String>("ok".to_string()),
+            [1.5f64; 2],
+        )
+            .into_value_and_type();
+        assert_eq!(validate(&value), Ok(()));
+    }
+
+    #[test]
+    fn mismatches() {
+        let typ = <(u8, Vec<String>, Result<(), u32>, Option<(bool, char)>)>::get_type();
+        let value: WitValue = Value::Tuple(vec![
+            Value::U16(1),
+            Value::List(vec![Value::String("a".to_string()), Value::Bool(true)]),
+            Value::Result(Ok(Some(Box::new(Value::U32(1))))),
+            Value::Option(Some(Box::new(Value::Tuple(vec![Value::Bool(true)])))),
+        ])
+        .into();
+
+        let errors = check(&value, &typ)
+            .iter()
+            .map(|err| err.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            errors,
+            vec![
+                "at [0]: expected u8, found u16",
+                "at [1][1]: expected string, found bool",
+                "at [2]::ok: unexpected value of a case without one",
+                "at [3]::some[1]: missing value",
+            ]
+        );
+    }
+
+    #[test]
+    fn malformed_values() {
+        let typ = Vec::<Option<u8>>::get_type();
+        let cyclic = WitValue {
+            nodes: vec![WitNode::ListValue(vec![1]), WitNode::OptionValue(Some(0))],
+        };
+        assert_eq!(
+            check(&cyclic, &typ)[0].to_string(),
+            "at [0]::some: value node 0 is referenced more than once"
+        );
+
+        let dangling = WitValue {
+            nodes: vec![WitNode::ListValue(vec![3])],
+        };
+        assert!(!is_valid(&dangling, &typ));
+        assert!(!is_valid(&WitValue { nodes: vec![] }, &typ));
+    }
+
+    #[test]
+    fn cases_and_flags() {
+        let typ = <Result<u8, ()>>::get_type();
+        let value = WitValue {
+            nodes: vec![WitNode::ResultValue(Ok(None))],
+        };
+        let errors = check(&value, &typ);
+        assert_eq!(errors[0].kind(), &ValueErrorKind::Missing);
+        assert_eq!(errors[0].to_string(), "at ::ok: missing value");
+
+        let typ = u8::get_type();
+        let value = WitValue {
+            nodes: vec![WitNode::EnumValue(2)],
+        };
+        assert_eq!(
+            check(&value, &typ)[0].kind(),
+            &ValueErrorKind::UnexpectedKind {
+                expected: ValueKind::U8,
+                actual: Some(ValueKind::Enum)
+            }
+        );
+    }
+}
+
+#[cfg(all(test, feature = "macro"))]
+mod derive_tests {
+    use golem_wasm_rpc::golem_rpc_0_2_x::types::ValueAndType;
+    use golem_wasm_rpc::{Value, WitNode, WitValue};
+
+    use crate::value_and_type::validate::{check, validate};
+    use crate::value_and_type::{IntoValue as _, IntoValueAndType, ValueErrorKind};
+    use crate::{FromValueAndType, IntoValue};
+
+    #[derive(IntoValue, FromValueAndType)]
+    #[wit(flags)]
+    struct Permissions {
+        read: bool,
+        write: bool,
+    }
+
+    #[derive(IntoValue, FromValueAndType)]
+    enum Status {
+        Active,
+        Suspended { reason: String },
+    }
+
+    #[derive(IntoValue, FromValueAndType)]
+    struct User {
+        name: String,
+        permissions: Permissions,
+        status: Status,
+    }
+
+    #[test]
+    fn derived_types() {
+        let user = User {
+            name: "alice".to_string(),
+            permissions: Permissions {
+                read: true,
+                write: false,
+            },
+            status: Status::Suspended {
+                reason: "spam".to_string(),
+            },
+        };
+        assert_eq!(validate(&user.into_value_and_type()), Ok(()));
+
+        let typ = User::get_type();
+        let value: WitValue = Value::Record(vec![
+            Value::String("bob".to_string()),
+            Value::Flags(vec![true]),
+            Value::Variant {
+                case_idx: 2,
+                case_value: None,
+            },
+        ])
+        .into();
+        let errors = check(&value, &typ);
+        assert_eq!(
+            errors.iter().map(|err| err.to_string()).collect::<Vec<_>>(),
+            vec![
+                "at permissions: expected 2 flags, found 1",
+                "at status: invalid case index 2",
+            ]
+        );
+        assert_eq!(errors[1].kind(), &ValueErrorKind::InvalidCase(2));
+
+        let missing_field = WitValue {
+            nodes: vec![
+                WitNode::RecordValue(vec![1]),
+                WitNode::PrimString("carol".to_string()),
+            ],
+        };
+        let err = validate(&ValueAndType {
+            value: missing_field,
+            typ,
+        })
+        .unwrap_err();
+        assert_eq!(err.to_string(), "at permissions: missing value");
+        assert!(err.expected_type().is_some());
+    }
+}
@@ -0,0 +1,603 @@
+// Copyright 2024-2025 Golem Cloud
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{BuildHasher, Hash};
+use std::iter::Enumerate;
+use std::marker::PhantomData;
+use std::slice;
+
+use golem_wasm_rpc::{NodeIndex, WitNode, WitValue};
+
+use crate::value_and_type::{
+    generate_for_tuples, non_empty_unit_tuple, ValueError, ValueErrorKind, ValueKind,
+};
+
+/// Pointer to a node of a `WitValue`, giving access to the parts of the value for the lifetime
+/// of the `WitValue`, unlike `WitValueExtractor`
+#[derive(Debug, Clone, Copy)]
+pub struct ValueRef<'a> {
+    value: &'a WitValue,
+    idx: usize,
+}
+
+impl<'a> ValueRef<'a> {
+    /// Points to the root node of the value
+    pub fn new(value: &'a WitValue) -> Self {
+        Self { value, idx: 0 }
+    }
+
+    fn node(&self) -> Option<&'a WitNode> {
+        self.value.nodes.get(self.idx)
+    }
+
+    fn child(&self, idx: NodeIndex) -> Self {
+        Self {
+            value: self.value,
+            idx: idx as usize,
+        }
+    }
+
+    /// Kind of the node, `None` if the pointer does not point to a node
+    pub fn kind(&self) -> Option<ValueKind> {
+        self.node().map(ValueKind::of_node)
+    }
+
+    pub fn bool(&self) -> Option<bool> {
+        match self.node()? {
+            WitNode::PrimBool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn u8(&self) -> Option<u8> {
+        match self.node()? {
+            WitNode::PrimU8(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn u16(&self) -> Option<u16> {
+        match self.node()? {
+            WitNode::PrimU16(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn u32(&self) -> Option<u32> {
+        match self.node()? {
+            WitNode::PrimU32(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn u64(&self) -> Option<u64> {
+        match self.node()? {
+            WitNode::PrimU64(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn s8(&self) -> Option<i8> {
+        match self.node()? {
+            WitNode::PrimS8(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn s16(&self) -> Option<i16> {
+        match self.node()? {
+            WitNode::PrimS16(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn s32(&self) -> Option<i32> {
+        match self.node()? {
+            WitNode::PrimS32(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn s64(&self) -> Option<i64> {
+        match self.node()? {
+            WitNode::PrimS64(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn f32(&self) -> Option<f32> {
+        match self.node()? {
+            WitNode::PrimFloat32(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn f64(&self) -> Option<f64> {
+        match self.node()? {
+            WitNode::PrimFloat64(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn char(&self) -> Option<char> {
+        match self.node()? {
+            WitNode::PrimChar(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn string(&self) -> Option<&'a str> {
+        match self.node()? {
+            WitNode::PrimString(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn field(&self, field_idx: usize) -> Option<ValueRef<'a>> {
+        match self.node()? {
+            WitNode::RecordValue(fields) => fields.get(field_idx).map(|idx| self.child(*idx)),
+            _ => None,
+        }
+    }
+
+    pub fn variant(&self) -> Option<(u32, Option<ValueRef<'a>>)> {
+        match self.node()? {
+            WitNode::VariantValue((case_idx, case_value)) => {
+                Some((*case_idx, case_value.map(|idx| self.child(idx))))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn enum_value(&self) -> Option<u32> {
+        match self.node()? {
+            WitNode::EnumValue(case_idx) => Some(*case_idx),
+            _ => None,
+        }
+    }
+
+    pub fn flags(&self) -> Option<&'a [bool]> {
+        match self.node()? {
+            WitNode::FlagsValue(flags) => Some(flags),
+            _ => None,
+        }
+    }
+
+    pub fn tuple_element(&self, element_idx: usize) -> Option<ValueRef<'a>> {
+        match self.node()? {
+            WitNode::TupleValue(items) => items.get(element_idx).map(|idx| self.child(*idx)),
+            _ => None,
+        }
+    }
+
+    pub fn list(&self) -> Option<ListRef<'a>> {
+        match self.node()? {
+            WitNode::ListValue(items) => Some(ListRef {
+                value: self.value,
+                items,
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn option(&self) -> Option<Option<ValueRef<'a>>> {
+        match self.node()? {
+            WitNode::OptionValue(inner) => Some(inner.map(|idx| self.child(idx))),
+            _ => None,
+        }
+    }
+
+    #[allow(clippy::type_complexity)]
+    pub fn result(&self) -> Option<Result<Option<ValueRef<'a>>, Option<ValueRef<'a>>>> {
+        match self.node()? {
+            WitNode::ResultValue(result) => Some(match result {
+                Ok(ok) => Ok(ok.map(|idx| self.child(idx))),
+                Err(err) => Err(err.map(|idx| self.child(idx))),
+            }),
+            _ => None,
+        }
+    }
+
+    /// The URI of the worker owning the resource, and the resource's handle value
+    pub fn handle(&self) -> Option<(&'a str, u64)> {
+        match self.node()? {
+            WitNode::Handle((uri, value)) => Some((&uri.value, *value)),
+            _ => None,
+        }
+    }
+}
+
+/// List node of a `WitValue`, whose items are accessed without collecting them first
+#[derive(Debug, Clone, Copy)]
+pub struct ListRef<'a> {
+    value: &'a WitValue,
+    items: &'a [NodeIndex],
+}
+
+impl<'a> ListRef<'a> {
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn get(&self, idx: usize) -> Option<ValueRef<'a>> {
+        self.items.get(idx).map(|item| ValueRef {
+            value: self.value,
+            idx: *item as usize,
+        })
+    }
+
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = ValueRef<'a>> + 'a {
+        let value = self.value;
+        self.items.iter().map(move |item| ValueRef {
+            value,
+            idx: *item as usize,
+        })
+    }
+
+    /// Extracts the items of a `list<u8>`, reading the nodes directly into a vector allocated
+    /// with the length of the list
+    pub fn bytes(&self) -> Result<Vec<u8>, ValueError> {
+        let mut bytes = Vec::with_capacity(self.items.len());
+        for (idx, item) in self.items.iter().enumerate() {
+            match self.value.nodes.get(*item as usize) {
+                Some(WitNode::PrimU8(byte)) => bytes.push(*byte),
+                _ => {
+                    let item = ValueRef {
+                        value: self.value,
+                        idx: *item as usize,
+                    };
+                    return Err(unexpected(ValueKind::U8, item).at_index(idx));
+                }
+            }
+        }
+        Ok(bytes)
+    }
+
+    /// Iterates over the items extracted as `T`
+    pub fn iter_as<T: FromValueRef<'a>>(&self) -> ListIter<'a, T> {
+        ListIter {
+            value: self.value,
+            items: self.items.iter().enumerate(),
+            _item: PhantomData,
+        }
+    }
+}
+
+/// Iterator over the items of a list extracted as `T`, with errors pointing to the item
+pub struct ListIter<'a, T> {
+    value: &'a WitValue,
+    items: Enumerate<slice::Iter<'a, NodeIndex>>,
+    _item: PhantomData<T>,
+}
+
+impl<'a, T: FromValueRef<'a>> Iterator for ListIter<'a, T> {
+    type Item = Result<T, ValueError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (idx, item) = self.items.next()?;
+        let item = ValueRef {
+            value: self.value,
+            idx: *item as usize,
+        };
+        Some(T::from_value_ref(item).map_err(|err| err.at_index(idx)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.items.size_hint()
+    }
+}
+
+impl<'a, T: FromValueRef<'a>> ExactSizeIterator for ListIter<'a, T> {}
+
+/// Extraction of values borrowing from the `WitValue`, such as `&str` and lazy list iterators.
+///
+/// The counterpart of `FromValueAndType` for reading large values, like durable function
+/// results, without copying strings or collecting intermediate vectors. WIT byte lists are
+/// stored as one node per byte, so they cannot be borrowed as `&[u8]`, but `Vec<u8>` is
+/// extracted by reading the nodes of the list directly, like `ListRef::bytes`.
+pub trait FromValueRef<'a>: Sized {
+    fn from_value_ref(value: ValueRef<'a>) -> Result<Self, ValueError>;
+
+    /// Extracts the items of a list into a vector, overridden by `u8` to read the bytes directly
+    #[doc(hidden)]
+    fn vec_from_list(list: ListRef<'a>) -> Result<Vec<Self>, ValueError> {
+        let mut result = Vec::with_capacity(list.len());
+        for item in list.iter_as() {
+            result.push(item?);
+        }
+        Ok(result)
+    }
+
+    /// Extracts the value from the root node of the `WitValue`
+    fn from_wit_value(value: &'a WitValue) -> Result<Self, ValueError> {
+        Self::from_value_ref(ValueRef::new(value))
+    }
+
+    /// Constructs the value from a unit case of a `Result`, only supported by `()`
+    #[doc(hidden)]
+    fn from_unit_case() -> Option<Self> {
+        None
+    }
+}
+
+pub(crate) fn unexpected(expected: ValueKind, value: ValueRef<'_>) -> ValueError {
+    ValueError::new(ValueErrorKind::UnexpectedKind {
+        expected,
+        actual: value.kind(),
+    })
+}
+
+macro_rules! prim_value_ref {
+    ($ty:ty, $method:ident, $kind:ident) => {
+        impl<'a> FromValueRef<'a> for $ty {
+            fn from_value_ref(value: ValueRef<'a>) -> Result<Self, ValueError> {
+                value
+                    .$method()
+                    .ok_or_else(|| unexpected(ValueKind::$kind, value))
+            }
+        }
+    };
+}
+
+prim_value_ref!(bool, bool, Bool);
+prim_value_ref!(u16, u16, U16);
+prim_value_ref!(u32, u32, U32);
+prim_value_ref!(u64, u64, U64);
+prim_value_ref!(i8, s8, S8);
+prim_value_ref!(i16, s16, S16);
+prim_value_ref!(i32, s32, S32);
+prim_value_ref!(i64, s64, S64);
+prim_value_ref!(f32, f32, F32);
+prim_value_ref!(f64, f64, F64);
+prim_value_ref!(char, char, Char);
+prim_value_ref!(&'a str, string, String);
+prim_value_ref!(&'a [bool], flags, Flags);
+prim_value_ref!(ListRef<'a>, list, List);
+
+impl<'a> FromValueRef<'a> for u8 {
+    fn from_value_ref(value: ValueRef<'a>) -> Result<Self, ValueError> {
+        value.u8().ok_or_else(|| unexpected(ValueKind::U8, value))
+    }
+
+    fn vec_from_list(list: ListRef<'a>) -> Result<Vec<Self>, ValueError> {
+        list.bytes()
+    }
+}
+
+impl<'a> FromValueRef<'a> for ValueRef<'a> {
+    fn from_value_ref(value: ValueRef<'a>) -> Result<Self, ValueError> {
+        Ok(value)
+    }
+}
+
+impl<'a> FromValueRef<'a> for String {
+    fn from_value_ref(value: ValueRef<'a>) -> Result<Self, ValueError> {
+        <&str>::from_value_ref(value).map(|s| s.to_string())
+    }
+}
+
+impl<'a> FromValueRef<'a> for Cow<'a, str> {
+    fn from_value_ref(value: ValueRef<'a>) -> Result<Self, ValueError> {
+        <&str>::from_value_ref(value).map(Cow::Borrowed)
+    }
+}
+
+impl<'a, T: FromValueRef<'a>> FromValueRef<'a> for ListIter<'a, T> {
+    fn from_value_ref(value: ValueRef<'a>) -> Result<Self, ValueError> {
+        ListRef::from_value_ref(value).map(|list| list.iter_as())
+    }
+}
+
+impl<'a, T: FromValueRef<'a>> FromValueRef<'a> for Vec<T> {
+    fn from_value_ref(value: ValueRef<'a>) -> Result<Self, ValueError> {
+        T::vec_from_list(ListRef::from_value_ref(value)?)
+    }
+}
+
+impl<'a, K: FromValueRef<'a> + Eq + Hash, V: FromValueRef<'a>, S: BuildHasher + Default>
+    FromValueRef<'a> for HashMap<K, V, S>
+{
+    fn from_value_ref(value: ValueRef<'a>) -> Result<Self, ValueError> {
+        ListIter::<(K, V)>::from_value_ref(value)?.collect()
+    }
+}
+
+impl<'a, K: FromValueRef<'a> + Ord, V: FromValueRef<'a>> FromValueRef<'a> for BTreeMap<K, V> {
+    fn from_value_ref(value: ValueRef<'a>) -> Result<Self, ValueError> {
+        ListIter::<(K, V)>::from_value_ref(value)?.collect()
+    }
+}
+
+impl<'a, T: FromValueRef<'a>> FromValueRef<'a> for Option<T> {
+    fn from_value_ref(value: ValueRef<'a>) -> Result<Self, ValueError> {
+        match value.option() {
+            Some(Some(inner)) => T::from_value_ref(inner)
+                .map(Some)
+                .map_err(|err| err.at_case("some")),
+            Some(None) => Ok(None),
+            None => Err(unexpected(ValueKind::Option, value)),
+        }
+    }
+}
+
+impl<'a, S: FromValueRef<'a>, E: FromValueRef<'a>> FromValueRef<'a> for Result<S, E> {
+    fn from_value_ref(value: ValueRef<'a>) -> Result<Self, ValueError> {
+        match value.result() {
+            Some(Ok(Some(ok))) => S::from_value_ref(ok)
+                .map(Ok)
+                .map_err(|err| err.at_case("ok")),
+            Some(Ok(None)) => S::from_unit_case()
+                .map(Ok)
+                .ok_or_else(|| ValueError::missing_case_value("ok")),
+            Some(Err(Some(err))) => E::from_value_ref(err)
+                .map(Err)
+                .map_err(|err| err.at_case("err")),
+            Some(Err(None)) => E::from_unit_case()
+                .map(Err)
+                .ok_or_else(|| ValueError::missing_case_value("err")),
+            None => Err(unexpected(ValueKind::Result, value)),
+        }
+    }
+}
+
+impl<'a, T: FromValueRef<'a>> FromValueRef<'a> for Box<T> {
+    fn from_value_ref(value: ValueRef<'a>) -> Result<Self, ValueError> {
+        T::from_value_ref(value).map(Box::new)
+    }
+}
+
+impl<'a> FromValueRef<'a> for () {
+    fn from_value_ref(value: ValueRef<'a>) -> Result<Self, ValueError> {
+        match value.node() {
+            Some(WitNode::TupleValue(items)) if items.is_empty() => Ok(()),
+            Some(WitNode::TupleValue(_)) => Err(non_empty_unit_tuple()),
+            _ => Err(unexpected(ValueKind::Tuple, value)),
+        }
+    }
+
+    fn from_unit_case() -> Option<Self> {
+        Some(())
+    }
+}
+
+macro_rules! tuple_value_ref {
+    ($($ty:ident),*) => {
+        impl<'a, $($ty: FromValueRef<'a>),*> FromValueRef<'a> for ($($ty,)*) {
+            #[allow(unused_assignments)]
+            fn from_value_ref(value: ValueRef<'a>) -> Result<Self, ValueError> {
+                if value.kind() != Some(ValueKind::Tuple) {
+                    return Err(unexpected(ValueKind::Tuple, value));
+                }
+                let mut idx = 0;
+                Ok(($(
+                    {
+                        let item = $ty::from_value_ref(
+                            value
+                                .tuple_element(idx)
+                                .ok_or_else(|| ValueError::new(ValueErrorKind::Missing).at_index(idx))?,
+                        )
+                        .map_err(|err| err.at_index(idx))?;
+                        idx += 1;
+                        item
+                    },
+                )*))
+            }
+        }
+    };
+}
+
+generate_for_tuples!(tuple_value_ref);
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+    use std::collections::HashMap;
+
+    use golem_wasm_rpc::WitValue;
+
+    use crate::value_and_type::{
+        FromValueRef, IntoValue, ListIter, ListRef, PathSegment, ValueErrorKind, ValueKind,
+    };
+
+    #[test]
+    fn borrowed_values() {
+        let value = (
+            "text".to_string(),
+            vec![1u8, 2, 3],
+            vec![Some("a".to_string()), None],
+            Ok::<(), u32>(()),
+        )
+            .into_value();
+
+        let (text, bytes, items, result) =
+            <(&str, Vec<u8>, ListRef, Result<(), u32>)>::from_wit_value(&value).unwrap();
+        assert_eq!(text, "text");
+        assert_eq!(bytes, vec![1, 2, 3]);
+        assert_eq!(bytes.capacity(), 3);
+        assert_eq!(items.len(), 2);
+        assert_eq!(
+            items
+                .iter_as::<Option<Cow<str>>>()
+                .collect::<Result<Vec<_>, _>>(),
+            Ok(vec![Some(Cow::Borrowed("a")), None])
+        );
+        assert_eq!(result, Ok(()));
+
+        let value = vec!["first".to_string()].into_value();
+        assert_eq!(first_item(&value), "first");
+    }
+
+    /// The extracted string outlives the pointers it was extracted through
+    fn first_item(value: &WitValue) -> &str {
+        let item = ListRef::from_wit_value(value).unwrap().get(0).unwrap();
+        <&str>::from_value_ref(item).unwrap()
+    }
+
+    #[test]
+    fn byte_lists() {
+        let value = vec![1u8, 2, 3].into_value();
+        let list = ListRef::from_wit_value(&value).unwrap();
+        assert_eq!(list.bytes(), Ok(vec![1, 2, 3]));
+        assert_eq!(Vec::<u8>::from_wit_value(&value), Ok(vec![1, 2, 3]));
+
+        let value = vec![1u16, 2].into_value();
+        let err = Vec::<u8>::from_wit_value(&value).unwrap_err();
+        assert_eq!(err.path(), &[PathSegment::Index(0)]);
+        assert_eq!(
+            err.kind(),
+            &ValueErrorKind::UnexpectedKind {
+                expected: ValueKind::U8,
+                actual: Some(ValueKind::U16)
+            }
+        );
+    }
+
+    #[test]
+    fn maps_and_errors() {
+        let map = HashMap::from([("a".to_string(), 1u32), ("b".to_string(), 2)]);
+        let value = map.into_value();
+        let extracted = HashMap::<&str, u32>::from_wit_value(&value).unwrap();
+        assert_eq!(extracted, HashMap::from([("a", 1), ("b", 2)]));
+
+        let value = vec![1u32, 2].into_value();
+        let mut items = ListIter::<u16>::from_wit_value(&value).unwrap();
+        assert_eq!(items.len(), 2);
+        let err = items.next().unwrap().unwrap_err();
+        assert_eq!(err.path(), &[PathSegment::Index(0)]);
+        assert_eq!(
+            err.kind(),
+            &ValueErrorKind::UnexpectedKind {
+                expected: ValueKind::U16,
+                actual: Some(ValueKind::U32)
+            }
+        );
+    }
+
+    #[test]
+    fn unit_requires_an_empty_tuple() {
+        assert_eq!(<()>::from_wit_value(&().into_value()), Ok(()));
+        assert_eq!(
+            Result::<(), String>::from_wit_value(&Ok::<(), String>(()).into_value()),
+            Ok(Ok(()))
+        );
+        assert!(<()>::from_wit_value(&(1u8,).into_value()).is_err());
+        assert!(<()>::from_wit_value(&1u8.into_value()).is_err());
+    }
+}
@@ -0,0 +1,709 @@
+// Copyright 2024-2025 Golem Cloud
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Printing and parsing values in the WebAssembly Value Encoding (WAVE).
+//!
+//! Values are printed as in the WAVE specification: records as `{name: value}` (`{:}` when
+//! empty), lists as `[..]`, tuples as `(..)`, flags as `{a, b}`, variant and enum cases by their
+//! name followed by the parenthesized value if any, options as `some(..)` and `none`, and results
+//! as `ok(..)`, `ok`, `err(..)` and `err`. Labels which are WAVE keywords are prefixed by `%`.
+//!
+//! Parsing is directed by the type, so the same text can be parsed as different types, for
+//! example `1` as any integer or float type. Record fields may be in any order, and fields of
+//! option types may be left out, meaning `none`. Printing and then parsing with the same type
+//! gives back the original value, with the exception of NaN payloads.
+//!
+//! Resource handles have no WAVE representation, so neither printing nor parsing supports them.
+
+use std::fmt::{Display, Formatter, Write};
+
+use golem_wasm_rpc::golem_rpc_0_2_x::types::ValueAndType;
+use golem_wasm_rpc::{NodeIndex, Value, WitType, WitTypeNode};
+
+use crate::value_and_type::{DynValue, FromValueAndType, IntoValue, IntoValueAndType, ValueError};
+
+/// Error of printing or parsing WAVE text
+#[derive(Debug, Clone, PartialEq)]
+pub enum WaveError {
+    /// The value cannot be printed, because it does not match its type or contains a handle,
+    /// or the parsed value cannot be converted to the requested Rust type
+    Value(ValueError),
+    /// The text is not a valid WAVE representation of a value of the type
+    Parse {
+        /// Byte offset in the text
+        offset: usize,
+        message: String,
+    },
+}
+
+impl Display for WaveError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WaveError::Value(err) => write!(f, "{err}"),
+            WaveError::Parse { offset, message } => write!(f, "at offset {offset}: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for WaveError {}
+
+impl From<ValueError> for WaveError {
+    fn from(err: ValueError) -> Self {
+        WaveError::Value(err)
+    }
+}
+
+/// Prints the value in WAVE
+pub fn print(value_and_type: &ValueAndType) -> Result<String, WaveError> {
+    let value = DynValue::from_value_and_type(value_and_type)?;
+    let mut result = String::new();
+    write_value(&mut result, &value)?;
+    Ok(result)
+}
+
+/// Parses WAVE text as a value of the given type
+pub fn parse(typ: &WitType, text: &str) -> Result<ValueAndType, WaveError> {
+    let mut parser = Parser { text, pos: 0, typ };
+    let value = parser.value(0)?;
+    if parser.peek().is_some() {
+        return Err(parser.error("unexpected text after the value"));
+    }
+    Ok(ValueAndType {
+        value: value.into(),
+        typ: typ.clone(),
+    })
+}
+
+/// Prints a Rust value in WAVE
+pub fn to_string<T: IntoValue>(value: T) -> Result<String, WaveError> {
+    print(&value.into_value_and_type())
+}
+
+/// Parses WAVE text as a Rust value
+pub fn from_str<T: IntoValue + FromValueAndType>(text: &str) -> Result<T, WaveError> {
+    let value_and_type = parse(&T::get_type(), text)?;
+    Ok(T::from_value_and_type(value_and_type)?)
+}
+
+const KEYWORDS: &[&str] = &["true", "false", "inf", "nan", "some", "none", "ok", "err"];
+
+fn write_value(out: &mut String, value: &DynValue) -> Result<(), WaveError> {
+    match value {
+        DynValue::Bool(value) => out.push_str(&value.to_string()),
+        DynValue::U8(value) => out.push_str(&value.to_string()),
+        DynValue::U16(value) => out.push_str(&value.to_string()),
+        DynValue::U32(value) => out.push_str(&value.to_string()),
+        DynValue::U64(value) => out.push_str(&value.to_string()),
+        DynValue::S8(value) => out.push_str(&value.to_string()),
+        DynValue::S16(value) => out.push_str(&value.to_string()),
+        DynValue::S32(value) => out.push_str(&value.to_string()),
+        DynValue::S64(value) => out.push_str(&value.to_string()),
+        DynValue::F32(value) => write_float(out, *value as f64, value.to_string()),
+        DynValue::F64(value) => write_float(out, *value, value.to_string()),
+        DynValue::Char(value) => {
+            out.push('\'');
+            write_escaped(out, *value);
+            out.push('\'');
+        }
+        DynValue::String(value) => {
+            out.push('"');
+            value.chars().for_each(|c| write_escaped(out, c));
+            out.push('"');
+        }
+        DynValue::Record(fields) if fields.is_empty() => out.push_str("{:}"),
+        DynValue::Record(fields) => {
+            out.push('{');
+            for (idx, (name, value)) in fields.iter().enumerate() {
+                if idx > 0 {
+                    out.push_str(", ");
+                }
+                write_label(out, name);
+                out.push_str(": ");
+                write_value(out, value)?;
+            }
+            out.push('}');
+        }
+        DynValue::Variant { case, value } => {
+            write_label(out, case);
+            write_case_value(out, value.as_deref())?;
+        }
+        DynValue::Enum(case) => write_label(out, case),
+        DynValue::Flags(flags) => {
+            out.push('{');
+            for (idx, flag) in flags.iter().enumerate() {
+                if idx > 0 {
+                    out.push_str(", ");
+                }
+                write_label(out, flag);
+            }
+            out.push('}');
+        }
+        DynValue::Tuple(items) => write_items(out, ('(', ')'), items)?,
+        DynValue::List(items) => write_items(out, ('[', ']'), items)?,
+        DynValue::Option(Some(value)) => {
+            out.push_str("some");
+            write_case_value(out, Some(value))?;
+        }
+        DynValue::Option(None) => out.push_str("none"),
+        DynValue::Result(Ok(value)) => {
+            out.push_str("ok");
+            write_case_value(out, value.as_deref())?;
+        }
+        DynValue::Result(Err(value)) => {
+            out.push_str("err");
+            write_case_value(out, value.as_deref())?;
+        }
+        DynValue::Handle { .. } => {
+            return Err(ValueError::custom("handles cannot be represented in WAVE").into())
+        }
+    }
+    Ok(())
+}
+
+fn write_float(out: &mut String, value: f64, printed: String) {
+    if value.is_nan() {
+        out.push_str("nan");
+    } else if value.is_infinite() {
+        out.push_str(if value > 0.0 { "inf" } else { "-inf" });
+    } else {
+        out.push_str(&printed);
+    }
+}
+
+fn write_escaped(out: &mut String, c: char) {
+    match c {
+        '\\' => out.push_str("\\\\"),
+        '"' => out.push_str("\\\""),
+        '\'' => out.push_str("\\'"),
+        '\t' => out.push_str("\\t"),
+        '\n' => out.push_str("\\n"),
+        '\r' => out.push_str("\\r"),
+        c if c.is_control() => {
+            let _ = write!(out, "\\u{{{:x}}}", c as u32);
+        }
+        c => out.push(c),
+    }
+}
+
+fn write_label(out: &mut String, label: &str) {
+    if KEYWORDS.contains(&label) {
+        out.push('%');
+    }
+    out.push_str(label);
+}
+
+fn write_case_value(out: &mut String, value: Option<&DynValue>) -> Result<(), WaveError> {
+    if let Some(value) = value {
+        out.push('(');
+        write_value(out, value)?;
+        out.push(')');
+    }
+    Ok(())
+}
+
+fn write_items(
+    out: &mut String,
+    (open, close): (char, char),
+    items: &[DynValue],
+) -> Result<(), WaveError> {
+    out.push(open);
+    for (idx, item) in items.iter().enumerate() {
+        if idx > 0 {
+            out.push_str(", ");
+        }
+        write_value(out, item)?;
+    }
+    out.push(close);
+    Ok(())
+}
+
+/// Recursive descent parser, directed by the type
+struct Parser<'t> {
+    text: &'t str,
+    pos: usize,
+    typ: &'t WitType,
+}
+
+impl<'t> Parser<'t> {
+    fn value(&mut self, node_idx: NodeIndex) -> Result<Value, WaveError> {
+        let node = self
+            .typ
+            .nodes
+            .get(node_idx as usize)
+            .ok_or_else(|| self.error(format!("type node {node_idx} does not exist")))?;
+        match &node.type_ {
+            WitTypeNode::PrimBoolType => match self.word()? {
+                "true" => Ok(Value::Bool(true)),
+                "false" => Ok(Value::Bool(false)),
+                _ => Err(self.error("expected bool")),
+            },
+            WitTypeNode::PrimU8Type => self.number("u8").map(Value::U8),
+            WitTypeNode::PrimU16Type => self.number("u16").map(Value::U16),
+            WitTypeNode::PrimU32Type => self.number("u32").map(Value::U32),
+            WitTypeNode::PrimU64Type => self.number("u64").map(Value::U64),
+            WitTypeNode::PrimS8Type => self.number("s8").map(Value::S8),
+            WitTypeNode::PrimS16Type => self.number("s16").map(Value::S16),
+            WitTypeNode::PrimS32Type => self.number("s32").map(Value::S32),
+            WitTypeNode::PrimS64Type => self.number("s64").map(Value::S64),
+            WitTypeNode::PrimF32Type => self.float("f32").map(Value::F32),
+            WitTypeNode::PrimF64Type => self.float("f64").map(Value::F64),
+            WitTypeNode::PrimCharType => {
+                let start = self.pos;
+                let value = self.quoted('\'')?;
+                let mut chars = value.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => Ok(Value::Char(c)),
+                    _ => Err(self.error_at(start, "expected a single character")),
+                }
+            }
+            WitTypeNode::PrimStringType => self.quoted('"').map(Value::String),
+            WitTypeNode::RecordType(fields) => {
+                self.expect('{')?;
+                let mut values = vec![None; fields.len()];
+                if self.eat(':') {
+                    self.expect('}')?;
+                } else {
+                    self.items('}', |parser| {
+                        let start = parser.pos;
+                        let label = parser.label()?;
+                        let field_idx = fields
+                            .iter()
+                            .position(|(name, _)| name == label)
+                            .ok_or_else(|| {
+                                parser.error_at(start, format!("unknown field {label}"))
+                            })?;
+                        if values[field_idx].is_some() {
+                            return Err(parser.error_at(start, format!("duplicate field {label}")));
+                        }
+                        parser.expect(':')?;
+                        values[field_idx] = Some(parser.value(fields[field_idx].1)?);
+                        Ok(())
+                    })?;
+                }
+                values
+                    .into_iter()
+                    .zip(fields)
+                    .map(|(value, (name, field_type))| match value {
+                        Some(value) => Ok(value),
+                        None if self.is_option(*field_type) => Ok(Value::Option(None)),
+                        None => Err(self.error(format!("missing field {name}"))),
+                    })
+                    .collect::<Result<_, _>>()
+                    .map(Value::Record)
+            }
+            WitTypeNode::VariantType(cases) => {
+                let start = self.pos;
+                let label = self.label()?;
+                let case_idx = cases
+                    .iter()
+                    .position(|(name, _)| name == label)
+                    .ok_or_else(|| self.error_at(start, format!("unknown case {label}")))?;
+                let case_value = self.case_value(cases[case_idx].1)?;
+                Ok(Value::Variant {
+                    case_idx: case_idx as u32,
+                    case_value,
+                })
+            }
+            WitTypeNode::EnumType(cases) => {
+                let start = self.pos;
+                let label = self.label()?;
+                cases
+                    .iter()
+                    .position(|name| name == label)
+                    .map(|case_idx| Value::Enum(case_idx as u32))
+                    .ok_or_else(|| self.error_at(start, format!("unknown case {label}")))
+            }
+            WitTypeNode::FlagsType(names) => {
+                self.expect('{')?;
+                let mut flags = vec![false; names.len()];
+                self.items('}', |parser| {
+                    let start = parser.pos;
+                    let label = parser.label()?;
+                    let flag_idx = names
+                        .iter()
+                        .position(|name| name == label)
+                        .ok_or_else(|| parser.error_at(start, format!("unknown flag {label}")))?;
+                    flags[flag_idx] = true;
+                    Ok(())
+                })?;
+                Ok(Value::Flags(flags))
+            }
+            WitTypeNode::TupleType(item_types) => {
+                self.expect('(')?;
+                let mut items = Vec::new();
+                self.items(')', |parser| {
+                    let item_type = item_types
+                        .get(items.len())
+                        .ok_or_else(|| parser.error("too many tuple elements"))?;
+                    items.push(parser.value(*item_type)?);
+                    Ok(())
+                })?;
+                if items.len() < item_types.len() {
+                    return Err(self.error("too few tuple elements"));
+                }
+                Ok(Value::Tuple(items))
+            }
+            WitTypeNode::ListType(item_type) => {
+                self.expect('[')?;
+                let mut items = Vec::new();
+                self.items(']', |parser| {
+                    items.push(parser.value(*item_type)?);
+                    Ok(())
+                })?;
+                Ok(Value::List(items))
+            }
+            WitTypeNode::OptionType(inner_type) => match self.word()? {
+                "some" => Ok(Value::Option(self.case_value(Some(*inner_type))?)),
+                "none" => Ok(Value::Option(None)),
+                _ => Err(self.error("expected some or none")),
+            },
+            WitTypeNode::ResultType((ok_type, err_type)) => match self.word()? {
+                "ok" => Ok(Value::Result(Ok(self.case_value(*ok_type)?))),
+                "err" => Ok(Value::Result(Err(self.case_value(*err_type)?))),
+                _ => Err(self.error("expected ok or err")),
+            },
+            WitTypeNode::HandleType(_) => Err(self.error("handles cannot be represented in WAVE")),
+        }
+    }
+
+    fn case_value(
+        &mut self,
+        case_type: Option<NodeIndex>,
+    ) -> Result<Option<Box<Value>>, WaveError> {
+        match case_type {
+            Some(case_type) => {
+                self.expect('(')?;
+                let value = self.value(case_type)?;
+                self.expect(')')?;
+                Ok(Some(Box::new(value)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn is_option(&self, node_idx: NodeIndex) -> bool {
+        matches!(
+            self.typ
+                .nodes
+                .get(node_idx as usize)
+                .map(|node| &node.type_),
+            Some(WitTypeNode::OptionType(_))
+        )
+    }
+
+    fn number<N: std::str::FromStr>(&mut self, name: &str) -> Result<N, WaveError> {
+        let start = self.pos;
+        self.word()?
+            .parse()
+            .map_err(|_| self.error_at(start, format!("expected {name}")))
+    }
+
+    fn float<F: std::str::FromStr + From<f32>>(&mut self, name: &str) -> Result<F, WaveError> {
+        let start = self.pos;
+        let word = self.word()?;
+        match word {
+            "nan" => Ok(F::from(f32::NAN)),
+            "inf" => Ok(F::from(f32::INFINITY)),
+            "-inf" => Ok(F::from(f32::NEG_INFINITY)),
+            _ if word
+                .trim_start_matches('-')
+                .starts_with(|c: char| c.is_ascii_digit()) =>
+            {
+                word.parse()
+                    .map_err(|_| self.error_at(start, format!("expected {name}")))
+            }
+            _ => Err(self.error_at(start, format!("expected {name}"))),
+        }
+    }
+
+    /// A string or character literal with the given quote, with escapes resolved
+    fn quoted(&mut self, quote: char) -> Result<String, WaveError> {
+        self.expect(quote)?;
+        let mut result = String::new();
+        loop {
+            let c = self
+                .next_char()
+                .ok_or_else(|| self.error("unterminated literal"))?;
+            match c {
+                c if c == quote => return Ok(result),
+                '\\' => {
+                    let start = self.pos - 1;
+                    let escaped = match self.next_char() {
+                        Some('\\') => '\\',
+                        Some('"') => '"',
+                        Some('\'') => '\'',
+                        Some('t') => '\t',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('u') => self.unicode_escape(start)?,
+                        _ => return Err(self.error_at(start, "invalid escape")),
+                    };
+                    result.push(escaped);
+                }
+                c => result.push(c),
+            }
+        }
+    }
+
+    /// The rest of a `\u{...}` escape
+    fn unicode_escape(&mut self, start: usize) -> Result<char, WaveError> {
+        let rest = &self.text[self.pos..];
+        let digits = rest
+            .strip_prefix('{')
+            .and_then(|rest| rest.split_once('}'))
+            .map(|(digits, _)| digits)
+            .ok_or_else(|| self.error_at(start, "invalid unicode escape"))?;
+        let c = u32::from_str_radix(digits, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or_else(|| self.error_at(start, "invalid unicode escape"))?;
+        self.pos += digits.len() + 2;
+        Ok(c)
+    }
+
+    /// Parses comma-separated items until the closing delimiter, allowing a trailing comma
+    fn items(
+        &mut self,
+        close: char,
+        mut item: impl FnMut(&mut Self) -> Result<(), WaveError>,
+    ) -> Result<(), WaveError> {
+        loop {
+            if self.eat(close) {
+                return Ok(());
+            }
+            item(self)?;
+            if !self.eat(',') {
+                return self.expect(close);
+            }
+        }
+    }
+
+    /// A label, optionally prefixed by `%`
+    fn label(&mut self) -> Result<&'t str, WaveError> {
+        self.peek();
+        if self.text[self.pos..].starts_with('%') {
+            self.pos += 1;
+        }
+        self.take_while(|c| c.is_alphanumeric() || c == '-' || c == '_')
+            .ok_or_else(|| self.error("expected a label"))
+    }
+
+    /// A keyword or number
+    fn word(&mut self) -> Result<&'t str, WaveError> {
+        self.peek();
+        self.take_while(|c| c.is_alphanumeric() || matches!(c, '-' | '+' | '.' | '_'))
+            .ok_or_else(|| self.error("expected a value"))
+    }
+
+    fn take_while(&mut self, f: impl Fn(char) -> bool) -> Option<&'t str> {
+        let rest = &self.text[self.pos..];
+        let len = rest.find(|c| !f(c)).unwrap_or(rest.len());
+        if len == 0 {
+            None
+        } else {
+            self.pos += len;
+            Some(&rest[..len])
+        }
+    }
+
+    /// The next character after whitespace, without consuming it
+    fn peek(&mut self) -> Option<char> {
+        let rest = &self.text[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+        self.text[self.pos..].chars().next()
+    }
+
+    fn next_char(&mut self) -> Option<char> {
+        let c = self.text[self.pos..].chars().next()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), WaveError> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected '{c}'")))
+        }
+    }
+
+    fn error(&self, message: impl Into<String>) -> WaveError {
+        self.error_at(self.pos, message)
+    }
+
+    fn error_at(&self, offset: usize, message: impl Into<String>) -> WaveError {
+        WaveError::Parse {
+            offset,
+            message: message.into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::value_and_type::wave::{from_str, parse, print, to_string, WaveError};
+    use crate::value_and_type::IntoValue;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn print_values() {
+        assert_eq!(
+            to_string((1u8, -2i64, 1.5f32, 'x', "a \"quoted\"\n".to_string())),
+            Ok(r#"(1, -2, 1.5, 'x', "a \"quoted\"\n")"#.to_string())
+        );
+        assert_eq!(
+            to_string(vec![
+                Some(Ok::<u8, String>(1)),
+                Some(Err("e".to_string())),
+                None
+            ]),
+            Ok(r#"[some(ok(1)), some(err("e")), none]"#.to_string())
+        );
+        assert_eq!(to_string(Err::<(), ()>(())), Ok("err".to_string()));
+        assert_eq!(
+            to_string((f64::NAN, f64::NEG_INFINITY)),
+            Ok("(nan, -inf)".to_string())
+        );
+    }
+
+    #[test]
+    fn roundtrip() {
+        let value = (
+            vec![(1u32, "one".to_string()), (2, "two".to_string())],
+            Some('\u{7}'),
+            Ok::<_, u8>(Some(-0.0f64)),
+            (u64::MAX, i8::MIN, f32::MIN_POSITIVE),
+        );
+        let text = to_string(value.clone()).unwrap();
+        assert_eq!(from_str(&text), Ok(value));
+
+        let map = BTreeMap::from([("a".to_string(), vec![true]), ("b".to_string(), vec![])]);
+        let text = to_string(map.clone()).unwrap();
+        assert_eq!(from_str(&text), Ok(map));
+    }
+
+    #[test]
+    fn parse_with_type() {
+        let typ = <(u16, Option<String>, Vec<f32>)>::get_type();
+        let result = parse(&typ, " ( 7 ,none, [1, 2.5e1, inf,] ) ").unwrap();
+        assert_eq!(
+            result.value,
+            (7u16, None::<String>, vec![1.0f32, 25.0, f32::INFINITY]).into_value()
+        );
+        assert_eq!(print(&result), Ok("(7, none, [1, 25, inf])".to_string()));
+
+        assert_eq!(
+            parse(&typ, "(70000, none, [])").unwrap_err(),
+            WaveError::Parse {
+                offset: 1,
+                message: "expected u16".to_string()
+            }
+        );
+        assert_eq!(
+            parse(&typ, "(1, none, []) x").unwrap_err(),
+            WaveError::Parse {
+                offset: 14,
+                message: "unexpected text after the value".to_string()
+            }
+        );
+        assert!(from_str::<char>("'ab'").is_err());
+        assert_eq!(
+            from_str::<String>(r#""\u{1f600}""#),
+            Ok("\u{1f600}".to_string())
+        );
+    }
+}
+
+#[cfg(all(test, feature = "macro"))]
+mod derive_tests {
+    use crate::value_and_type::wave::{from_str, to_string};
+    use crate::{FromValueAndType, IntoValue};
+
+    #[derive(Debug, Clone, PartialEq, IntoValue, FromValueAndType)]
+    #[wit(flags)]
+    struct Access {
+        read: bool,
+        write: bool,
+    }
+
+    #[derive(Debug, Clone, PartialEq, IntoValue, FromValueAndType)]
+    enum Color {
+        Red,
+        None,
+    }
+
+    #[derive(Debug, Clone, PartialEq, IntoValue, FromValueAndType)]
+    enum Shape {
+        Circle(f64),
+        Empty,
+        Ok,
+    }
+
+    #[derive(Debug, Clone, PartialEq, IntoValue, FromValueAndType)]
+    struct Item {
+        name: String,
+        access: Access,
+        color: Color,
+        shapes: Vec<Shape>,
+        note: Option<String>,
+    }
+
+    #[derive(Debug, Clone, PartialEq, IntoValue, FromValueAndType)]
+    struct Empty {}
+
+    #[test]
+    fn named_types() {
+        let item = Item {
+            name: "item".to_string(),
+            access: Access {
+                read: true,
+                write: false,
+            },
+            color: Color::None,
+            shapes: vec![Shape::Circle(1.5), Shape::Empty, Shape::Ok],
+            note: None,
+        };
+        let text = to_string(item.clone()).unwrap();
+        assert_eq!(
+            text,
+            r#"{name: "item", access: {read}, color: %none, shapes: [circle(1.5), empty, %ok], note: none}"#
+        );
+        assert_eq!(from_str(&text), Ok(item.clone()));
+        assert_eq!(
+            from_str(r#"{shapes: [], color: red, access: {write, read}, name: "x"}"#),
+            Ok(Item {
+                name: "x".to_string(),
+                access: Access {
+                    read: true,
+                    write: true,
+                },
+                color: Color::Red,
+                shapes: vec![],
+                note: None,
+            })
+        );
+
+        assert_eq!(to_string(Empty {}), Ok("{:}".to_string()));
+        assert_eq!(from_str("{:}"), Ok(Empty {}));
+    }
+}
@@ -0,0 +1,521 @@
+// Copyright 2024-2025 Golem Cloud
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Rendering types as WIT source.
+//!
+//! Records, variants, enums and flags become definitions named after their type nodes, and
+//! resource handles become `resource` definitions. Other named nodes, such as named tuples,
+//! become `type` aliases. Everything else is rendered inline, for example `list<user>` or
+//! `result<_, string>`. Names are converted to kebab-case, as WIT requires, and the ones which
+//! are WIT keywords are prefixed by `%`.
+//!
+//! WIT requires all definitions to be named, so unnamed records, variants, enums, flags and
+//! resources are named after their kind and node index, for example `record-3`. Different types
+//! sharing a name, such as the instantiations of a generic type, get a numeric suffix, while
+//! identical definitions are only rendered once.
+//!
+//! The definitions of recursive types are rendered as well, even though WIT itself does not
+//! support them.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+use golem_wasm_rpc::{NodeIndex, ResourceMode, WitType, WitTypeNode};
+use heck::ToKebabCase;
+
+/// Definition of a named type in WIT source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WitDefinition {
+    /// Name of the definition, the type node's name in kebab-case, with a suffix when multiple
+    /// types share it
+    pub name: String,
+    /// Owner of the type node, for example `"my:component/api"`
+    pub owner: Option<String>,
+    /// WIT source of the definition, for example `enum color { red, green }` on multiple lines
+    pub source: String,
+}
+
+/// The definitions of the named types used by the type, with dependencies before their users
+pub fn definitions(typ: &WitType) -> Vec<WitDefinition> {
+    let mut renderer = Renderer::new(typ);
+    renderer.reference(0);
+    renderer.finish()
+}
+
+/// The type as used in WIT source, for example `list<user>`, referring to the names of
+/// `definitions`
+pub fn type_reference(typ: &WitType) -> String {
+    Renderer::new(typ).reference(0)
+}
+
+/// The definitions of the named types used by the type, separated by empty lines, followed by
+/// a `type` alias of the type itself if it is not one of the definitions
+pub fn render(typ: &WitType, alias: &str) -> String {
+    let mut renderer = Renderer::new(typ);
+    let reference = renderer.reference(0);
+    let is_defined = renderer.names.get(&0).map(|name| ident(name)) == Some(reference.clone());
+    let mut sources = renderer
+        .finish()
+        .into_iter()
+        .map(|definition| definition.source)
+        .collect::<Vec<_>>();
+    if !is_defined {
+        sources.push(format!("type {} = {reference};", ident(alias)));
+    }
+    sources.join("\n\n")
+}
+
+const KEYWORDS: &[&str] = &[
+    "as",
+    "async",
+    "bool",
+    "borrow",
+    "char",
+    "constructor",
+    "enum",
+    "error-context",
+    "export",
+    "f32",
+    "f64",
+    "flags",
+    "func",
+    "future",
+    "import",
+    "include",
+    "interface",
+    "list",
+    "option",
+    "own",
+    "package",
+    "record",
+    "resource",
+    "result",
+    "s16",
+    "s32",
+    "s64",
+    "s8",
+    "static",
+    "stream",
+    "string",
+    "tuple",
+    "type",
+    "u16",
+    "u32",
+    "u64",
+    "u8",
+    "use",
+    "variant",
+    "with",
+    "world",
+];
+
+fn ident(name: &str) -> String {
+    let name = name.to_kebab_case();
+    if KEYWORDS.contains(&name.as_str()) {
+        format!("%{name}")
+    } else {
+        name
+    }
+}
+
+struct Definition {
+    name: String,
+    /// The type node's name, or the generated one
+    base_name: String,
+    owner: Option<String>,
+    /// The source after the name, compared to find identical definitions
+    keyword: &'static str,
+    body: String,
+}
+
+struct Renderer<'t> {
+    typ: &'t WitType,
+    /// Names of the nodes rendered as definitions
+    names: HashMap<NodeIndex, String>,
+    used_names: HashSet<String>,
+    definitions: Vec<Definition>,
+    /// Nodes whose definitions are being rendered
+    in_progress: HashSet<NodeIndex>,
+    /// Nodes referenced while their definitions were being rendered
+    recursive: HashSet<NodeIndex>,
+}
+
+impl<'t> Renderer<'t> {
+    fn new(typ: &'t WitType) -> Self {
+        Self {
+            typ,
+            names: HashMap::new(),
+            used_names: HashSet::new(),
+            definitions: Vec::new(),
+            in_progress: HashSet::new(),
+            recursive: HashSet::new(),
+        }
+    }
+
+    fn finish(self) -> Vec<WitDefinition> {
+        self.definitions
+            .into_iter()
+            .map(|definition| WitDefinition {
+                source: format!(
+                    "{} {}{}",
+                    definition.keyword,
+                    ident(&definition.name),
+                    definition.body
+                ),
+                name: definition.name,
+                owner: definition.owner,
+            })
+            .collect()
+    }
+
+    fn reference(&mut self, idx: NodeIndex) -> String {
+        let node = &self.typ.nodes[idx as usize];
+        match &node.type_ {
+            WitTypeNode::RecordType(_)
+            | WitTypeNode::VariantType(_)
+            | WitTypeNode::EnumType(_)
+            | WitTypeNode::FlagsType(_) => ident(&self.define(idx)),
+            WitTypeNode::HandleType((_, ResourceMode::Owned)) => ident(&self.define(idx)),
+            WitTypeNode::HandleType((_, ResourceMode::Borrowed)) => {
+                format!("borrow<{}>", ident(&self.define(idx)))
+            }
+            _ if node.name.is_some() => ident(&self.define(idx)),
+            _ => self.inline(idx),
+        }
+    }
+
+    fn inline(&mut self, idx: NodeIndex) -> String {
+        match &self.typ.nodes[idx as usize].type_ {
+            WitTypeNode::PrimBoolType => "bool".to_string(),
+            WitTypeNode::PrimU8Type => "u8".to_string(),
+            WitTypeNode::PrimU16Type => "u16".to_string(),
+            WitTypeNode::PrimU32Type => "u32".to_string(),
+            WitTypeNode::PrimU64Type => "u64".to_string(),
+            WitTypeNode::PrimS8Type => "s8".to_string(),
+            WitTypeNode::PrimS16Type => "s16".to_string(),
+            WitTypeNode::PrimS32Type => "s32".to_string(),
+            WitTypeNode::PrimS64Type => "s64".to_string(),
+            WitTypeNode::PrimF32Type => "f32".to_string(),
+            WitTypeNode::PrimF64Type => "f64".to_string(),
+            WitTypeNode::PrimCharType => "char".to_string(),
+            WitTypeNode::PrimStringType => "string".to_string(),
+            WitTypeNode::ListType(item) => format!("list<{}>", self.reference(*item)),
+            WitTypeNode::OptionType(inner) => format!("option<{}>", self.reference(*inner)),
+            WitTypeNode::TupleType(items) => {
+                let items = items
+                    .iter()
+                    .map(|item| self.reference(*item))
+                    .collect::<Vec<_>>();
+                format!("tuple<{}>", items.join(", "))
+            }
+            WitTypeNode::ResultType((ok, err)) => match (ok, err) {
+                (None, None) => "result".to_string(),
+                (Some(ok), None) => format!("result<{}>", self.reference(*ok)),
+                (None, Some(err)) => format!("result<_, {}>", self.reference(*err)),
+                (Some(ok), Some(err)) => {
+                    let ok = self.reference(*ok);
+                    format!("result<{ok}, {}>", self.reference(*err))
+                }
+            },
+            // Rendered as definitions
+            WitTypeNode::RecordType(_)
+            | WitTypeNode::VariantType(_)
+            | WitTypeNode::EnumType(_)
+            | WitTypeNode::FlagsType(_)
+            | WitTypeNode::HandleType(_) => self.reference(idx),
+        }
+    }
+
+    /// Renders the definition of the node if not yet rendered, and returns its name
+    fn define(&mut self, idx: NodeIndex) -> String {
+        if let Some(name) = self.names.get(&idx) {
+            if self.in_progress.contains(&idx) {
+                self.recursive.insert(idx);
+            }
+            return name.clone();
+        }
+
+        let node = &self.typ.nodes[idx as usize];
+        let kind = match &node.type_ {
+            WitTypeNode::RecordType(_) => "record",
+            WitTypeNode::VariantType(_) => "variant",
+            WitTypeNode::EnumType(_) => "enum",
+            WitTypeNode::FlagsType(_) => "flags",
+            WitTypeNode::HandleType(_) => "resource",
+            _ => "type",
+        };
+        let base_name = node
+            .name
+            .as_deref()
+            .map(|name| name.to_kebab_case())
+            .unwrap_or_else(|| format!("{kind}-{idx}"));
+        let name = (1..)
+            .map(|n| match n {
+                1 => base_name.clone(),
+                n => format!("{base_name}-{n}"),
+            })
+            .find(|name| !self.used_names.contains(name))
+            .expect("unused name");
+        self.used_names.insert(name.clone());
+        self.names.insert(idx, name.clone());
+
+        self.in_progress.insert(idx);
+        let body = self.body(idx);
+        self.in_progress.remove(&idx);
+
+        let owner = node.owner.clone();
+        if !self.recursive.contains(&idx) {
+            if let Some(existing) = self.definitions.iter().find(|definition| {
+                definition.base_name == base_name
+                    && definition.owner == owner
+                    && definition.keyword == kind
+                    && definition.body == body
+            }) {
+                let existing = existing.name.clone();
+                self.used_names.remove(&name);
+                self.names.insert(idx, existing.clone());
+                return existing;
+            }
+        }
+
+        self.definitions.push(Definition {
+            name: name.clone(),
+            base_name,
+            owner,
+            keyword: kind,
+            body,
+        });
+        name
+    }
+
+    /// The source of the definition following its name
+    fn body(&mut self, idx: NodeIndex) -> String {
+        let mut body = String::new();
+        match &self.typ.nodes[idx as usize].type_ {
+            WitTypeNode::RecordType(fields) => {
+                body.push_str(" {\n");
+                for (name, field) in fields {
+                    let field = self.reference(*field);
+                    let _ = writeln!(body, "    {}: {field},", ident(name));
+                }
+                body.push('}');
+            }
+            WitTypeNode::VariantType(cases) => {
+                body.push_str(" {\n");
+                for (name, case) in cases {
+                    match case {
+                        Some(case) => {
+                            let case = self.reference(*case);
+                            let _ = writeln!(body, "    {}({case}),", ident(name));
+                        }
+                        None => {
+                            let _ = writeln!(body, "    {},", ident(name));
+                        }
+                    }
+                }
+                body.push('}');
+            }
+            WitTypeNode::EnumType(names) | WitTypeNode::FlagsType(names) => {
+                body.push_str(" {\n");
+                for name in names {
+                    let _ = writeln!(body, "    {},", ident(name));
+                }
+                body.push('}');
+            }
+            WitTypeNode::HandleType(_) => body.push(';'),
+            _ => {
+                let _ = write!(body, " = {};", self.inline(idx));
+            }
+        }
+        body
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::value_and_type::test_utils::Counter;
+    use crate::value_and_type::type_builder::WitTypeBuilderExtensions;
+    use crate::value_and_type::wit::{definitions, render, type_reference};
+    use crate::value_and_type::{BorrowedHandle, Handle, IntoValue, TypeNodeBuilder};
+    use golem_wasm_rpc::WitType;
+
+    #[test]
+    fn inline_types() {
+        assert_eq!(type_reference(&u8::get_type()), "u8");
+        assert_eq!(
+            type_reference(&<(Vec<Option<String>>, char)>::get_type()),
+            "tuple<list<option<string>>, char>"
+        );
+        assert_eq!(
+            type_reference(&Result::<(), String>::get_type()),
+            "result<_, string>"
+        );
+        assert_eq!(
+            type_reference(&Result::<u32, ()>::get_type()),
+            "result<u32>"
+        );
+        assert_eq!(render(&u8::get_type(), "byte"), "type byte = u8;");
+    }
+
+    #[test]
+    fn handles() {
+        let typ = <(Handle<Counter>, BorrowedHandle<Counter>)>::get_type();
+        assert_eq!(type_reference(&typ), "tuple<counter, borrow<counter>>");
+        assert_eq!(
+            render(&typ, "pair"),
+            "resource counter;\n\ntype pair = tuple<counter, borrow<counter>>;"
+        );
+    }
+
+    #[test]
+    fn unnamed_definitions() {
+        let typ = WitType::builder()
+            .list(None, None)
+            .r#enum(Some("type".to_string()), None, &["list", "a"])
+            .build();
+        assert_eq!(type_reference(&typ), "list<%type>");
+        assert_eq!(
+            definitions(&typ)[0].source,
+            "enum %type {\n    %list,\n    a,\n}"
+        );
+
+        let typ = WitType::builder().flags(None, None, &["x"]);
+        assert_eq!(render(&typ, "unused"), "flags flags-0 {\n    x,\n}");
+    }
+}
+
+#[cfg(all(test, feature = "macro"))]
+mod derive_tests {
+    use crate::value_and_type::wit::{definitions, render, type_reference, WitDefinition};
+    use crate::value_and_type::IntoValue;
+    use crate::{FromValueAndType, IntoValue};
+
+    #[derive(IntoValue, FromValueAndType)]
+    #[wit(flags)]
+    struct Access {
+        read: bool,
+        write: bool,
+    }
+
+    #[derive(IntoValue, FromValueAndType)]
+    #[wit(owner = "app:users/api")]
+    enum Status {
+        Active,
+        Blocked,
+    }
+
+    #[derive(IntoValue, FromValueAndType)]
+    enum Contact {
+        Email(String),
+        Postal {
+            street: String,
+            city: String,
+        },
+        #[wit(type_name = "phone-number")]
+        Phone(u16, String),
+        Unknown,
+    }
+
+    #[derive(IntoValue, FromValueAndType)]
+    struct Wrapper<T> {
+        inner: T,
+    }
+
+    #[derive(IntoValue, FromValueAndType)]
+    struct User {
+        name: String,
+        access: Access,
+        status: Status,
+        contacts: Vec<Contact>,
+        previous: Option<Contact>,
+        id: Wrapper<u64>,
+        tag: Wrapper<String>,
+        other_tag: Wrapper<String>,
+    }
+
+    #[derive(IntoValue, FromValueAndType)]
+    struct Tree {
+        children: Vec<Tree>,
+    }
+
+    #[test]
+    fn named_types() {
+        let typ = User::get_type();
+        assert_eq!(type_reference(&typ), "user");
+        assert_eq!(
+            render(&typ, "unused"),
+            "\
+flags access {
+    read,
+    write,
+}
+
+enum status {
+    active,
+    blocked,
+}
+
+record contact-postal {
+    street: string,
+    city: string,
+}
+
+type phone-number = tuple<u16, string>;
+
+variant contact {
+    email(string),
+    postal(contact-postal),
+    phone(phone-number),
+    unknown,
+}
+
+record wrapper {
+    inner: u64,
+}
+
+record wrapper-2 {
+    inner: string,
+}
+
+record user {
+    name: string,
+    access: access,
+    status: status,
+    contacts: list<contact>,
+    previous: option<contact>,
+    id: wrapper,
+    tag: wrapper-2,
+    other-tag: wrapper-2,
+}"
+        );
+
+        let status = definitions(&typ).into_iter().nth(1);
+        assert_eq!(
+            status.map(|definition| definition.owner),
+            Some(Some("app:users/api".to_string()))
+        );
+    }
+
+    #[test]
+    fn recursive_types() {
+        assert_eq!(
+            definitions(&Tree::get_type()),
+            vec![WitDefinition {
+                name: "tree".to_string(),
+                owner: None,
+                source: "record tree {\n    children: list<tree>,\n}".to_string(),
+            }]
+        );
+    }
+}